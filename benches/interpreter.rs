@@ -0,0 +1,265 @@
+// Times the interpreter on a loop heavy and a call heavy program, and on
+// examples/primes.tt, next to a reference interpreter that keeps each call's
+// variables in HashMaps the way frames were stored before they were
+// slot-indexed Vecs.
+// Run with: cargo bench
+
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+use rustcompiler::interpreter::{Program, Vm};
+use rustcompiler::phases::ir::{BinaryOp, Instruction, Module, Operand};
+use rustcompiler::phases::lexer::lex;
+use rustcompiler::phases::semantic_parser::parse_program_ir;
+
+// sieve of eratosthenes, counting the primes below 200000.
+const SIEVE: &str = "
+func main() -> int {
+    int[200000] crossed;
+    int count;
+    int i;
+    int j;
+    i = 2;
+    while i < 200000 {
+        if crossed[i] == 0 {
+            count = count + 1;
+            j = i + i;
+            while j < 200000 {
+                crossed[j] = 1;
+                j = j + i;
+            }
+        }
+        i = i + 1;
+    }
+    return count;
+}
+";
+
+// naive recursive fibonacci.
+const FIB: &str = "
+func fib(int n) -> int {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+func main() -> int {
+    return fib(24);
+}
+";
+
+const PRIMES: &str = include_str!("../examples/primes.tt");
+
+// the reference interpreter. like the old loader it turns variable names into
+// ids once, but every read and write looks the id up in the call's HashMap.
+mod reference {
+    use super::*;
+
+    enum Value {
+        Var(usize),
+        Num(i64),
+    }
+
+    enum Op {
+        Declare(usize),
+        DeclareArray(usize, usize),
+        Call(usize, usize, Vec<Value>),
+        Return(Value),
+        Mov(usize, Value),
+        Load(usize, usize, Value),
+        Store(usize, Value, Value),
+        Binary(BinaryOp, usize, Value, Value),
+        Jump(usize),
+        BranchIf(Value, usize),
+        BranchIfn(Value, usize),
+        // labels, and output, which goes nowhere in the benchmark.
+        Nothing,
+    }
+
+    struct Function {
+        parameters: Vec<usize>,
+        body: Vec<Op>,
+    }
+
+    pub struct Program {
+        functions: Vec<Function>,
+        main: usize,
+    }
+
+    struct Frame {
+        variables: HashMap<usize, i64>,
+        arrays: HashMap<usize, Vec<i64>>,
+    }
+
+    impl Frame {
+        fn value(&self, value: &Value) -> i64 {
+            match value {
+            Value::Var(id) => self.variables[id],
+            Value::Num(num) => *num,
+            }
+        }
+    }
+
+    // the ids of one function's variables, given out as the names come up.
+    #[derive(Default)]
+    struct Names(HashMap<String, usize>);
+
+    impl Names {
+        fn id(&mut self, name: &str) -> usize {
+            let next = self.0.len();
+            *self.0.entry(String::from(name)).or_insert(next)
+        }
+
+        fn value(&mut self, operand: &Operand) -> Value {
+            match operand {
+            Operand::Var(name) => Value::Var(self.id(name)),
+            Operand::Num(num) => Value::Num(*num),
+            }
+        }
+    }
+
+    pub fn load(module: &Module) -> Program {
+        let function_ids: HashMap<&str, usize> = module.functions.iter().enumerate().map(|(id, f)| (f.name.as_str(), id)).collect();
+        let mut functions = vec![];
+        for function in &module.functions {
+            let mut labels: HashMap<&str, usize> = HashMap::new();
+            for (index, instruction) in function.body.iter().enumerate() {
+                if let Instruction::Label(label) = instruction {
+                    labels.insert(&label.0, index);
+                }
+            }
+            let mut names = Names::default();
+            let parameters = function.parameters.iter().map(|(_, name)| names.id(name)).collect();
+            let body = function.body.iter().map(|instruction| match instruction {
+            Instruction::Declare(_, name) => Op::Declare(names.id(name)),
+            Instruction::DeclareArray(_, name, len) => Op::DeclareArray(names.id(name), *len as usize),
+            Instruction::Call(dest, name, arguments) => {
+                let arguments = arguments.iter().map(|argument| names.value(argument)).collect();
+                Op::Call(names.id(dest), function_ids[name.as_str()], arguments)
+            }
+            Instruction::Return(operand) => Op::Return(names.value(operand)),
+            Instruction::Mov(dest, operand) => Op::Mov(names.id(dest), names.value(operand)),
+            Instruction::Load(dest, array, index) => Op::Load(names.id(dest), names.id(array), names.value(index)),
+            Instruction::Store(array, index, operand) => Op::Store(names.id(array), names.value(index), names.value(operand)),
+            Instruction::Binary(op, dest, a, b) => Op::Binary(*op, names.id(dest), names.value(a), names.value(b)),
+            Instruction::Jump(label) => Op::Jump(labels[label.0.as_str()]),
+            Instruction::BranchIf(condition, label) => Op::BranchIf(names.value(condition), labels[label.0.as_str()]),
+            Instruction::BranchIfn(condition, label) => Op::BranchIfn(names.value(condition), labels[label.0.as_str()]),
+            Instruction::In(_) | Instruction::InChar(_) => panic!("the benchmarks do not read input"),
+            _ => Op::Nothing,
+            }).collect();
+            functions.push(Function { parameters, body });
+        }
+        Program { functions, main: function_ids["main"] }
+    }
+
+    impl Program {
+        pub fn run(&self) -> i64 {
+            self.call(self.main, vec![])
+        }
+
+        fn call(&self, id: usize, arguments: Vec<i64>) -> i64 {
+            let function = &self.functions[id];
+            let mut frame = Frame { variables: HashMap::new(), arrays: HashMap::new() };
+            for (parameter, argument) in function.parameters.iter().zip(arguments) {
+                frame.variables.insert(*parameter, argument);
+            }
+            let mut pc = 0;
+            while pc < function.body.len() {
+                match &function.body[pc] {
+                Op::Declare(id) => {
+                    frame.variables.entry(*id).or_insert(0);
+                }
+                Op::DeclareArray(id, len) => {
+                    frame.arrays.entry(*id).or_insert_with(|| vec![0; *len]);
+                }
+                Op::Call(dest, callee, arguments) => {
+                    let arguments = arguments.iter().map(|argument| frame.value(argument)).collect();
+                    let result = self.call(*callee, arguments);
+                    frame.variables.insert(*dest, result);
+                }
+                Op::Return(value) => return frame.value(value),
+                Op::Mov(dest, value) => {
+                    let value = frame.value(value);
+                    frame.variables.insert(*dest, value);
+                }
+                Op::Load(dest, array, index) => {
+                    let value = frame.arrays[array][frame.value(index) as usize];
+                    frame.variables.insert(*dest, value);
+                }
+                Op::Store(array, index, value) => {
+                    let (index, value) = (frame.value(index) as usize, frame.value(value));
+                    frame.arrays.get_mut(array).unwrap()[index] = value;
+                }
+                Op::Binary(op, dest, a, b) => {
+                    let (a, b) = (frame.value(a), frame.value(b));
+                    let value = match op {
+                    BinaryOp::Add => a.wrapping_add(b),
+                    BinaryOp::Sub => a.wrapping_sub(b),
+                    BinaryOp::Mult => a.wrapping_mul(b),
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Mod => a % b,
+                    BinaryOp::LessThan => (a < b) as i64,
+                    BinaryOp::LessEqual => (a <= b) as i64,
+                    BinaryOp::NotEqual => (a != b) as i64,
+                    BinaryOp::Equal => (a == b) as i64,
+                    BinaryOp::GreaterEqual => (a >= b) as i64,
+                    BinaryOp::GreaterThan => (a > b) as i64,
+                    };
+                    frame.variables.insert(*dest, value);
+                }
+                Op::Jump(target) => {
+                    pc = *target;
+                    continue;
+                }
+                Op::BranchIf(condition, target) if frame.value(condition) != 0 => {
+                    pc = *target;
+                    continue;
+                }
+                Op::BranchIfn(condition, target) if frame.value(condition) == 0 => {
+                    pc = *target;
+                    continue;
+                }
+                _ => {}
+                }
+                pc += 1;
+            }
+            0
+        }
+    }
+}
+
+fn compile(code: &str) -> Module {
+    let tokens = lex(code).unwrap();
+    parse_program_ir(&tokens, &mut 0).unwrap()
+}
+
+// best of a few runs, so one slow run does not skew the result.
+fn best_of(mut run: impl FnMut() -> i64, expected: i64) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        let result = run();
+        best = best.min(start.elapsed());
+        assert_eq!(result, expected);
+    }
+    best
+}
+
+fn bench(name: &str, code: &str, expected: i64) {
+    let module = compile(code);
+    let program = Program::load(&module).unwrap();
+    let reference = reference::load(&module);
+    let before = best_of(|| reference.run(), expected);
+    let after = best_of(|| program.run(&mut Vm::with_io(io::empty(), io::sink())).unwrap(), expected);
+    println!("{:<8} {:>10.2?} {:>10.2?} {:>7.1}x", name, before, after, before.as_secs_f64() / after.as_secs_f64());
+}
+
+fn main() {
+    println!("{:<8} {:>10} {:>10} {:>8}", "", "HashMap", "slots", "speedup");
+    bench("sieve", SIEVE, 17984);
+    bench("fib", FIB, 46368);
+    bench("primes", PRIMES, 0);
+}
@@ -1,20 +1,40 @@
 #!/bin/bash
 
-# If no files are specified, use all .tt files in the examples directory
+# If no files are specified, use all .tt and .ir files in the examples directory
 if [ "$#" -eq 0 ]; then
-    files=($(ls examples/*.tt))
+    files=($(ls examples/*.tt examples/*.ir))
 else
     files=("$@")
 fi
 
+# exit codes from the compiler, see the README. main's return value is the
+# exit code when it fits in 0-255 and is not one of the codes below.
+describe() {
+    case $1 in
+        2) echo "could not be read" ;;
+        3) echo "failed to lex" ;;
+        4) echo "failed to parse" ;;
+        5) echo "failed semantic analysis" ;;
+        6) echo "produced invalid IR" ;;
+        7) echo "stopped with a runtime error" ;;
+        8) echo "ran, main returned a value printed above" ;;
+        *) echo "ran, main returned $1" ;;
+    esac
+}
+
 # Loop through all specified files
+failed=0
 for file in "${files[@]}"; do
     echo "Running compiler on $file"
-    cargo run -- "$file"
-    if [ $? -eq 0 ]; then
-        echo "$file compiled successfully"
+    cargo run -q -- "$file"
+    status=$?
+    if [ $status -eq 0 ]; then
+        echo "$file compiled and ran successfully"
     else
-        echo "Error compiling $file"
+        echo "$file $(describe $status) (exit code $status)"
+        failed=1
     fi
     echo "-----------------------------------"
 done
+
+exit $failed
@@ -0,0 +1,432 @@
+// src/backend/c.rs
+// Translates the IR into a single C99 source file that any C compiler can
+// build, for running Teh Terik programs where there is no native backend and
+// for checking the interpreter against a compiled program:
+//
+// let source = c::generate(&module);
+// fs::write("program.c", source)?;
+//
+// Every variable becomes an int64_t local, with ints kept to 32 bits on every
+// write, and arrays are allocated with calloc when the function is called,
+// like the interpreter does. Labels and branches become labels and gotos.
+// Arithmetic is done on unsigned numbers so that overflow wraps instead of
+// being undefined. The runtime at the top does the input and output with
+// printf and getchar, and reports runtime errors on standard error with the
+// interpreter's messages before exiting with 7. %input reads whole lines
+// rather than using scanf, which cannot tell a bad number apart from the
+// start of the next one.
+//
+// The module has to have loaded with Program::load first, which checks
+// everything generate relies on, like every variable being declared once.
+
+use std::collections::HashMap;
+
+use crate::phases::ir::{BinaryOp, Function, Instruction, Module, Operand, Type};
+
+struct Generator<'m> {
+    out: String,
+    // the index and length of each string constant.
+    strings: HashMap<&'m str, (usize, usize)>,
+}
+
+// what generate knows about a variable.
+#[derive(Debug, Clone, Copy)]
+struct Variable {
+    long: bool,
+    // the number of elements, for arrays.
+    length: Option<i64>,
+}
+
+// the C source for a whole module, runtime included.
+pub fn generate(module: &Module) -> String {
+    let mut generator = Generator { out: String::new(), strings: HashMap::new() };
+    generator.out += "/* generated by the Teh Terik compiler */\n";
+    generator.out += RUNTIME;
+
+    if !module.strings.is_empty() {
+        generator.out += "\n";
+    }
+    for (i, (name, text)) in module.strings.iter().enumerate() {
+        generator.out += &format!("static const char tt_str{i}[] = \"{}\";\n", escape(text));
+        generator.strings.insert(name, (i, text.len()));
+    }
+
+    // functions can be called before they are defined.
+    generator.out += "\n";
+    for function in &module.functions {
+        generator.out += &format!("{};\n", signature(function));
+    }
+    for function in &module.functions {
+        generator.function(function);
+    }
+
+    // what run would say when there is no main to call.
+    generator.out += "\nint main(void) {\n";
+    match module.functions.iter().find(|function| function.name == "main") {
+    Some(main) if main.parameters.is_empty() => generator.code(String::from("return (unsigned char)(uint64_t)f_main();")),
+    Some(main) => {
+        let e = format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got 0 parameters\\n", main.parameters.len());
+        generator.code(format!("tt_fail(\"{e}\");"));
+        generator.code(String::from("return 7;"));
+    }
+    None => {
+        generator.code(String::from("tt_fail(\"Runtime Error. No main function declared.\\n\");"));
+        generator.code(String::from("return 7;"));
+    }
+    }
+    generator.out += "}\n";
+    generator.out
+}
+
+// names in hand-written IR can have any characters but whitespace, so
+// anything C would not take is written as '_' and its hex, and '_' as "__".
+fn mangle(name: &str) -> String {
+    let mut mangled = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() {
+            mangled.push(byte as char);
+        } else if byte == b'_' {
+            mangled += "__";
+        } else {
+            mangled += &format!("_{byte:02x}");
+        }
+    }
+    mangled
+}
+
+// the text of a string constant inside "...". '?' is escaped too, so no
+// trigraph can turn up.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for byte in text.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'"' && byte != b'\\' && byte != b'?' {
+            escaped.push(byte as char);
+        } else {
+            escaped += &format!("\\{byte:03o}");
+        }
+    }
+    escaped
+}
+
+fn signature(function: &Function) -> String {
+    let parameters: Vec<String> = function.parameters.iter().map(|(_, name)| format!("int64_t v_{}", mangle(name))).collect();
+    let parameters = if parameters.is_empty() { String::from("void") } else { parameters.join(", ") };
+    format!("static int64_t f_{}({parameters})", mangle(&function.name))
+}
+
+// the most negative number has no literal of its own in C.
+fn number(num: i64) -> String {
+    if num == i64::MIN {
+        return String::from("(-INT64_C(9223372036854775807) - 1)");
+    }
+    if num == num as i32 as i64 {
+        return num.to_string();
+    }
+    format!("INT64_C({num})")
+}
+
+fn operand(value: &Operand) -> String {
+    match value {
+    Operand::Num(num) => number(*num),
+    Operand::Var(name) => format!("v_{}", mangle(name)),
+    }
+}
+
+fn label(name: &str) -> String {
+    format!("l_{}", mangle(name))
+}
+
+impl<'m> Generator<'m> {
+    fn code(&mut self, text: String) {
+        self.out += "    ";
+        self.out += &text;
+        self.out += "\n";
+    }
+
+    // writes value to a variable, cut down to 32 bits for an int.
+    fn assign(&mut self, variables: &HashMap<&str, Variable>, dest: &str, value: String) {
+        if variables[dest].long {
+            self.code(format!("v_{} = {value};", mangle(dest)));
+        } else {
+            self.code(format!("v_{} = tt_int({value});", mangle(dest)));
+        }
+    }
+
+    fn function(&mut self, function: &'m Function) {
+        let mut variables: HashMap<&str, Variable> = HashMap::new();
+        let mut declared: Vec<&str> = vec![];
+        for (typ, name) in &function.parameters {
+            variables.insert(name, Variable { long: *typ == Type::Long, length: None });
+        }
+        for instruction in &function.body {
+            let (typ, name, length) = match instruction {
+            Instruction::Declare(typ, name) => (typ, name, None),
+            Instruction::DeclareArray(typ, name, length) => (typ, name, Some(*length)),
+            _ => continue,
+            };
+            variables.insert(name, Variable { long: *typ == Type::Long, length });
+            declared.push(name);
+        }
+        let arrays: Vec<&str> = declared.iter().copied().filter(|name| variables[name].length.is_some()).collect();
+
+        self.out += &format!("\n/* %func {} */\n", function.name.replace("*/", "* /"));
+        self.out += &format!("{} {{\n", signature(function));
+        // every variable starts out as 0, even if a goto skips its declaration.
+        for name in &declared {
+            match variables[name].length {
+            None => self.code(format!("int64_t v_{} = 0;", mangle(name))),
+            Some(length) => self.code(format!("int64_t *v_{} = tt_array({});", mangle(name), number(length))),
+            }
+        }
+        if !arrays.is_empty() {
+            self.code(String::from("int64_t result = 0;"));
+        }
+        for (typ, name) in &function.parameters {
+            if *typ == Type::Int {
+                self.code(format!("v_{0} = tt_int(v_{0});", mangle(name)));
+            }
+        }
+
+        for instruction in &function.body {
+            self.instruction(&variables, !arrays.is_empty(), instruction);
+        }
+
+        // falling off the end returns 0.
+        if arrays.is_empty() {
+            self.code(String::from("return 0;"));
+        } else {
+            self.out += "tt_return:\n";
+            for name in &arrays {
+                self.code(format!("free(v_{});", mangle(name)));
+            }
+            self.code(String::from("return result;"));
+        }
+        self.out += "}\n";
+    }
+
+    // with arrays to free, returns go through the end of the function.
+    fn instruction(&mut self, variables: &HashMap<&str, Variable>, arrays: bool, instruction: &Instruction) {
+        match instruction {
+        Instruction::Declare(_, name) => self.code(format!("v_{} = 0;", mangle(name))),
+
+        Instruction::DeclareArray(_, name, length) => {
+            self.code(format!("memset(v_{}, 0, (size_t){} * sizeof(int64_t));", mangle(name), number(*length)));
+        }
+
+        Instruction::Call(dest, function, arguments) => {
+            let arguments: Vec<String> = arguments.iter().map(operand).collect();
+            self.assign(variables, dest, format!("f_{}({})", mangle(function), arguments.join(", ")));
+        }
+
+        Instruction::Return(value) => {
+            if arrays {
+                self.code(format!("result = {};", operand(value)));
+                self.code(String::from("goto tt_return;"));
+            } else {
+                self.code(format!("return {};", operand(value)));
+            }
+        }
+
+        // printf takes anything after the format as it is, so numbers need their type.
+        Instruction::Out(value) => self.code(format!("printf(\"%\" PRId64 \"\\n\", (int64_t){});", operand(value))),
+
+        Instruction::OutStr(name) => {
+            let (index, length) = self.strings[name.as_str()];
+            self.code(format!("fwrite(tt_str{index}, 1, {length}, stdout);"));
+        }
+
+        Instruction::OutInt(value) => self.code(format!("printf(\"%\" PRId64, (int64_t){});", operand(value))),
+        Instruction::OutLine => self.code(String::from("putchar('\\n');")),
+        Instruction::In(dest) => self.code(format!("v_{} = tt_input({});", mangle(dest), variables[dest.as_str()].long as i32)),
+        Instruction::OutChar(value) => self.code(format!("putchar((unsigned char)(uint64_t){});", operand(value))),
+        Instruction::InChar(dest) => self.assign(variables, dest, String::from("tt_inputc()")),
+        Instruction::Mov(dest, src) => self.assign(variables, dest, operand(src)),
+
+        Instruction::Load(dest, array, element) => {
+            let length = variables[array.as_str()].length.unwrap_or(0);
+            let value = format!("tt_load(v_{}, {}, {})", mangle(array), number(length), operand(element));
+            self.assign(variables, dest, value);
+        }
+
+        Instruction::Store(array, element, src) => {
+            let Variable { long, length } = variables[array.as_str()];
+            let value = if long { operand(src) } else { format!("tt_int({})", operand(src)) };
+            self.code(format!("tt_store(v_{}, {}, {}, {value});", mangle(array), number(length.unwrap_or(0)), operand(element)));
+        }
+
+        Instruction::Binary(op, dest, src1, src2) => {
+            let (a, b) = (operand(src1), operand(src2));
+            // ints do their arithmetic on 32 bits, like the interpreter.
+            let long = variables[dest.as_str()].long;
+            let (a, b) = match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mult | BinaryOp::Div | BinaryOp::Mod if !long => (format!("tt_int({a})"), format!("tt_int({b})")),
+            _ => (a, b),
+            };
+            let value = match op {
+            BinaryOp::Add => format!("tt_wrap((uint64_t){a} + (uint64_t){b})"),
+            BinaryOp::Sub => format!("tt_wrap((uint64_t){a} - (uint64_t){b})"),
+            BinaryOp::Mult => format!("tt_wrap((uint64_t){a} * (uint64_t){b})"),
+            BinaryOp::Div => format!("tt_div({a}, {b})"),
+            BinaryOp::Mod => format!("tt_mod({a}, {b})"),
+            BinaryOp::LessThan => format!("{a} < {b}"),
+            BinaryOp::LessEqual => format!("{a} <= {b}"),
+            BinaryOp::NotEqual => format!("{a} != {b}"),
+            BinaryOp::Equal => format!("{a} == {b}"),
+            BinaryOp::GreaterEqual => format!("{a} >= {b}"),
+            BinaryOp::GreaterThan => format!("{a} > {b}"),
+            };
+            self.assign(variables, dest, value);
+        }
+
+        // the empty statement lets a label end the function.
+        Instruction::Label(name) => self.out += &format!("{}:;\n", label(&name.0)),
+        Instruction::Jump(name) => self.code(format!("goto {};", label(&name.0))),
+        Instruction::BranchIf(condition, name) => self.code(format!("if (tt_branch({})) goto {};", operand(condition), label(&name.0))),
+        Instruction::BranchIfn(condition, name) => self.code(format!("if (!tt_branch({})) goto {};", operand(condition), label(&name.0))),
+        }
+    }
+}
+
+// the same for every program.
+const RUNTIME: &str = r#"#include <ctype.h>
+#include <errno.h>
+#include <inttypes.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* prints a runtime error and exits with 7, like run does. */
+static void tt_fail(const char *message) {
+    fputs(message, stderr);
+    exit(7);
+}
+
+/* turns the bits of an unsigned number back into a signed one without
+   relying on implementation defined conversions. */
+static int64_t tt_wrap(uint64_t num) {
+    if (num <= (uint64_t)INT64_MAX) {
+        return (int64_t)num;
+    }
+    return -(int64_t)(~num) - 1;
+}
+
+/* the low 32 bits, sign extended, which is what an int keeps. */
+static int64_t tt_int(int64_t num) {
+    uint64_t low = (uint64_t)num & UINT64_C(0xffffffff);
+    if (low >= UINT64_C(0x80000000)) {
+        return (int64_t)low - INT64_C(0x100000000);
+    }
+    return (int64_t)low;
+}
+
+/* the most negative number divided by -1 wraps back to itself. */
+static int64_t tt_div(int64_t a, int64_t b) {
+    if (b == 0) {
+        tt_fail("Runtime Error. Attempt to divide by zero.\n");
+    }
+    if (b == -1) {
+        return tt_wrap(0 - (uint64_t)a);
+    }
+    return a / b;
+}
+
+static int64_t tt_mod(int64_t a, int64_t b) {
+    if (b == 0) {
+        tt_fail("Runtime Error. Attempt to divide by zero.\n");
+    }
+    if (b == -1) {
+        return 0;
+    }
+    return a % b;
+}
+
+static int tt_branch(int64_t condition) {
+    if (condition != 0 && condition != 1) {
+        fprintf(stderr, "Runtime Error. Branch on a variable that is neither 0 or 1. The value is: %" PRId64 "\n", condition);
+        exit(7);
+    }
+    return condition == 1;
+}
+
+static int64_t *tt_array(int64_t length) {
+    int64_t *array = calloc((size_t)length, sizeof(int64_t));
+    if (array == NULL && length > 0) {
+        tt_fail("Runtime Error. Out of memory.\n");
+    }
+    return array;
+}
+
+static int64_t tt_load(const int64_t *array, int64_t length, int64_t index) {
+    if (index < 0 || index >= length) {
+        fprintf(stderr, "Runtime Error: Array out of bounds. Index %" PRId64 ". Array Length %" PRId64 ".\n", index, length);
+        exit(7);
+    }
+    return array[index];
+}
+
+static void tt_store(int64_t *array, int64_t length, int64_t index, int64_t value) {
+    if (index < 0 || index >= length) {
+        fprintf(stderr, "Runtime Error: Array out of bounds. Value %" PRId64 ". Array Length %" PRId64 "\n", index, length);
+        exit(7);
+    }
+    array[index] = value;
+}
+
+/* %inputc: the next byte, or -1 at the end of the input. */
+static int64_t tt_inputc(void) {
+    int c;
+    fflush(stdout);
+    c = getchar();
+    return c == EOF ? -1 : c;
+}
+
+/* %input: reads lines until one is a number that fits, 64 bits if long is 1
+   and 32 bits otherwise. */
+static int64_t tt_input(int long_) {
+    size_t capacity = 64;
+    char *line = malloc(capacity);
+    if (line == NULL) {
+        tt_fail("Runtime Error. Out of memory.\n");
+    }
+    fflush(stdout);
+    for (;;) {
+        size_t length = 0;
+        int c;
+        char *end;
+        int64_t num;
+        while ((c = getchar()) != EOF) {
+            if (length + 1 == capacity) {
+                char *bigger = realloc(line, capacity * 2);
+                if (bigger == NULL) {
+                    tt_fail("Runtime Error. Out of memory.\n");
+                }
+                line = bigger;
+                capacity *= 2;
+            }
+            line[length++] = (char)c;
+            if (c == '\n') {
+                break;
+            }
+        }
+        if (length == 0) {
+            tt_fail("Runtime Error. Reached the end of the input while reading a number for '%input'.\n");
+        }
+        /* drop the newline and any other space at the end. */
+        while (length > 0 && isspace((unsigned char)line[length - 1])) {
+            length--;
+        }
+        line[length] = '\0';
+        /* strtoll would skip spaces at the start, so only a sign or a digit can start a number. */
+        if (line[0] == '+' || line[0] == '-' || isdigit((unsigned char)line[0])) {
+            errno = 0;
+            num = strtoll(line, &end, 10);
+            if (*end == '\0' && errno == 0 && (long_ || num == tt_int(num))) {
+                free(line);
+                return num;
+            }
+        }
+        printf("User Input Error. '%s' is not a valid number.\n", line);
+    }
+}
+"#;
@@ -0,0 +1,699 @@
+// src/backend/llvm.rs
+// Lowers the IR to LLVM IR in its text form, so llc or clang can optimise it
+// and build it for any target LLVM knows:
+//
+// let text = llvm::generate(&module);
+// fs::write("program.ll", text)?;
+//
+// It uses opaque pointers, which LLVM 15 and later read as they are, and
+// LLVM 14 with -opaque-pointers. Every variable gets an alloca in its
+// function's entry block, which mem2reg turns back into registers, with ints
+// kept sign extended from 32 bits. Arrays are allocated with calloc when the
+// function is called, like the interpreter does, and their elements are
+// reached with getelementptr after checking the index. The runtime at the end
+// does the input and output with printf, putchar and getchar, and reports
+// runtime errors on standard error with the interpreter's messages before
+// exiting with 7. %input reads whole lines rather than using scanf, which
+// cannot tell a bad number apart from the start of the next one.
+//
+// The module has to have loaded with Program::load first, which checks
+// everything generate relies on, like every label being declared.
+
+use std::collections::HashMap;
+
+use crate::phases::ir::{BinaryOp, Function, Instruction, Module, Operand, Type};
+
+// what generate knows about a variable.
+#[derive(Debug, Clone, Copy)]
+struct Variable {
+    long: bool,
+    // the number of elements, for arrays.
+    length: Option<i64>,
+}
+
+struct Generator<'m> {
+    out: String,
+    // the number of the next temporary, %t0, %t1 and so on.
+    next: usize,
+    // the index and length of each string constant.
+    strings: HashMap<&'m str, (usize, usize)>,
+    variables: HashMap<&'m str, Variable>,
+    // whether the last instruction written ends a basic block, so anything
+    // after it needs a new one.
+    terminated: bool,
+}
+
+// the LLVM IR for a whole module, runtime included.
+pub fn generate(module: &Module) -> String {
+    let mut generator = Generator { out: String::new(), next: 0, strings: HashMap::new(), variables: HashMap::new(), terminated: false };
+    generator.out += "; generated by the Teh Terik compiler\n";
+
+    if !module.strings.is_empty() {
+        generator.out += "\n";
+    }
+    for (i, (name, text)) in module.strings.iter().enumerate() {
+        generator.out += &format!("@str.{i} = private unnamed_addr constant [{} x i8] c\"{}\"\n", text.len(), escape(text.as_bytes()));
+        generator.strings.insert(name, (i, text.len()));
+    }
+
+    for function in &module.functions {
+        generator.function(function);
+    }
+
+    // the exit code is the low byte, like in run.
+    let main = module.functions.iter().find(|function| function.name == "main");
+    let error = match main {
+    Some(main) if main.parameters.is_empty() => None,
+    Some(main) => Some(format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got 0 parameters\n", main.parameters.len())),
+    None => Some(String::from("Runtime Error. No main function declared.\n")),
+    };
+    match error {
+    None => {
+        generator.out += "\ndefine i32 @main() {\n";
+        generator.code(String::from("%result = call i64 @\"f.main\"()"));
+        generator.code(String::from("%byte = trunc i64 %result to i8"));
+        generator.code(String::from("%code = zext i8 %byte to i32"));
+        generator.code(String::from("ret i32 %code"));
+    }
+    // what run would say when there is no main to call.
+    Some(e) => {
+        generator.out += &format!("\n@main.error = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n", e.len() + 1, escape(e.as_bytes()));
+        generator.out += "\ndefine i32 @main() {\n";
+        generator.code(String::from("call void @tt_fail(ptr @main.error)"));
+        generator.code(String::from("unreachable"));
+    }
+    }
+    generator.out += "}\n";
+    generator.out += RUNTIME;
+    generator.out
+}
+
+// the text inside "..." of a quoted name or a c"..." constant.
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for byte in bytes {
+        if (0x20..0x7f).contains(byte) && *byte != b'"' && *byte != b'\\' {
+            escaped.push(*byte as char);
+        } else {
+            escaped += &format!("\\{byte:02X}");
+        }
+    }
+    escaped
+}
+
+// names are quoted, so they can have any characters hand-written IR can.
+fn variable(name: &str) -> String {
+    format!("%\"v.{}\"", escape(name.as_bytes()))
+}
+
+fn label(name: &str) -> String {
+    format!("\"l.{}\"", escape(name.as_bytes()))
+}
+
+fn function_name(name: &str) -> String {
+    format!("@\"f.{}\"", escape(name.as_bytes()))
+}
+
+impl<'m> Generator<'m> {
+    fn code(&mut self, text: String) {
+        self.out += "  ";
+        self.out += &text;
+        self.out += "\n";
+    }
+
+    fn temporary(&mut self) -> String {
+        self.next += 1;
+        format!("%t{}", self.next - 1)
+    }
+
+    // starts a basic block when the last one has ended, for code after a jump
+    // that nothing jumps to.
+    fn reachable(&mut self) {
+        if self.terminated {
+            let block = self.temporary();
+            self.out += &format!("{}:\n", &block[1..]);
+            self.terminated = false;
+        }
+    }
+
+    // the operand as an i64 value, loading a variable first.
+    fn operand(&mut self, value: &Operand) -> String {
+        match value {
+        Operand::Num(num) => num.to_string(),
+        Operand::Var(name) => {
+            let t = self.temporary();
+            self.code(format!("{t} = load i64, ptr {}", variable(name)));
+            t
+        }
+        }
+    }
+
+    // the low 32 bits of the value, sign extended.
+    fn truncate(&mut self, value: &str) -> String {
+        if let Ok(num) = value.parse::<i64>() {
+            return (num as i32 as i64).to_string();
+        }
+        let (narrow, wide) = (self.temporary(), self.temporary());
+        self.code(format!("{narrow} = trunc i64 {value} to i32"));
+        self.code(format!("{wide} = sext i32 {narrow} to i64"));
+        wide
+    }
+
+    // writes the value to a variable, cut down to 32 bits for an int.
+    fn store(&mut self, dest: &str, value: &str) {
+        let value = if self.variables[dest].long { String::from(value) } else { self.truncate(value) };
+        self.code(format!("store i64 {value}, ptr {}", variable(dest)));
+    }
+
+    fn function(&mut self, function: &'m Function) {
+        self.variables.clear();
+        self.next = 0;
+        self.terminated = false;
+        let mut declared: Vec<&str> = vec![];
+        for (typ, name) in &function.parameters {
+            self.variables.insert(name, Variable { long: *typ == Type::Long, length: None });
+        }
+        for instruction in &function.body {
+            let (typ, name, length) = match instruction {
+            Instruction::Declare(typ, name) => (typ, name, None),
+            Instruction::DeclareArray(typ, name, length) => (typ, name, Some(*length)),
+            _ => continue,
+            };
+            self.variables.insert(name, Variable { long: *typ == Type::Long, length });
+            declared.push(name);
+        }
+        let arrays: Vec<&str> = declared.iter().copied().filter(|name| self.variables[name].length.is_some()).collect();
+
+        let parameters: Vec<String> = function.parameters.iter().map(|(_, name)| format!("i64 %\"p.{}\"", escape(name.as_bytes()))).collect();
+        self.out += &format!("\n; %func {}\n", function.name);
+        self.out += &format!("define internal i64 {}({}) {{\n", function_name(&function.name), parameters.join(", "));
+        self.out += "entry:\n";
+        self.code(String::from("%result = alloca i64"));
+        for (_, name) in &function.parameters {
+            self.code(format!("{} = alloca i64", variable(name)));
+            self.store(name, &format!("%\"p.{}\"", escape(name.as_bytes())));
+        }
+        // every variable starts out as 0, even if a jump skips its declaration.
+        // an array's variable holds the address of its elements.
+        for name in &declared {
+            match self.variables[name].length {
+            None => {
+                self.code(format!("{} = alloca i64", variable(name)));
+                self.code(format!("store i64 0, ptr {}", variable(name)));
+            }
+            Some(length) => self.code(format!("{} = call ptr @tt_array(i64 {length})", variable(name))),
+            }
+        }
+
+        for instruction in &function.body {
+            self.instruction(instruction);
+        }
+
+        // falling off the end returns 0.
+        if !self.terminated {
+            self.code(String::from("store i64 0, ptr %result"));
+            self.code(String::from("br label %return"));
+        }
+        self.out += "return:\n";
+        for name in &arrays {
+            self.code(format!("call void @free(ptr {})", variable(name)));
+        }
+        let t = self.temporary();
+        self.code(format!("{t} = load i64, ptr %result"));
+        self.code(format!("ret i64 {t}"));
+        self.out += "}\n";
+    }
+
+    // the address of an element, after checking the index.
+    fn element(&mut self, array: &str, element: &Operand, check: &str) -> String {
+        let index = self.operand(element);
+        self.code(format!("call void {check}(i64 {index}, i64 {})", self.variables[array].length.unwrap_or(0)));
+        let t = self.temporary();
+        self.code(format!("{t} = getelementptr inbounds i64, ptr {}, i64 {index}", variable(array)));
+        t
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        if let Instruction::Label(name) = instruction {
+            // falling into a label is a jump to it too.
+            if !self.terminated {
+                self.code(format!("br label %{}", label(&name.0)));
+            }
+            self.out += &format!("{}:\n", label(&name.0));
+            self.terminated = false;
+            return;
+        }
+        self.reachable();
+        self.code(format!("; {instruction}"));
+        match instruction {
+        Instruction::Declare(_, name) => self.code(format!("store i64 0, ptr {}", variable(name))),
+
+        Instruction::DeclareArray(_, name, length) => {
+            let t = self.temporary();
+            self.code(format!("{t} = call ptr @memset(ptr {}, i32 0, i64 {})", variable(name), length * 8));
+        }
+
+        Instruction::Call(dest, function, arguments) => {
+            let arguments: Vec<String> = arguments.iter().map(|argument| format!("i64 {}", self.operand(argument))).collect();
+            let t = self.temporary();
+            self.code(format!("{t} = call i64 {}({})", function_name(function), arguments.join(", ")));
+            self.store(dest, &t);
+        }
+
+        Instruction::Return(value) => {
+            let value = self.operand(value);
+            self.code(format!("store i64 {value}, ptr %result"));
+            self.code(String::from("br label %return"));
+            self.terminated = true;
+        }
+
+        Instruction::Out(value) => {
+            let value = self.operand(value);
+            self.code(format!("call void @tt_out(i64 {value})"));
+        }
+
+        Instruction::OutStr(name) => {
+            let (index, length) = self.strings[name.as_str()];
+            self.code(format!("call void @tt_out_str(ptr @str.{index}, i64 {length})"));
+        }
+
+        Instruction::OutInt(value) => {
+            let value = self.operand(value);
+            self.code(format!("call void @tt_out_int(i64 {value})"));
+        }
+
+        Instruction::OutLine => {
+            let t = self.temporary();
+            self.code(format!("{t} = call i32 @putchar(i32 10)"));
+        }
+
+        Instruction::In(dest) => {
+            let t = self.temporary();
+            self.code(format!("{t} = call i64 @tt_input(i1 {})", self.variables[dest.as_str()].long));
+            self.code(format!("store i64 {t}, ptr {}", variable(dest)));
+        }
+
+        Instruction::OutChar(value) => {
+            let value = self.operand(value);
+            self.code(format!("call void @tt_outc(i64 {value})"));
+        }
+
+        Instruction::InChar(dest) => {
+            let t = self.temporary();
+            self.code(format!("{t} = call i64 @tt_inputc()"));
+            self.store(dest, &t);
+        }
+
+        Instruction::Mov(dest, src) => {
+            let value = self.operand(src);
+            self.store(dest, &value);
+        }
+
+        Instruction::Load(dest, array, element) => {
+            let address = self.element(array, element, "@tt_check_read");
+            let t = self.temporary();
+            self.code(format!("{t} = load i64, ptr {address}"));
+            self.store(dest, &t);
+        }
+
+        Instruction::Store(array, element, src) => {
+            let address = self.element(array, element, "@tt_check_write");
+            let mut value = self.operand(src);
+            if !self.variables[array.as_str()].long {
+                value = self.truncate(&value);
+            }
+            self.code(format!("store i64 {value}, ptr {address}"));
+        }
+
+        Instruction::Binary(op, dest, src1, src2) => {
+            let (mut a, mut b) = (self.operand(src1), self.operand(src2));
+            // ints divide their low 32 bits, like the interpreter.
+            if !self.variables[dest.as_str()].long && matches!(op, BinaryOp::Div | BinaryOp::Mod) {
+                a = self.truncate(&a);
+                b = self.truncate(&b);
+            }
+            let t = self.temporary();
+            let comparison = match op {
+            BinaryOp::Add => format!("{t} = add i64 {a}, {b}"),
+            BinaryOp::Sub => format!("{t} = sub i64 {a}, {b}"),
+            BinaryOp::Mult => format!("{t} = mul i64 {a}, {b}"),
+            BinaryOp::Div => format!("{t} = call i64 @tt_div(i64 {a}, i64 {b})"),
+            BinaryOp::Mod => format!("{t} = call i64 @tt_rem(i64 {a}, i64 {b})"),
+            BinaryOp::LessThan => format!("{t} = icmp slt i64 {a}, {b}"),
+            BinaryOp::LessEqual => format!("{t} = icmp sle i64 {a}, {b}"),
+            BinaryOp::NotEqual => format!("{t} = icmp ne i64 {a}, {b}"),
+            BinaryOp::Equal => format!("{t} = icmp eq i64 {a}, {b}"),
+            BinaryOp::GreaterEqual => format!("{t} = icmp sge i64 {a}, {b}"),
+            BinaryOp::GreaterThan => format!("{t} = icmp sgt i64 {a}, {b}"),
+            };
+            self.code(comparison);
+            match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mult | BinaryOp::Div | BinaryOp::Mod => self.store(dest, &t),
+            // 0 and 1 fit an int as they are.
+            _ => {
+                let wide = self.temporary();
+                self.code(format!("{wide} = zext i1 {t} to i64"));
+                self.code(format!("store i64 {wide}, ptr {}", variable(dest)));
+            }
+            }
+        }
+
+        Instruction::Jump(name) => {
+            self.code(format!("br label %{}", label(&name.0)));
+            self.terminated = true;
+        }
+
+        Instruction::BranchIf(condition, name) | Instruction::BranchIfn(condition, name) => {
+            let condition = self.operand(condition);
+            let (taken, otherwise) = (self.temporary(), self.temporary());
+            self.code(format!("{taken} = call i1 @tt_branch(i64 {condition})"));
+            if matches!(instruction, Instruction::BranchIf(..)) {
+                self.code(format!("br i1 {taken}, label %{}, label {otherwise}", label(&name.0)));
+            } else {
+                self.code(format!("br i1 {taken}, label {otherwise}, label %{}", label(&name.0)));
+            }
+            self.out += &format!("{}:\n", &otherwise[1..]);
+        }
+
+        Instruction::Label(_) => {}
+        }
+    }
+}
+
+// the same for every program.
+const RUNTIME: &str = r#"
+; runtime
+declare i32 @printf(ptr, ...)
+declare i32 @dprintf(i32, ptr, ...)
+declare i32 @putchar(i32)
+declare i32 @getchar()
+declare i32 @fflush(ptr)
+declare ptr @malloc(i64)
+declare ptr @calloc(i64, i64)
+declare ptr @realloc(ptr, i64)
+declare void @free(ptr)
+declare ptr @memset(ptr, i32, i64)
+declare void @exit(i32) noreturn
+
+@tt.format.out = private unnamed_addr constant [6 x i8] c"%lld\0A\00"
+@tt.format.int = private unnamed_addr constant [5 x i8] c"%lld\00"
+@tt.format.string = private unnamed_addr constant [3 x i8] c"%s\00"
+@tt.bad_input = private unnamed_addr constant [47 x i8] c"User Input Error. '%s' is not a valid number.\0A\00"
+@tt.end_of_input = private unnamed_addr constant [82 x i8] c"Runtime Error. Reached the end of the input while reading a number for '%input'.\0A\00"
+@tt.divide = private unnamed_addr constant [43 x i8] c"Runtime Error. Attempt to divide by zero.\0A\00"
+@tt.branch = private unnamed_addr constant [80 x i8] c"Runtime Error. Branch on a variable that is neither 0 or 1. The value is: %lld\0A\00"
+@tt.bad_read = private unnamed_addr constant [68 x i8] c"Runtime Error: Array out of bounds. Index %lld. Array Length %lld.\0A\00"
+@tt.bad_write = private unnamed_addr constant [67 x i8] c"Runtime Error: Array out of bounds. Value %lld. Array Length %lld\0A\00"
+@tt.memory = private unnamed_addr constant [31 x i8] c"Runtime Error. Out of memory.\0A\00"
+
+; prints the message to standard error and exits with 7, like run does.
+define internal void @tt_fail(ptr %message) noreturn {
+entry:
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @tt.format.string, ptr %message)
+  call void @exit(i32 7)
+  unreachable
+}
+
+; %out
+define internal void @tt_out(i64 %n) {
+entry:
+  %printed = call i32 (ptr, ...) @printf(ptr @tt.format.out, i64 %n)
+  ret void
+}
+
+; %out_int
+define internal void @tt_out_int(i64 %n) {
+entry:
+  %printed = call i32 (ptr, ...) @printf(ptr @tt.format.int, i64 %n)
+  ret void
+}
+
+; %out_str, a byte at a time so a string can have a 0 in it.
+define internal void @tt_out_str(ptr %text, i64 %length) {
+entry:
+  br label %check
+check:
+  %i = phi i64 [ 0, %entry ], [ %next, %print ]
+  %more = icmp ult i64 %i, %length
+  br i1 %more, label %print, label %done
+print:
+  %at = getelementptr inbounds i8, ptr %text, i64 %i
+  %byte = load i8, ptr %at
+  %c = zext i8 %byte to i32
+  %printed = call i32 @putchar(i32 %c)
+  %next = add i64 %i, 1
+  br label %check
+done:
+  ret void
+}
+
+; %outc
+define internal void @tt_outc(i64 %n) {
+entry:
+  %byte = trunc i64 %n to i8
+  %c = zext i8 %byte to i32
+  %printed = call i32 @putchar(i32 %c)
+  ret void
+}
+
+; %inputc: the next byte, or -1 at the end of the input.
+define internal i64 @tt_inputc() {
+entry:
+  %flushed = call i32 @fflush(ptr null)
+  %c = call i32 @getchar()
+  %end = icmp slt i32 %c, 0
+  %byte = select i1 %end, i32 -1, i32 %c
+  %n = sext i32 %byte to i64
+  ret i64 %n
+}
+
+; %input: reads lines until one is a number that fits, 64 bits if %long is
+; true and 32 bits otherwise.
+define internal i64 @tt_input(i1 %long) {
+entry:
+  %line = alloca ptr
+  %capacity = alloca i64
+  %length = alloca i64
+  %at = alloca i64
+  %magnitude = alloca i64
+  %first_line = call ptr @malloc(i64 64)
+  %no_line = icmp eq ptr %first_line, null
+  br i1 %no_line, label %out_of_memory, label %start
+start:
+  store ptr %first_line, ptr %line
+  store i64 64, ptr %capacity
+  %flushed = call i32 @fflush(ptr null)
+  br label %read_line
+read_line:
+  store i64 0, ptr %length
+  br label %read_byte
+read_byte:
+  %c = call i32 @getchar()
+  %end = icmp slt i32 %c, 0
+  br i1 %end, label %line_read, label %room
+room:
+  ; one byte is kept for the 0 at the end.
+  %used = load i64, ptr %length
+  %had = load i64, ptr %capacity
+  %needed = add i64 %used, 1
+  %full = icmp eq i64 %needed, %had
+  br i1 %full, label %grow, label %append
+grow:
+  %bigger = mul i64 %had, 2
+  %old = load ptr, ptr %line
+  %new = call ptr @realloc(ptr %old, i64 %bigger)
+  %failed = icmp eq ptr %new, null
+  br i1 %failed, label %out_of_memory, label %grown
+grown:
+  store ptr %new, ptr %line
+  store i64 %bigger, ptr %capacity
+  br label %append
+append:
+  %text = load ptr, ptr %line
+  %next = load i64, ptr %length
+  %slot = getelementptr inbounds i8, ptr %text, i64 %next
+  %byte = trunc i32 %c to i8
+  store i8 %byte, ptr %slot
+  %longer = add i64 %next, 1
+  store i64 %longer, ptr %length
+  %newline = icmp eq i32 %c, 10
+  br i1 %newline, label %line_read, label %read_byte
+line_read:
+  %read = load i64, ptr %length
+  %nothing = icmp eq i64 %read, 0
+  br i1 %nothing, label %end_of_input, label %trim
+trim:
+  ; drop the newline and any other space at the end.
+  %left = load i64, ptr %length
+  %empty = icmp eq i64 %left, 0
+  br i1 %empty, label %trimmed, label %trim_last
+trim_last:
+  %trim_text = load ptr, ptr %line
+  %last = sub i64 %left, 1
+  %last_slot = getelementptr inbounds i8, ptr %trim_text, i64 %last
+  %last_byte = load i8, ptr %last_slot
+  %is_space = icmp eq i8 %last_byte, 32
+  %control = sub i8 %last_byte, 9
+  %is_control = icmp ule i8 %control, 4
+  %is_blank = or i1 %is_space, %is_control
+  br i1 %is_blank, label %trim_one, label %trimmed
+trim_one:
+  store i64 %last, ptr %length
+  br label %trim
+trimmed:
+  %number = load ptr, ptr %line
+  %size = load i64, ptr %length
+  %end_slot = getelementptr inbounds i8, ptr %number, i64 %size
+  store i8 0, ptr %end_slot
+  ; an optional sign and at least one digit, and nothing else.
+  %first = load i8, ptr %number
+  %negative = icmp eq i8 %first, 45
+  %plus = icmp eq i8 %first, 43
+  %signed = or i1 %negative, %plus
+  %digits = zext i1 %signed to i64
+  store i64 %digits, ptr %at
+  store i64 0, ptr %magnitude
+  %no_digits = icmp uge i64 %digits, %size
+  br i1 %no_digits, label %bad, label %digit
+digit:
+  %i = load i64, ptr %at
+  %digit_slot = getelementptr inbounds i8, ptr %number, i64 %i
+  %character = load i8, ptr %digit_slot
+  %value = sub i8 %character, 48
+  %not_digit = icmp ugt i8 %value, 9
+  br i1 %not_digit, label %bad, label %accumulate
+accumulate:
+  ; anything past 2^63 is too big, with or without a sign.
+  %so_far = load i64, ptr %magnitude
+  %too_big = icmp ugt i64 %so_far, 922337203685477580
+  br i1 %too_big, label %bad, label %add_digit
+add_digit:
+  %shifted = mul i64 %so_far, 10
+  %wide_value = zext i8 %value to i64
+  %with_digit = add i64 %shifted, %wide_value
+  store i64 %with_digit, ptr %magnitude
+  %following = add i64 %i, 1
+  store i64 %following, ptr %at
+  %more = icmp ult i64 %following, %size
+  br i1 %more, label %digit, label %parsed
+parsed:
+  ; 2^63 only fits when it is negative.
+  %total = load i64, ptr %magnitude
+  %extra = zext i1 %negative to i64
+  %limit = add i64 9223372036854775807, %extra
+  %overflow = icmp ugt i64 %total, %limit
+  br i1 %overflow, label %bad, label %in_range
+in_range:
+  %negated = sub i64 0, %total
+  %n = select i1 %negative, i64 %negated, i64 %total
+  ; an int has to fit in 32 bits.
+  %narrow = trunc i64 %n to i32
+  %as_int = sext i32 %narrow to i64
+  %fits_int = icmp eq i64 %n, %as_int
+  %fits = or i1 %long, %fits_int
+  br i1 %fits, label %done, label %bad
+done:
+  call void @free(ptr %number)
+  ret i64 %n
+bad:
+  %bad_text = load ptr, ptr %line
+  %printed = call i32 (ptr, ...) @printf(ptr @tt.bad_input, ptr %bad_text)
+  br label %read_line
+end_of_input:
+  call void @tt_fail(ptr @tt.end_of_input)
+  unreachable
+out_of_memory:
+  call void @tt_fail(ptr @tt.memory)
+  unreachable
+}
+
+; the most negative number divided by -1 wraps back to itself, where sdiv
+; would be undefined.
+define internal i64 @tt_div(i64 %a, i64 %b) {
+entry:
+  %zero = icmp eq i64 %b, 0
+  br i1 %zero, label %divide_by_zero, label %nonzero
+nonzero:
+  %minus_one = icmp eq i64 %b, -1
+  br i1 %minus_one, label %negate, label %divide
+negate:
+  %negated = sub i64 0, %a
+  ret i64 %negated
+divide:
+  %quotient = sdiv i64 %a, %b
+  ret i64 %quotient
+divide_by_zero:
+  call void @tt_fail(ptr @tt.divide)
+  unreachable
+}
+
+define internal i64 @tt_rem(i64 %a, i64 %b) {
+entry:
+  %zero = icmp eq i64 %b, 0
+  br i1 %zero, label %divide_by_zero, label %nonzero
+nonzero:
+  %minus_one = icmp eq i64 %b, -1
+  br i1 %minus_one, label %no_remainder, label %divide
+no_remainder:
+  ret i64 0
+divide:
+  %remainder = srem i64 %a, %b
+  ret i64 %remainder
+divide_by_zero:
+  call void @tt_fail(ptr @tt.divide)
+  unreachable
+}
+
+; true for 1 and false for 0, anything else is a runtime error.
+define internal i1 @tt_branch(i64 %condition) {
+entry:
+  %bad = icmp ugt i64 %condition, 1
+  br i1 %bad, label %fail, label %ok
+ok:
+  %taken = icmp eq i64 %condition, 1
+  ret i1 %taken
+fail:
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @tt.branch, i64 %condition)
+  call void @exit(i32 7)
+  unreachable
+}
+
+define internal ptr @tt_array(i64 %length) {
+entry:
+  %array = call ptr @calloc(i64 %length, i64 8)
+  %failed = icmp eq ptr %array, null
+  %some = icmp ne i64 %length, 0
+  %out_of_memory = and i1 %failed, %some
+  br i1 %out_of_memory, label %fail, label %ok
+ok:
+  ret ptr %array
+fail:
+  call void @tt_fail(ptr @tt.memory)
+  unreachable
+}
+
+; unsigned, so a negative index is out of bounds too.
+define internal void @tt_check_read(i64 %index, i64 %length) {
+entry:
+  %outside = icmp uge i64 %index, %length
+  br i1 %outside, label %fail, label %ok
+ok:
+  ret void
+fail:
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @tt.bad_read, i64 %index, i64 %length)
+  call void @exit(i32 7)
+  unreachable
+}
+
+define internal void @tt_check_write(i64 %index, i64 %length) {
+entry:
+  %outside = icmp uge i64 %index, %length
+  br i1 %outside, label %fail, label %ok
+ok:
+  ret void
+fail:
+  %printed = call i32 (i32, ptr, ...) @dprintf(i32 2, ptr @tt.bad_write, i64 %index, i64 %length)
+  call void @exit(i32 7)
+  unreachable
+}
+"#;
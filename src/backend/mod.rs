@@ -0,0 +1,6 @@
+// src/backend/mod.rs
+// Code generators that take the checked IR somewhere other than the interpreter.
+pub mod c;
+pub mod llvm;
+pub mod wasm;
+pub mod x86;
@@ -0,0 +1,1205 @@
+// src/backend/wasm.rs
+// Lowers the IR to a WebAssembly module in the text format (WAT), for running
+// Teh Terik programs in a browser:
+//
+// let text = wasm::generate(&module);
+//
+// The module imports three functions from "tt", which the host provides:
+//
+// print(pointer: i32, length: i32)  writes bytes from memory to standard output
+// read() -> i32                     the next byte of standard input, or -1 at its end
+// error(pointer: i32, length: i32)  writes bytes from memory to standard error
+//
+// and exports its "memory" and "main", which runs the program and gives back
+// the exit code run would give. A runtime error writes the interpreter's
+// message with error and then traps, which the host should treat as exit code 7.
+//
+// Every variable is an i64 local, with ints kept sign extended from 32 bits.
+// Arrays live in linear memory, allocated from a heap pointer when the function
+// is called and given back when it returns. WebAssembly has no goto, so the
+// labels and jumps of each function are turned back into blocks, loops and ifs
+// following Ramsey's "Beyond Relooper", with a dispatch loop for the jumps
+// that cannot be written that way.
+//
+// The module has to have loaded with Program::load first, which checks
+// everything generate relies on, like every label being declared.
+
+use std::collections::HashMap;
+
+use crate::phases::ir::{BinaryOp, Function, Instruction, Module, Operand, Type};
+
+// the first 32 bytes of memory are where the runtime writes out numbers and
+// characters, the data goes after them.
+const DATA_START: usize = 32;
+
+// the text the runtime prints, with the name RUNTIME uses for its address and
+// length, e.g. @newline and #newline.
+const MESSAGES: [(&str, &str); 11] = [
+    ("newline", "\n"),
+    ("bad_input", "User Input Error. '"),
+    ("not_a_number", "' is not a valid number.\n"),
+    ("end_of_input", "Runtime Error. Reached the end of the input while reading a number for '%input'.\n"),
+    ("divide", "Runtime Error. Attempt to divide by zero.\n"),
+    ("branch", "Runtime Error. Branch on a variable that is neither 0 or 1. The value is: "),
+    ("read_index", "Runtime Error: Array out of bounds. Index "),
+    ("write_index", "Runtime Error: Array out of bounds. Value "),
+    ("array_length", ". Array Length "),
+    ("read_end", ".\n"),
+    ("memory", "Runtime Error. Out of memory.\n"),
+];
+
+// where a jump goes when the function is cut into basic blocks.
+#[derive(Debug, Clone, Copy)]
+enum Exit<'m> {
+    Jump(usize),
+    // the condition, whether the branch is taken on 1, where it goes then and otherwise.
+    Branch(&'m Operand, bool, usize, usize),
+    Return(&'m Operand),
+    // falling off the end of the function.
+    End,
+}
+
+// a run of instructions that is only ever entered at the top.
+struct BasicBlock<'m> {
+    label: Option<&'m str>,
+    body: Vec<&'m Instruction>,
+    exit: Exit<'m>,
+}
+
+// the shape of a function's jumps, with the basic blocks numbered in the order
+// they are in the function.
+struct Flow<'m> {
+    blocks: Vec<BasicBlock<'m>>,
+    // the reverse postorder number of each reachable block.
+    order: Vec<Option<usize>>,
+    // the dominator tree.
+    children: Vec<Vec<usize>>,
+    // blocks jumped back to, which need a loop.
+    headers: Vec<bool>,
+    // blocks jumped forward to from more than one place, which need a block to break out of.
+    merges: Vec<bool>,
+    // whether every loop is entered at its top, so Ramsey's translation works.
+    reducible: bool,
+}
+
+// what generate knows about a variable.
+#[derive(Debug, Clone, Copy)]
+struct Variable {
+    long: bool,
+    // the number of elements, for arrays.
+    length: Option<i64>,
+}
+
+struct Generator<'m> {
+    out: String,
+    indent: usize,
+    // the address and length of each string constant.
+    strings: HashMap<&'m str, (usize, usize)>,
+    variables: HashMap<&'m str, Variable>,
+    // whether the function being generated has arrays to give back when it returns.
+    arrays: bool,
+    // the position of each block in the dispatch loop, when there is one.
+    dispatch: Option<HashMap<usize, usize>>,
+}
+
+// the WAT for a whole module, runtime included.
+pub fn generate(module: &Module) -> String {
+    let mut generator = Generator { out: String::new(), indent: 1, strings: HashMap::new(), variables: HashMap::new(), arrays: false, dispatch: None };
+
+    // the runtime's messages and the string constants go after the number buffer,
+    // and the heap after them.
+    let mut data: Vec<(usize, &[u8])> = vec![];
+    let mut address = DATA_START;
+    let mut runtime = String::from(RUNTIME);
+    for (name, text) in MESSAGES {
+        runtime = runtime.replace(&format!("@{name}\n"), &format!("{address}\n")).replace(&format!("#{name}\n"), &format!("{}\n", text.len()));
+        data.push((address, text.as_bytes()));
+        address += text.len();
+    }
+    for (name, text) in &module.strings {
+        generator.strings.insert(name, (address, text.len()));
+        data.push((address, text.as_bytes()));
+        address += text.len();
+    }
+    // what run would say when there is no main to call.
+    let main_error = match module.functions.iter().find(|function| function.name == "main") {
+    Some(main) if main.parameters.is_empty() => None,
+    Some(main) => Some(format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got 0 parameters\n", main.parameters.len())),
+    None => Some(String::from("Runtime Error. No main function declared.\n")),
+    };
+    if let Some(e) = &main_error {
+        data.push((address, e.as_bytes()));
+        address += e.len();
+    }
+    let heap = address.div_ceil(8) * 8;
+
+    generator.out += "(module\n";
+    generator.line(String::from(";; generated by the Teh Terik compiler"));
+    generator.line(String::from("(import \"tt\" \"print\" (func $tt_print (param i32 i32)))"));
+    generator.line(String::from("(import \"tt\" \"read\" (func $tt_read (result i32)))"));
+    generator.line(String::from("(import \"tt\" \"error\" (func $tt_error (param i32 i32)))"));
+    generator.line(format!("(memory (export \"memory\") {})", heap.div_ceil(65536).max(1)));
+    generator.line(format!("(global $tt_heap (mut i32) (i32.const {heap}))"));
+    for (address, bytes) in data {
+        generator.line(format!("(data (i32.const {address}) \"{}\")", escape(bytes)));
+    }
+
+    generator.out += "\n";
+    generator.line(String::from("(func (export \"main\") (result i32)"));
+    generator.indent += 1;
+    match &main_error {
+    None => {
+        generator.line(String::from("call $f_main"));
+        // the exit code is the low byte, like in run.
+        generator.line(String::from("i32.wrap_i64"));
+        generator.line(String::from("i32.const 255"));
+        generator.line(String::from("i32.and"));
+    }
+    Some(e) => {
+        generator.line(format!("i32.const {}", address - e.len()));
+        generator.line(format!("i32.const {}", e.len()));
+        generator.line(String::from("call $tt_error"));
+        generator.line(String::from("unreachable"));
+    }
+    }
+    generator.indent -= 1;
+    generator.line(String::from(")"));
+
+    for function in &module.functions {
+        generator.function(function);
+    }
+    generator.out += &runtime;
+    generator.out += ")\n";
+    generator.out
+}
+
+// names in hand-written IR can have any characters but whitespace, so
+// anything WAT would not take in a name is written as '.' and its hex.
+fn mangle(name: &str) -> String {
+    let mut mangled = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'_' {
+            mangled.push(byte as char);
+        } else {
+            mangled += &format!(".{byte:02x}");
+        }
+    }
+    mangled
+}
+
+// the text of a data segment inside "...".
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for byte in bytes {
+        if (0x20..0x7f).contains(byte) && *byte != b'"' && *byte != b'\\' {
+            escaped.push(*byte as char);
+        } else {
+            escaped += &format!("\\{byte:02x}");
+        }
+    }
+    escaped
+}
+
+impl<'m> Flow<'m> {
+    // cuts the body into basic blocks, and works out which jumps go back, which
+    // blocks are jumped to from more than one place, and what dominates what.
+    fn new(function: &'m Function) -> Flow<'m> {
+        let body = &function.body;
+        // a block starts at every label, and after every jump.
+        let mut starts = vec![0];
+        for (i, instruction) in body.iter().enumerate() {
+            match instruction {
+            Instruction::Label(_) if i > 0 => starts.push(i),
+            Instruction::Jump(_) | Instruction::BranchIf(..) | Instruction::BranchIfn(..) | Instruction::Return(_) if i + 1 < body.len() => starts.push(i + 1),
+            _ => {}
+            }
+        }
+        starts.dedup();
+
+        let mut labels: HashMap<&str, usize> = HashMap::new();
+        for (block, start) in starts.iter().enumerate() {
+            if let Some(Instruction::Label(label)) = body.get(*start) {
+                labels.insert(&label.0, block);
+            }
+        }
+
+        let mut blocks: Vec<BasicBlock> = vec![];
+        for (block, start) in starts.iter().enumerate() {
+            let end = starts.get(block + 1).copied().unwrap_or(body.len());
+            let mut instructions: Vec<&Instruction> = body[*start..end].iter().collect();
+            let label = match instructions.first() {
+            Some(Instruction::Label(label)) => {
+                instructions.remove(0);
+                Some(label.0.as_str())
+            }
+            _ => None,
+            };
+            let exit = match instructions.last() {
+            Some(Instruction::Jump(label)) => Exit::Jump(labels[label.0.as_str()]),
+            Some(Instruction::BranchIf(condition, label)) => Exit::Branch(condition, true, labels[label.0.as_str()], block + 1),
+            Some(Instruction::BranchIfn(condition, label)) => Exit::Branch(condition, false, labels[label.0.as_str()], block + 1),
+            Some(Instruction::Return(value)) => Exit::Return(value),
+            _ => Exit::Jump(block + 1),
+            };
+            if matches!(instructions.last(), Some(Instruction::Jump(_) | Instruction::BranchIf(..) | Instruction::BranchIfn(..) | Instruction::Return(_))) {
+                instructions.pop();
+            }
+            blocks.push(BasicBlock { label, body: instructions, exit });
+        }
+        // one more block at the end, for falling off it.
+        blocks.push(BasicBlock { label: None, body: vec![], exit: Exit::End });
+
+        let successors: Vec<Vec<usize>> = blocks.iter().map(|block| match block.exit {
+        Exit::Jump(target) => vec![target],
+        Exit::Branch(_, _, target, otherwise) => vec![otherwise, target],
+        Exit::Return(_) | Exit::End => vec![],
+        }).collect();
+
+        // reverse postorder, from a depth first search from the entry.
+        let mut postorder: Vec<usize> = vec![];
+        let mut visited = vec![false; blocks.len()];
+        let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+        visited[0] = true;
+        while let Some((block, next)) = stack.pop() {
+            match successors[block].get(next) {
+            Some(&successor) => {
+                stack.push((block, next + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => postorder.push(block),
+            }
+        }
+        let mut order = vec![None; blocks.len()];
+        for (number, block) in postorder.iter().rev().enumerate() {
+            order[*block] = Some(number);
+        }
+        let reachable: Vec<usize> = postorder.iter().rev().copied().collect();
+
+        // Cooper, Harvey and Kennedy's "A Simple, Fast Dominance Algorithm".
+        let mut predecessors: Vec<Vec<usize>> = vec![vec![]; blocks.len()];
+        for &block in &reachable {
+            for &successor in &successors[block] {
+                predecessors[successor].push(block);
+            }
+        }
+        let mut idom: Vec<Option<usize>> = vec![None; blocks.len()];
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in reachable.iter().skip(1) {
+                let mut new_idom: Option<usize> = None;
+                for &predecessor in &predecessors[block] {
+                    if idom[predecessor].is_none() {
+                        continue;
+                    }
+                    new_idom = match new_idom {
+                    None => Some(predecessor),
+                    Some(other) => Some(intersect(&idom, &order, predecessor, other)),
+                    };
+                }
+                if new_idom.is_some() && idom[block] != new_idom {
+                    idom[block] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut children = vec![vec![]; blocks.len()];
+        for &block in reachable.iter().skip(1) {
+            children[idom[block].unwrap()].push(block);
+        }
+
+        let mut headers = vec![false; blocks.len()];
+        let mut forward = vec![0; blocks.len()];
+        let mut reducible = true;
+        for &block in &reachable {
+            for &successor in &successors[block] {
+                if order[successor] > order[block] {
+                    forward[successor] += 1;
+                    continue;
+                }
+                headers[successor] = true;
+                // a jump back into the middle of a loop, not to its top.
+                let mut dominator = block;
+                while dominator != successor && dominator != 0 {
+                    dominator = idom[dominator].unwrap();
+                }
+                if dominator != successor {
+                    reducible = false;
+                }
+            }
+        }
+        let merges = forward.iter().map(|count| *count > 1).collect();
+
+        Flow { blocks, order, children, headers, merges, reducible }
+    }
+
+    fn is_backward(&self, from: usize, to: usize) -> bool {
+        self.order[to] <= self.order[from]
+    }
+}
+
+// the closest block dominating both a and b.
+fn intersect(idom: &[Option<usize>], order: &[Option<usize>], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while order[a] > order[b] {
+            a = idom[a].unwrap();
+        }
+        while order[b] > order[a] {
+            b = idom[b].unwrap();
+        }
+    }
+    a
+}
+
+impl<'m> Generator<'m> {
+    fn line(&mut self, text: String) {
+        for _ in 0..self.indent {
+            self.out += "  ";
+        }
+        self.out += &text;
+        self.out += "\n";
+    }
+
+    fn open(&mut self, text: String) {
+        self.line(text);
+        self.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.indent -= 1;
+        self.line(String::from("end"));
+    }
+
+    fn operand(&mut self, value: &Operand) {
+        match value {
+        Operand::Num(num) => self.line(format!("i64.const {num}")),
+        Operand::Var(name) => self.line(format!("local.get $v_{}", mangle(name))),
+        }
+    }
+
+    // an operand cut down to 32 bits, which numbers can be before they are written out.
+    fn narrow_operand(&mut self, value: &Operand) {
+        match value {
+        Operand::Num(num) => self.line(format!("i64.const {}", *num as i32)),
+        Operand::Var(_) => {
+            self.operand(value);
+            self.truncate();
+        }
+        }
+    }
+
+    // sign extends the low 32 bits of the number on the stack.
+    fn truncate(&mut self) {
+        self.line(String::from("i32.wrap_i64"));
+        self.line(String::from("i64.extend_i32_s"));
+    }
+
+    // writes the number on the stack to a variable, cut down to 32 bits for an int.
+    fn store(&mut self, dest: &str) {
+        if !self.variables[dest].long {
+            self.truncate();
+        }
+        self.line(format!("local.set $v_{}", mangle(dest)));
+    }
+
+    fn function(&mut self, function: &'m Function) {
+        self.variables.clear();
+        let mut declared: Vec<&str> = vec![];
+        for (typ, name) in &function.parameters {
+            self.variables.insert(name, Variable { long: *typ == Type::Long, length: None });
+        }
+        for instruction in &function.body {
+            let (typ, name, length) = match instruction {
+            Instruction::Declare(typ, name) => (typ, name, None),
+            Instruction::DeclareArray(typ, name, length) => (typ, name, Some(*length)),
+            _ => continue,
+            };
+            self.variables.insert(name, Variable { long: *typ == Type::Long, length });
+            declared.push(name);
+        }
+        let arrays: Vec<&str> = declared.iter().copied().filter(|name| self.variables[name].length.is_some()).collect();
+        self.arrays = !arrays.is_empty();
+        let flow = Flow::new(function);
+
+        self.out += "\n";
+        self.line(format!(";; %func {}", function.name));
+        let mut header = format!("(func $f_{}", mangle(&function.name));
+        for (_, name) in &function.parameters {
+            header += &format!(" (param $v_{} i64)", mangle(name));
+        }
+        self.open(header + " (result i64)");
+        // an array's local holds the address of its elements.
+        for name in &declared {
+            let typ = if self.variables[name].length.is_some() { "i32" } else { "i64" };
+            self.line(format!("(local $v_{} {typ})", mangle(name)));
+        }
+        if self.arrays {
+            self.line(String::from("(local $tt_heap_saved i32)"));
+        }
+        if !flow.reducible {
+            self.line(String::from("(local $tt_next i32)"));
+        }
+
+        for (typ, name) in &function.parameters {
+            if *typ == Type::Int {
+                self.line(format!("local.get $v_{}", mangle(name)));
+                self.store(name);
+            }
+        }
+        if self.arrays {
+            self.line(String::from("global.get $tt_heap"));
+            self.line(String::from("local.set $tt_heap_saved"));
+            for name in &arrays {
+                self.line(format!("i64.const {}", self.variables[name].length.unwrap_or(0)));
+                self.line(String::from("call $tt_alloc"));
+                self.line(format!("local.set $v_{}", mangle(name)));
+            }
+        }
+
+        if flow.reducible {
+            self.dispatch = None;
+            self.tree(&flow, 0);
+        } else {
+            self.dispatch_loop(&flow);
+        }
+        // every way through the code above ends in a return or a jump.
+        self.line(String::from("unreachable"));
+        self.indent -= 1;
+        self.line(String::from(")"));
+    }
+
+    // a block and everything it dominates, in a loop if it is jumped back to.
+    fn tree(&mut self, flow: &Flow, block: usize) {
+        let mut merges: Vec<usize> = flow.children[block].iter().copied().filter(|child| flow.merges[*child]).collect();
+        merges.sort_by_key(|child| std::cmp::Reverse(flow.order[*child]));
+        if flow.headers[block] {
+            self.open(format!("loop $loop{block}"));
+            self.within(flow, block, &merges);
+            self.close();
+        } else {
+            self.within(flow, block, &merges);
+        }
+    }
+
+    // the code of the block, inside one block to break out of for each of the
+    // merges, which follow it with the one furthest down last.
+    fn within(&mut self, flow: &Flow, block: usize, merges: &[usize]) {
+        match merges.split_first() {
+        Some((&merge, rest)) => {
+            self.open(format!("block $b{merge}"));
+            self.within(flow, block, rest);
+            self.close();
+            self.tree(flow, merge);
+        }
+        None => self.code(flow, block),
+        }
+    }
+
+    fn branch(&mut self, flow: &Flow, from: usize, to: usize) {
+        if let Some(positions) = &self.dispatch {
+            self.line(format!("i32.const {}", positions[&to]));
+            self.line(String::from("local.set $tt_next"));
+            self.line(String::from("br $dispatch"));
+        } else if flow.is_backward(from, to) {
+            self.line(format!("br $loop{to}"));
+        } else if flow.merges[to] {
+            self.line(format!("br $b{to}"));
+        } else {
+            self.tree(flow, to);
+        }
+    }
+
+    // when some loop can be entered in the middle, every block goes in one big
+    // loop that picks the block to run next from $tt_next.
+    fn dispatch_loop(&mut self, flow: &Flow) {
+        let mut blocks: Vec<usize> = (0..flow.blocks.len()).filter(|block| flow.order[*block].is_some()).collect();
+        // the entry has to be first, since $tt_next starts out as 0.
+        blocks.sort_by_key(|block| *block != 0);
+        self.dispatch = Some(blocks.iter().enumerate().map(|(position, block)| (*block, position)).collect());
+
+        self.open(String::from("loop $dispatch"));
+        for block in blocks.iter().rev() {
+            self.open(format!("block $b{block}"));
+        }
+        self.line(String::from("local.get $tt_next"));
+        let targets: Vec<String> = blocks.iter().map(|block| format!("$b{block}")).collect();
+        self.line(format!("br_table {}", targets.join(" ")));
+        for block in &blocks {
+            self.close();
+            self.code(flow, *block);
+        }
+        self.close();
+    }
+
+    fn code(&mut self, flow: &Flow, block: usize) {
+        if let Some(label) = flow.blocks[block].label {
+            self.line(format!(";; :{label}"));
+        }
+        for instruction in &flow.blocks[block].body {
+            self.instruction(instruction);
+        }
+        match flow.blocks[block].exit {
+        Exit::Jump(to) => self.branch(flow, block, to),
+        Exit::Branch(condition, when, to, otherwise) => {
+            self.operand(condition);
+            self.line(String::from("call $tt_branch"));
+            if !when {
+                self.line(String::from("i32.eqz"));
+            }
+            self.open(String::from("if"));
+            self.branch(flow, block, to);
+            self.indent -= 1;
+            self.line(String::from("else"));
+            self.indent += 1;
+            self.branch(flow, block, otherwise);
+            self.close();
+        }
+        Exit::Return(value) => {
+            self.operand(value);
+            self.ret();
+        }
+        // falling off the end returns 0.
+        Exit::End => {
+            self.line(String::from("i64.const 0"));
+            self.ret();
+        }
+        }
+    }
+
+    // returns the number on the stack, giving back the memory of the arrays first.
+    fn ret(&mut self) {
+        if self.arrays {
+            self.line(String::from("local.get $tt_heap_saved"));
+            self.line(String::from("global.set $tt_heap"));
+        }
+        self.line(String::from("return"));
+    }
+
+    // the address of an element, after checking the index.
+    fn element(&mut self, array: &str, element: &Operand, check: &str) {
+        self.line(format!("local.get $v_{}", mangle(array)));
+        self.operand(element);
+        self.line(format!("i64.const {}", self.variables[array].length.unwrap_or(0)));
+        self.line(format!("call {check}"));
+        self.line(String::from("i32.add"));
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        self.line(format!(";; {instruction}"));
+        match instruction {
+        Instruction::Declare(_, name) => {
+            self.line(String::from("i64.const 0"));
+            self.line(format!("local.set $v_{}", mangle(name)));
+        }
+
+        Instruction::DeclareArray(_, name, length) => {
+            self.line(format!("local.get $v_{}", mangle(name)));
+            self.line(String::from("i32.const 0"));
+            self.line(format!("i32.const {}", length * 8));
+            self.line(String::from("memory.fill"));
+        }
+
+        Instruction::Call(dest, function, arguments) => {
+            for argument in arguments {
+                self.operand(argument);
+            }
+            self.line(format!("call $f_{}", mangle(function)));
+            self.store(dest);
+        }
+
+        Instruction::Out(value) => {
+            self.operand(value);
+            self.line(String::from("call $tt_out"));
+        }
+
+        Instruction::OutStr(name) => {
+            let (address, length) = self.strings[name.as_str()];
+            self.line(format!("i32.const {address}"));
+            self.line(format!("i32.const {length}"));
+            self.line(String::from("call $tt_print"));
+        }
+
+        Instruction::OutInt(value) => {
+            self.operand(value);
+            self.line(String::from("call $tt_out_int"));
+        }
+
+        Instruction::OutLine => self.line(String::from("call $tt_out_nl")),
+
+        Instruction::In(dest) => {
+            self.line(format!("i32.const {}", self.variables[dest.as_str()].long as i32));
+            self.line(String::from("call $tt_input"));
+            self.line(format!("local.set $v_{}", mangle(dest)));
+        }
+
+        Instruction::OutChar(value) => {
+            self.operand(value);
+            self.line(String::from("call $tt_outc"));
+        }
+
+        Instruction::InChar(dest) => {
+            self.line(String::from("call $tt_inputc"));
+            self.store(dest);
+        }
+
+        Instruction::Mov(dest, src) => {
+            // a number can be cut down to fit before it is written out.
+            if let Operand::Num(num) = src {
+                let num = if self.variables[dest.as_str()].long { *num } else { *num as i32 as i64 };
+                self.line(format!("i64.const {num}"));
+                self.line(format!("local.set $v_{}", mangle(dest)));
+            } else {
+                self.operand(src);
+                self.store(dest);
+            }
+        }
+
+
+        Instruction::Load(dest, array, element) => {
+            self.element(array, element, "$tt_read_index");
+            self.line(String::from("i64.load"));
+            self.store(dest);
+        }
+
+        Instruction::Store(array, element, src) => {
+            self.element(array, element, "$tt_write_index");
+            self.operand(src);
+            if !self.variables[array.as_str()].long {
+                self.truncate();
+            }
+            self.line(String::from("i64.store"));
+        }
+
+        Instruction::Binary(op, dest, src1, src2) => {
+            // ints divide their low 32 bits, like the interpreter.
+            if !self.variables[dest.as_str()].long && matches!(op, BinaryOp::Div | BinaryOp::Mod) {
+                self.narrow_operand(src1);
+                self.narrow_operand(src2);
+            } else {
+                self.operand(src1);
+                self.operand(src2);
+            }
+            let (code, comparison) = match op {
+            BinaryOp::Add => ("i64.add", false),
+            BinaryOp::Sub => ("i64.sub", false),
+            BinaryOp::Mult => ("i64.mul", false),
+            BinaryOp::Div => ("call $tt_div", false),
+            BinaryOp::Mod => ("call $tt_rem", false),
+            BinaryOp::LessThan => ("i64.lt_s", true),
+            BinaryOp::LessEqual => ("i64.le_s", true),
+            BinaryOp::NotEqual => ("i64.ne", true),
+            BinaryOp::Equal => ("i64.eq", true),
+            BinaryOp::GreaterEqual => ("i64.ge_s", true),
+            BinaryOp::GreaterThan => ("i64.gt_s", true),
+            };
+            self.line(String::from(code));
+            // 0 and 1 fit an int as they are.
+            if comparison {
+                self.line(String::from("i64.extend_i32_u"));
+                self.line(format!("local.set $v_{}", mangle(dest)));
+            } else {
+                self.store(dest);
+            }
+        }
+
+        // the flow of the function takes care of these.
+        Instruction::Label(_) | Instruction::Jump(_) | Instruction::BranchIf(..) | Instruction::BranchIfn(..) | Instruction::Return(_) => {}
+        }
+    }
+}
+
+// the same for every program. generate fills in the address and length of the
+// messages. numbers are written out as text ending at address 24, and %outc
+// writes its character at 24.
+const RUNTIME: &str = r#"
+  ;; runtime
+  ;; writes %n out as text, giving back the address it starts at.
+  (func $tt_format (param $n i64) (result i32)
+    (local $at i32)
+    (local $magnitude i64)
+    i32.const 24
+    local.set $at
+    ;; as an unsigned number, which is right for the most negative number too.
+    local.get $n
+    local.set $magnitude
+    local.get $n
+    i64.const 0
+    i64.lt_s
+    if
+      i64.const 0
+      local.get $n
+      i64.sub
+      local.set $magnitude
+    end
+    loop $digit
+      local.get $at
+      i32.const 1
+      i32.sub
+      local.tee $at
+      local.get $magnitude
+      i64.const 10
+      i64.rem_u
+      i64.const 48
+      i64.add
+      i64.store8
+      local.get $magnitude
+      i64.const 10
+      i64.div_u
+      local.tee $magnitude
+      i64.const 0
+      i64.ne
+      br_if $digit
+    end
+    local.get $n
+    i64.const 0
+    i64.lt_s
+    if
+      local.get $at
+      i32.const 1
+      i32.sub
+      local.tee $at
+      i32.const 45
+      i32.store8
+    end
+    local.get $at
+  )
+
+  ;; %out_int
+  (func $tt_out_int (param $n i64)
+    (local $at i32)
+    local.get $n
+    call $tt_format
+    local.tee $at
+    i32.const 24
+    local.get $at
+    i32.sub
+    call $tt_print
+  )
+
+  ;; %out
+  (func $tt_out (param $n i64)
+    local.get $n
+    call $tt_out_int
+    call $tt_out_nl
+  )
+
+  ;; %out_nl
+  (func $tt_out_nl
+    i32.const @newline
+    i32.const #newline
+    call $tt_print
+  )
+
+  ;; %outc
+  (func $tt_outc (param $c i64)
+    i32.const 24
+    local.get $c
+    i64.store8
+    i32.const 24
+    i32.const 1
+    call $tt_print
+  )
+
+  ;; %inputc: the next byte, or -1 at the end of the input.
+  (func $tt_inputc (result i64)
+    call $tt_read
+    i64.extend_i32_s
+  )
+
+  ;; %input: reads lines until one is a number that fits, 64 bits if $long is
+  ;; 1 and 32 bits otherwise. the line goes in the memory after the heap.
+  (func $tt_input (param $long i32) (result i64)
+    (local $length i32)
+    (local $c i32)
+    (local $at i32)
+    (local $negative i32)
+    (local $magnitude i64)
+    (local $n i64)
+    loop $line
+      i32.const 0
+      local.set $length
+      block $read
+        loop $byte
+          call $tt_read
+          local.tee $c
+          i32.const -1
+          i32.eq
+          br_if $read
+          ;; grow the memory when the line does not fit.
+          global.get $tt_heap
+          i64.extend_i32_u
+          local.get $length
+          i64.extend_i32_u
+          i64.add
+          memory.size
+          i64.extend_i32_u
+          i64.const 16
+          i64.shl
+          i64.ge_u
+          if
+            i32.const 1
+            memory.grow
+            i32.const -1
+            i32.eq
+            if
+              call $tt_out_of_memory
+            end
+          end
+          global.get $tt_heap
+          local.get $length
+          i32.add
+          local.get $c
+          i32.store8
+          local.get $length
+          i32.const 1
+          i32.add
+          local.set $length
+          local.get $c
+          i32.const 10
+          i32.ne
+          br_if $byte
+        end
+      end
+      local.get $length
+      i32.eqz
+      if
+        i32.const @end_of_input
+        i32.const #end_of_input
+        call $tt_error
+        unreachable
+      end
+
+      ;; drop the newline and any other space at the end.
+      block $trimmed
+        loop $trim
+          local.get $length
+          i32.eqz
+          br_if $trimmed
+          global.get $tt_heap
+          local.get $length
+          i32.add
+          i32.const 1
+          i32.sub
+          i32.load8_u
+          local.tee $c
+          i32.const 32
+          i32.eq
+          local.get $c
+          i32.const 9
+          i32.sub
+          i32.const 4
+          i32.le_u
+          i32.or
+          i32.eqz
+          br_if $trimmed
+          local.get $length
+          i32.const 1
+          i32.sub
+          local.set $length
+          br $trim
+        end
+      end
+
+      ;; an optional sign and at least one digit, and nothing else.
+      block $bad
+        local.get $length
+        i32.eqz
+        br_if $bad
+        i32.const 0
+        local.set $at
+        i64.const 0
+        local.set $magnitude
+        global.get $tt_heap
+        i32.load8_u
+        local.tee $c
+        i32.const 45
+        i32.eq
+        local.tee $negative
+        local.get $c
+        i32.const 43
+        i32.eq
+        i32.or
+        if
+          i32.const 1
+          local.set $at
+          local.get $length
+          i32.const 1
+          i32.eq
+          br_if $bad
+        end
+        block $digits
+          loop $digit
+            local.get $at
+            local.get $length
+            i32.eq
+            br_if $digits
+            global.get $tt_heap
+            local.get $at
+            i32.add
+            i32.load8_u
+            i32.const 48
+            i32.sub
+            local.tee $c
+            i32.const 9
+            i32.gt_u
+            br_if $bad
+            ;; anything past 2^63 is too big, with or without a sign.
+            local.get $magnitude
+            i64.const 922337203685477580
+            i64.gt_u
+            br_if $bad
+            local.get $magnitude
+            i64.const 10
+            i64.mul
+            local.get $c
+            i64.extend_i32_u
+            i64.add
+            local.set $magnitude
+            local.get $at
+            i32.const 1
+            i32.add
+            local.set $at
+            br $digit
+          end
+        end
+        ;; 2^63 only fits when it is negative.
+        local.get $magnitude
+        i64.const 9223372036854775807
+        local.get $negative
+        i64.extend_i32_u
+        i64.add
+        i64.gt_u
+        br_if $bad
+        i64.const 0
+        local.get $magnitude
+        i64.sub
+        local.get $magnitude
+        local.get $negative
+        select
+        local.set $n
+        ;; an int has to fit in 32 bits.
+        local.get $long
+        i32.eqz
+        local.get $n
+        local.get $n
+        i32.wrap_i64
+        i64.extend_i32_s
+        i64.ne
+        i32.and
+        br_if $bad
+        local.get $n
+        return
+      end
+      i32.const @bad_input
+      i32.const #bad_input
+      call $tt_print
+      global.get $tt_heap
+      local.get $length
+      call $tt_print
+      i32.const @not_a_number
+      i32.const #not_a_number
+      call $tt_print
+      br $line
+    end
+    unreachable
+  )
+
+  ;; the most negative number divided by -1 wraps back to itself, where
+  ;; i64.div_s would trap.
+  (func $tt_div (param $a i64) (param $b i64) (result i64)
+    local.get $b
+    i64.eqz
+    if
+      call $tt_divide_by_zero
+    end
+    local.get $b
+    i64.const -1
+    i64.eq
+    if
+      i64.const 0
+      local.get $a
+      i64.sub
+      return
+    end
+    local.get $a
+    local.get $b
+    i64.div_s
+  )
+
+  (func $tt_rem (param $a i64) (param $b i64) (result i64)
+    local.get $b
+    i64.eqz
+    if
+      call $tt_divide_by_zero
+    end
+    local.get $a
+    local.get $b
+    i64.rem_s
+  )
+
+  ;; 1 or 0 for a branch, anything else is a runtime error.
+  (func $tt_branch (param $condition i64) (result i32)
+    local.get $condition
+    i64.const 1
+    i64.gt_u
+    if
+      i32.const @branch
+      i32.const #branch
+      call $tt_error
+      local.get $condition
+      call $tt_error_int
+      i32.const @newline
+      i32.const #newline
+      call $tt_error
+      unreachable
+    end
+    local.get $condition
+    i32.wrap_i64
+  )
+
+  ;; the offset of an element, checking the index is inside the array.
+  (func $tt_read_index (param $index i64) (param $length i64) (result i32)
+    local.get $index
+    local.get $length
+    i64.ge_u
+    if
+      i32.const @read_index
+      i32.const #read_index
+      call $tt_error
+      local.get $index
+      call $tt_error_int
+      i32.const @array_length
+      i32.const #array_length
+      call $tt_error
+      local.get $length
+      call $tt_error_int
+      i32.const @read_end
+      i32.const #read_end
+      call $tt_error
+      unreachable
+    end
+    local.get $index
+    i32.wrap_i64
+    i32.const 3
+    i32.shl
+  )
+
+  (func $tt_write_index (param $index i64) (param $length i64) (result i32)
+    local.get $index
+    local.get $length
+    i64.ge_u
+    if
+      i32.const @write_index
+      i32.const #write_index
+      call $tt_error
+      local.get $index
+      call $tt_error_int
+      i32.const @array_length
+      i32.const #array_length
+      call $tt_error
+      local.get $length
+      call $tt_error_int
+      i32.const @newline
+      i32.const #newline
+      call $tt_error
+      unreachable
+    end
+    local.get $index
+    i32.wrap_i64
+    i32.const 3
+    i32.shl
+  )
+
+  ;; room for an array of $length zeroes on the heap.
+  (func $tt_alloc (param $length i64) (result i32)
+    (local $start i32)
+    (local $end i64)
+    global.get $tt_heap
+    local.set $start
+    ;; nothing bigger fits in 32-bit memory, and checking first keeps the sums below from wrapping.
+    local.get $length
+    i64.const 536870912
+    i64.gt_u
+    if
+      call $tt_out_of_memory
+    end
+    local.get $start
+    i64.extend_i32_u
+    local.get $length
+    i64.const 3
+    i64.shl
+    i64.add
+    local.tee $end
+    i64.const 4294967288
+    i64.gt_u
+    if
+      call $tt_out_of_memory
+    end
+    local.get $end
+    memory.size
+    i64.extend_i32_u
+    i64.const 16
+    i64.shl
+    i64.gt_u
+    if
+      ;; the pages still missing, rounded up.
+      local.get $end
+      i64.const 65535
+      i64.add
+      i64.const 16
+      i64.shr_u
+      memory.size
+      i64.extend_i32_u
+      i64.sub
+      i32.wrap_i64
+      memory.grow
+      i32.const -1
+      i32.eq
+      if
+        call $tt_out_of_memory
+      end
+    end
+    ;; the memory may have been used by an array that is gone.
+    local.get $start
+    i32.const 0
+    local.get $length
+    i32.wrap_i64
+    i32.const 3
+    i32.shl
+    memory.fill
+    local.get $end
+    i32.wrap_i64
+    global.set $tt_heap
+    local.get $start
+  )
+
+  (func $tt_error_int (param $n i64)
+    (local $at i32)
+    local.get $n
+    call $tt_format
+    local.tee $at
+    i32.const 24
+    local.get $at
+    i32.sub
+    call $tt_error
+  )
+
+  (func $tt_divide_by_zero
+    i32.const @divide
+    i32.const #divide
+    call $tt_error
+    unreachable
+  )
+
+  (func $tt_out_of_memory
+    i32.const @memory
+    i32.const #memory
+    call $tt_error
+    unreachable
+  )
+"#;
@@ -0,0 +1,627 @@
+// src/backend/x86.rs
+// Lowers the IR to x86-64 assembly for the GNU assembler, in AT&T syntax with
+// the System V calling convention, and links it against the C library with cc
+// into an ELF executable:
+//
+// let assembly = x86::generate(&module);
+// x86::link(&assembly, "a.out")?;
+//
+// Every variable gets 8 bytes in its function's stack frame, with ints kept
+// sign extended from 32 bits, and arrays are allocated with calloc when the
+// function is called, like the interpreter does. The runtime at the end does
+// the input and output, and reports runtime errors on standard error with the
+// interpreter's messages before exiting with 7.
+//
+// The module has to have loaded with Program::load first, which checks
+// everything generate relies on, like every variable being declared.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::phases::ir::{BinaryOp, Function, Instruction, Module, Operand, Type};
+
+const ARGUMENT_REGISTERS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+// where a variable lives in the frame. an array's slot holds the pointer to its elements.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    offset: i64,
+    long: bool,
+    // the number of elements, for arrays.
+    length: Option<i64>,
+}
+
+struct Generator<'m> {
+    out: String,
+    // the label and length of each string constant.
+    strings: HashMap<&'m str, (usize, usize)>,
+    // for the labels inside the code of a single instruction.
+    next_label: usize,
+}
+
+// the assembly for a whole module, runtime included.
+pub fn generate(module: &Module) -> String {
+    let mut generator = Generator { out: String::new(), strings: HashMap::new(), next_label: 0 };
+    generator.out += "# generated by the Teh Terik compiler\n";
+    if !module.strings.is_empty() {
+        generator.out += "    .section .rodata\n";
+    }
+    for (i, (name, text)) in module.strings.iter().enumerate() {
+        generator.label(&format!(".Ltt_str{i}"));
+        generator.code(format!(".ascii \"{}\"", escape(text)));
+        generator.strings.insert(name, (i, text.len()));
+    }
+
+    generator.out += "\n    .text\n";
+    for (index, function) in module.functions.iter().enumerate() {
+        generator.function(index, function);
+    }
+    generator.out += RUNTIME;
+    generator.out
+}
+
+// assembles and links the output of generate into an executable with cc.
+pub fn link(assembly: &str, executable: &str) -> Result<(), String> {
+    let mut cc = match Command::new("cc").args(["-x", "assembler", "-", "-o", executable]).stdin(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+    Ok(cc) => cc,
+    Err(e) => return Err(format!("could not run cc: {e}")),
+    };
+    if let Some(mut input) = cc.stdin.take() {
+        if let Err(e) = input.write_all(assembly.as_bytes()) {
+            return Err(format!("could not give cc the assembly: {e}"));
+        }
+    }
+    let output = match cc.wait_with_output() {
+    Ok(output) => output,
+    Err(e) => return Err(format!("could not run cc: {e}")),
+    };
+    if !output.status.success() {
+        return Err(format!("cc failed: {}", String::from_utf8_lossy(&output.stderr).trim_end()));
+    }
+    Ok(())
+}
+
+// names in hand-written IR can have any characters but whitespace, so
+// anything the assembler would not take is written as '.' and its hex.
+fn mangle(name: &str) -> String {
+    let mut mangled = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'_' {
+            mangled.push(byte as char);
+        } else {
+            mangled += &format!(".{byte:02x}");
+        }
+    }
+    mangled
+}
+
+// prefixed, so a function can be called main or printf.
+fn symbol(function: &str) -> String {
+    format!("tt_{}", mangle(function))
+}
+
+// the text of a string constant inside .ascii "...".
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for byte in text.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'"' && byte != b'\\' {
+            escaped.push(byte as char);
+        } else {
+            escaped += &format!("\\{byte:03o}");
+        }
+    }
+    escaped
+}
+
+impl<'m> Generator<'m> {
+    fn code(&mut self, text: String) {
+        self.out += "    ";
+        self.out += &text;
+        self.out += "\n";
+    }
+
+    fn label(&mut self, name: &str) {
+        self.out += name;
+        self.out += ":\n";
+    }
+
+    fn new_label(&mut self) -> String {
+        self.next_label += 1;
+        format!(".Ltt{}", self.next_label)
+    }
+
+    fn immediate(&mut self, num: i64, register: &str) {
+        if num == num as i32 as i64 {
+            self.code(format!("movq ${num}, {register}"));
+        } else {
+            self.code(format!("movabsq ${num}, {register}"));
+        }
+    }
+
+    fn load(&mut self, slots: &HashMap<&str, Slot>, operand: &Operand, register: &str) {
+        match operand {
+        Operand::Num(num) => self.immediate(*num, register),
+        Operand::Var(name) => self.code(format!("movq {}(%rbp), {register}", slots[name.as_str()].offset)),
+        }
+    }
+
+    // writes %rax to a variable, cut down to 32 bits for an int.
+    fn store(&mut self, slots: &HashMap<&str, Slot>, dest: &str) {
+        let slot = slots[dest];
+        if !slot.long {
+            self.code(String::from("cltq"));
+        }
+        self.code(format!("movq %rax, {}(%rbp)", slot.offset));
+    }
+
+    fn function(&mut self, index: usize, function: &'m Function) {
+        // the parameters take the first slots, then everything declared in the body.
+        let mut slots: HashMap<&str, Slot> = HashMap::new();
+        let mut declared: Vec<&str> = vec![];
+        for (typ, name) in &function.parameters {
+            let offset = -8 * (slots.len() as i64 + 1);
+            slots.insert(name, Slot { offset, long: *typ == Type::Long, length: None });
+        }
+        for instruction in &function.body {
+            let (typ, name, length) = match instruction {
+            Instruction::Declare(typ, name) => (typ, name, None),
+            Instruction::DeclareArray(typ, name, length) => (typ, name, Some(*length)),
+            _ => continue,
+            };
+            let offset = -8 * (slots.len() as i64 + 1);
+            slots.insert(name, Slot { offset, long: *typ == Type::Long, length });
+            declared.push(name);
+        }
+        // one more slot keeps the return value while the arrays are freed.
+        let result = -8 * (slots.len() as i64 + 1);
+        let frame = (8 * (slots.len() + 1)).div_ceil(16) * 16;
+        let arrays: Vec<&str> = declared.iter().copied().filter(|name| slots[name].length.is_some()).collect();
+
+        self.out += "\n";
+        self.code(format!("# %func {}", function.name));
+        self.label(&symbol(&function.name));
+        self.code(String::from("pushq %rbp"));
+        self.code(String::from("movq %rsp, %rbp"));
+        self.code(format!("subq ${frame}, %rsp"));
+        for (i, (_, name)) in function.parameters.iter().enumerate() {
+            match ARGUMENT_REGISTERS.get(i) {
+            Some(register) => self.code(format!("movq {register}, %rax")),
+            None => self.code(format!("movq {}(%rbp), %rax", 16 + 8 * (i - ARGUMENT_REGISTERS.len()))),
+            }
+            self.store(&slots, name);
+        }
+        // every variable starts out as 0, even if a jump skips its declaration.
+        for name in &declared {
+            match slots[name].length {
+            None => self.code(format!("movq $0, {}(%rbp)", slots[name].offset)),
+            Some(length) => {
+                self.immediate(length, "%rdi");
+                self.code(String::from("movl $8, %esi"));
+                self.code(String::from("call calloc@PLT"));
+                self.code(String::from("testq %rax, %rax"));
+                self.code(String::from("je __tt_out_of_memory"));
+                self.code(format!("movq %rax, {}(%rbp)", slots[name].offset));
+            }
+            }
+        }
+
+        for instruction in &function.body {
+            self.instruction(index, &slots, instruction);
+        }
+
+        // falling off the end returns 0.
+        self.code(String::from("xorl %eax, %eax"));
+        self.label(&format!(".L{index}.return"));
+        if !arrays.is_empty() {
+            self.code(format!("movq %rax, {result}(%rbp)"));
+            for name in &arrays {
+                self.code(format!("movq {}(%rbp), %rdi", slots[name].offset));
+                self.code(String::from("call free@PLT"));
+            }
+            self.code(format!("movq {result}(%rbp), %rax"));
+        }
+        self.code(String::from("leave"));
+        self.code(String::from("ret"));
+    }
+
+    fn instruction(&mut self, index: usize, slots: &HashMap<&str, Slot>, instruction: &Instruction) {
+        if !matches!(instruction, Instruction::Label(_)) {
+            self.code(format!("# {instruction}"));
+        }
+        match instruction {
+        Instruction::Declare(_, name) => self.code(format!("movq $0, {}(%rbp)", slots[name.as_str()].offset)),
+
+        Instruction::DeclareArray(_, name, length) => {
+            self.code(format!("movq {}(%rbp), %rdi", slots[name.as_str()].offset));
+            self.code(String::from("xorl %esi, %esi"));
+            self.immediate(length * 8, "%rdx");
+            self.code(String::from("call memset@PLT"));
+        }
+
+        Instruction::Call(dest, function, arguments) => {
+            // past the sixth, arguments go on the stack, which has to stay 16 byte aligned.
+            let pushed = arguments.len().saturating_sub(ARGUMENT_REGISTERS.len());
+            let padding = pushed % 2 == 1;
+            if padding {
+                self.code(String::from("subq $8, %rsp"));
+            }
+            for argument in arguments.iter().skip(ARGUMENT_REGISTERS.len()).rev() {
+                self.load(slots, argument, "%rax");
+                self.code(String::from("pushq %rax"));
+            }
+            for (argument, register) in arguments.iter().zip(ARGUMENT_REGISTERS) {
+                self.load(slots, argument, register);
+            }
+            self.code(format!("call {}", symbol(function)));
+            let popped = 8 * (pushed + padding as usize);
+            if popped > 0 {
+                self.code(format!("addq ${popped}, %rsp"));
+            }
+            self.store(slots, dest);
+        }
+
+        Instruction::Return(value) => {
+            self.load(slots, value, "%rax");
+            self.code(format!("jmp .L{index}.return"));
+        }
+
+        Instruction::Out(value) => {
+            self.load(slots, value, "%rdi");
+            self.code(String::from("call __tt_out"));
+        }
+
+        Instruction::OutStr(name) => {
+            let (label, length) = self.strings[name.as_str()];
+            self.code(format!("leaq .Ltt_str{label}(%rip), %rdi"));
+            self.code(format!("movq ${length}, %rsi"));
+            self.code(String::from("call __tt_out_str"));
+        }
+
+        Instruction::OutInt(value) => {
+            self.load(slots, value, "%rdi");
+            self.code(String::from("call __tt_out_int"));
+        }
+
+        Instruction::OutLine => {
+            self.code(String::from("movl $10, %edi"));
+            self.code(String::from("call putchar@PLT"));
+        }
+
+        Instruction::In(dest) => {
+            self.code(format!("movl ${}, %edi", slots[dest.as_str()].long as i32));
+            self.code(String::from("call __tt_input"));
+            self.store(slots, dest);
+        }
+
+        Instruction::OutChar(value) => {
+            self.load(slots, value, "%rdi");
+            self.code(String::from("movzbl %dil, %edi"));
+            self.code(String::from("call putchar@PLT"));
+        }
+
+        Instruction::InChar(dest) => {
+            self.code(String::from("call __tt_inputc"));
+            self.store(slots, dest);
+        }
+
+        Instruction::Mov(dest, src) => {
+            self.load(slots, src, "%rax");
+            self.store(slots, dest);
+        }
+
+        Instruction::Load(dest, array, element) => {
+            let slot = slots[array.as_str()];
+            self.bounds_check(slots, slot, element, "__tt_bad_read");
+            self.code(format!("movq {}(%rbp), %rax", slot.offset));
+            self.code(String::from("movq (%rax,%rcx,8), %rax"));
+            self.store(slots, dest);
+        }
+
+        Instruction::Store(array, element, src) => {
+            let slot = slots[array.as_str()];
+            self.bounds_check(slots, slot, element, "__tt_bad_write");
+            self.load(slots, src, "%rax");
+            if !slot.long {
+                self.code(String::from("cltq"));
+            }
+            self.code(format!("movq {}(%rbp), %rdx", slot.offset));
+            self.code(String::from("movq %rax, (%rdx,%rcx,8)"));
+        }
+
+        Instruction::Binary(op, dest, src1, src2) => {
+            self.load(slots, src1, "%rax");
+            self.load(slots, src2, "%rcx");
+            match op {
+            // the low 32 bits come out the same for ints, and store cuts them down.
+            BinaryOp::Add => self.code(String::from("addq %rcx, %rax")),
+            BinaryOp::Sub => self.code(String::from("subq %rcx, %rax")),
+            BinaryOp::Mult => self.code(String::from("imulq %rcx, %rax")),
+            BinaryOp::Div | BinaryOp::Mod => self.division(*op, slots[dest.as_str()].long),
+            BinaryOp::LessThan => self.compare("setl"),
+            BinaryOp::LessEqual => self.compare("setle"),
+            BinaryOp::NotEqual => self.compare("setne"),
+            BinaryOp::Equal => self.compare("sete"),
+            BinaryOp::GreaterEqual => self.compare("setge"),
+            BinaryOp::GreaterThan => self.compare("setg"),
+            }
+            self.store(slots, dest);
+        }
+
+        Instruction::Label(label) => self.label(&format!(".L{index}_{}", mangle(&label.0))),
+        Instruction::Jump(label) => self.code(format!("jmp .L{index}_{}", mangle(&label.0))),
+
+        // anything but 0 or 1 is a runtime error, with the value in %rax.
+        Instruction::BranchIf(condition, label) => {
+            self.load(slots, condition, "%rax");
+            self.code(String::from("cmpq $1, %rax"));
+            self.code(format!("je .L{index}_{}", mangle(&label.0)));
+            self.code(String::from("testq %rax, %rax"));
+            self.code(String::from("jne __tt_bad_branch"));
+        }
+
+        Instruction::BranchIfn(condition, label) => {
+            self.load(slots, condition, "%rax");
+            self.code(String::from("testq %rax, %rax"));
+            self.code(format!("je .L{index}_{}", mangle(&label.0)));
+            self.code(String::from("cmpq $1, %rax"));
+            self.code(String::from("jne __tt_bad_branch"));
+        }
+        }
+    }
+
+    // leaves the element index in %rcx, and the length in %rdx for the error.
+    fn bounds_check(&mut self, slots: &HashMap<&str, Slot>, array: Slot, element: &Operand, error: &str) {
+        self.load(slots, element, "%rcx");
+        self.immediate(array.length.unwrap_or(0), "%rdx");
+        // unsigned, so a negative index is out of bounds too.
+        self.code(String::from("cmpq %rdx, %rcx"));
+        self.code(format!("jae {error}"));
+    }
+
+    fn compare(&mut self, set: &str) {
+        self.code(String::from("cmpq %rcx, %rax"));
+        self.code(format!("{set} %al"));
+        self.code(String::from("movzbl %al, %eax"));
+    }
+
+    // %rax / %rcx. ints divide their low 32 bits like the interpreter, and
+    // MIN / -1 wraps instead of trapping like idiv would.
+    fn division(&mut self, op: BinaryOp, long: bool) {
+        let (divide, after) = (self.new_label(), self.new_label());
+        if long {
+            self.code(String::from("testq %rcx, %rcx"));
+            self.code(String::from("je __tt_divide_by_zero"));
+            self.code(String::from("cmpq $-1, %rcx"));
+            self.code(format!("jne {divide}"));
+            if op == BinaryOp::Div {
+                self.code(String::from("negq %rax"));
+            } else {
+                self.code(String::from("xorl %eax, %eax"));
+            }
+            self.code(format!("jmp {after}"));
+            self.label(&divide);
+            self.code(String::from("cqto"));
+            self.code(String::from("idivq %rcx"));
+            if op == BinaryOp::Mod {
+                self.code(String::from("movq %rdx, %rax"));
+            }
+        } else {
+            self.code(String::from("testl %ecx, %ecx"));
+            self.code(String::from("je __tt_divide_by_zero"));
+            self.code(String::from("cmpl $-1, %ecx"));
+            self.code(format!("jne {divide}"));
+            if op == BinaryOp::Div {
+                self.code(String::from("negl %eax"));
+            } else {
+                self.code(String::from("xorl %eax, %eax"));
+            }
+            self.code(format!("jmp {after}"));
+            self.label(&divide);
+            self.code(String::from("cltd"));
+            self.code(String::from("idivl %ecx"));
+            if op == BinaryOp::Mod {
+                self.code(String::from("movl %edx, %eax"));
+            }
+        }
+        self.label(&after);
+    }
+}
+
+// the same for every program. the error routines are jumped to from function
+// bodies, where the stack is aligned for calling into the C library.
+const RUNTIME: &str = r#"
+# runtime
+    .section .rodata
+.Ltt_format_out:
+    .string "%ld\n"
+.Ltt_format_int:
+    .string "%ld"
+.Ltt_format_bad_input:
+    .string "User Input Error. '%s' is not a valid number.\n"
+.Ltt_error_end_of_input:
+    .string "Runtime Error. Reached the end of the input while reading a number for '%%input'.\n"
+.Ltt_error_divide:
+    .string "Runtime Error. Attempt to divide by zero.\n"
+.Ltt_error_branch:
+    .string "Runtime Error. Branch on a variable that is neither 0 or 1. The value is: %ld\n"
+.Ltt_error_read:
+    .string "Runtime Error: Array out of bounds. Index %ld. Array Length %ld.\n"
+.Ltt_error_write:
+    .string "Runtime Error: Array out of bounds. Value %ld. Array Length %ld\n"
+.Ltt_error_memory:
+    .string "Runtime Error. Out of memory.\n"
+
+    .text
+    .globl main
+main:
+    subq $8, %rsp
+    call tt_main
+    addq $8, %rsp
+    ret
+
+# %out: the number in %rdi and a newline.
+__tt_out:
+    subq $8, %rsp
+    movq %rdi, %rsi
+    leaq .Ltt_format_out(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $8, %rsp
+    ret
+
+# %out_int: the number in %rdi.
+__tt_out_int:
+    subq $8, %rsp
+    movq %rdi, %rsi
+    leaq .Ltt_format_int(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $8, %rsp
+    ret
+
+# %out_str: %rsi bytes from %rdi.
+__tt_out_str:
+    subq $8, %rsp
+    movq stdout@GOTPCREL(%rip), %rax
+    movq (%rax), %rcx
+    movq %rsi, %rdx
+    movl $1, %esi
+    call fwrite@PLT
+    addq $8, %rsp
+    ret
+
+# %inputc: the next byte in %rax, or -1 at the end of the input.
+__tt_inputc:
+    subq $8, %rsp
+    xorl %edi, %edi
+    call fflush@PLT
+    call getchar@PLT
+    cltq
+    addq $8, %rsp
+    ret
+
+# %input: reads lines until one is a number that fits, 64 bits if %edi is 1
+# and 32 bits otherwise, and gives it back in %rax.
+__tt_input:
+    pushq %rbx
+    pushq %r12
+    pushq %r13
+    # 0(%rsp) is the end of the number, 8(%rsp) and 16(%rsp) the line for getline.
+    subq $32, %rsp
+    movl %edi, %ebx
+    movq $0, 8(%rsp)
+    movq $0, 16(%rsp)
+.Ltt_input_line:
+    xorl %edi, %edi
+    call fflush@PLT
+    leaq 16(%rsp), %rdi
+    leaq 8(%rsp), %rsi
+    movq stdin@GOTPCREL(%rip), %rax
+    movq (%rax), %rdx
+    call getline@PLT
+    testq %rax, %rax
+    jle .Ltt_input_end
+    movq 16(%rsp), %r12
+    # drop the newline and any other space at the end.
+.Ltt_input_trim:
+    testq %rax, %rax
+    je .Ltt_input_trimmed
+    movzbl -1(%r12,%rax), %ecx
+    cmpl $32, %ecx
+    je .Ltt_input_space
+    subl $9, %ecx
+    cmpl $4, %ecx
+    ja .Ltt_input_trimmed
+.Ltt_input_space:
+    decq %rax
+    jmp .Ltt_input_trim
+.Ltt_input_trimmed:
+    movb $0, (%r12,%rax)
+    # strtoll would skip spaces at the start, so only a sign or a digit can start a number.
+    movzbl (%r12), %ecx
+    cmpl $43, %ecx
+    je .Ltt_input_parse
+    cmpl $45, %ecx
+    je .Ltt_input_parse
+    subl $48, %ecx
+    cmpl $9, %ecx
+    ja .Ltt_input_bad
+.Ltt_input_parse:
+    call __errno_location@PLT
+    movl $0, (%rax)
+    movq %r12, %rdi
+    movq %rsp, %rsi
+    movl $10, %edx
+    call strtoll@PLT
+    movq %rax, %r13
+    movq (%rsp), %rcx
+    cmpb $0, (%rcx)
+    jne .Ltt_input_bad
+    call __errno_location@PLT
+    cmpl $0, (%rax)
+    jne .Ltt_input_bad
+    testl %ebx, %ebx
+    jne .Ltt_input_done
+    movslq %r13d, %rax
+    cmpq %rax, %r13
+    jne .Ltt_input_bad
+.Ltt_input_done:
+    movq %r12, %rdi
+    call free@PLT
+    movq %r13, %rax
+    addq $32, %rsp
+    popq %r13
+    popq %r12
+    popq %rbx
+    ret
+.Ltt_input_bad:
+    leaq .Ltt_format_bad_input(%rip), %rdi
+    movq %r12, %rsi
+    xorl %eax, %eax
+    call printf@PLT
+    jmp .Ltt_input_line
+.Ltt_input_end:
+    leaq .Ltt_error_end_of_input(%rip), %rdi
+    jmp __tt_fail
+
+__tt_divide_by_zero:
+    leaq .Ltt_error_divide(%rip), %rdi
+    jmp __tt_fail
+
+# the value is in %rax.
+__tt_bad_branch:
+    movq %rax, %rsi
+    leaq .Ltt_error_branch(%rip), %rdi
+    jmp __tt_fail
+
+# the index is in %rcx and the length in %rdx.
+__tt_bad_read:
+    movq %rcx, %rsi
+    leaq .Ltt_error_read(%rip), %rdi
+    jmp __tt_fail
+
+__tt_bad_write:
+    movq %rcx, %rsi
+    leaq .Ltt_error_write(%rip), %rdi
+    jmp __tt_fail
+
+__tt_out_of_memory:
+    leaq .Ltt_error_memory(%rip), %rdi
+    jmp __tt_fail
+
+# prints the printf format in %rdi, with up to two numbers from %rsi and
+# %rdx, to standard error and exits with 7 like a runtime error in run.
+__tt_fail:
+    movq %rdx, %rcx
+    movq %rsi, %rdx
+    movq %rdi, %rsi
+    movq stderr@GOTPCREL(%rip), %rax
+    movq (%rax), %rdi
+    xorl %eax, %eax
+    call fprintf@PLT
+    movl $7, %edi
+    call exit@PLT
+
+    .section .note.GNU-stack,"",@progbits
+"#;
@@ -0,0 +1,255 @@
+// src/coverage.rs
+// Records which lines of a .tt program ran, and which way each conditional
+// branch went, then writes it as an lcov tracefile (for genhtml and most
+// editors) or a summary per function.
+//
+// let mut coverage = Coverage::new(&module);
+// program.run_with_hook(&mut vm, &mut coverage)?;
+// coverage.write_lcov(&mut file, "examples/primes.tt")?;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::interpreter::{Control, Hook, State};
+use crate::phases::ir::{Instruction, Module, Operand, Span};
+
+struct FunctionCoverage {
+    name: String,
+    spans: Vec<Option<Span>>,
+    // how often each instruction ran, and for branches how often the condition
+    // was true and false.
+    counts: Vec<u64>,
+    branches: Vec<Option<[u64; 2]>>,
+    calls: u64,
+}
+
+// one line of a function, or one conditional branch on it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineCount {
+    pub line: usize,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BranchCount {
+    pub line: usize,
+    pub when_true: u64,
+    pub when_false: u64,
+}
+
+pub struct Coverage {
+    functions: Vec<FunctionCoverage>,
+    ids: HashMap<String, usize>,
+    depth: usize,
+    // set when the program ran an instruction the module does not have.
+    mismatch: Option<String>,
+}
+
+fn percent(hit: usize, found: usize) -> String {
+    if found == 0 {
+        String::from("-")
+    } else {
+        format!("{:.1}%", 100.0 * hit as f64 / found as f64)
+    }
+}
+
+impl FunctionCoverage {
+    // each source line with code on it, with the most times any of its instructions ran.
+    fn lines(&self) -> Vec<LineCount> {
+        let mut lines: BTreeMap<usize, u64> = BTreeMap::new();
+        for (span, count) in self.spans.iter().zip(&self.counts) {
+            if let Some(span) = span {
+                let line = lines.entry(span.line).or_insert(0);
+                *line = (*line).max(*count);
+            }
+        }
+        lines.into_iter().map(|(line, count)| LineCount { line, count }).collect()
+    }
+
+    fn branches(&self) -> Vec<BranchCount> {
+        let mut branches: Vec<BranchCount> = vec![];
+        for (span, outcomes) in self.spans.iter().zip(&self.branches) {
+            if let (Some(span), Some([when_true, when_false])) = (span, outcomes) {
+                branches.push(BranchCount { line: span.line, when_true: *when_true, when_false: *when_false });
+            }
+        }
+        branches
+    }
+
+    // the line the function starts on, as far as its code knows.
+    fn first_line(&self) -> Option<usize> {
+        self.spans.iter().flatten().map(|span| span.line).min()
+    }
+}
+
+impl Coverage {
+    // coverage for the functions of a module, all not run yet.
+    pub fn new(module: &Module) -> Coverage {
+        let mut coverage = Coverage { functions: vec![], ids: HashMap::new(), depth: 0, mismatch: None };
+        for function in &module.functions {
+            let branches = function.body.iter().map(|instruction| match instruction {
+            Instruction::BranchIf(_, _) | Instruction::BranchIfn(_, _) => Some([0, 0]),
+            _ => None,
+            }).collect();
+            coverage.ids.insert(function.name.clone(), coverage.functions.len());
+            coverage.functions.push(FunctionCoverage {
+                name: function.name.clone(),
+                spans: (0..function.body.len()).map(|i| function.span(i)).collect(),
+                // one more for the end of the function.
+                counts: vec![0; function.body.len() + 1],
+                branches,
+                calls: 0,
+            });
+        }
+        coverage
+    }
+
+    fn function(&self, name: &str) -> Option<&FunctionCoverage> {
+        self.ids.get(name).map(|id| &self.functions[*id])
+    }
+
+    // the first instruction that ran but is not in the module, when the program
+    // was loaded from a different one. its counts are left out.
+    pub fn mismatch(&self) -> Option<&str> {
+        self.mismatch.as_deref()
+    }
+
+    pub fn calls(&self, function: &str) -> Option<u64> {
+        self.function(function).map(|f| f.calls)
+    }
+
+    pub fn lines(&self, function: &str) -> Vec<LineCount> {
+        self.function(function).map_or(vec![], |f| f.lines())
+    }
+
+    pub fn branches(&self, function: &str) -> Vec<BranchCount> {
+        self.function(function).map_or(vec![], |f| f.branches())
+    }
+
+    // writes an lcov tracefile for the source file the module was compiled from.
+    pub fn write_lcov(&self, output: &mut dyn Write, source: &str) -> std::io::Result<()> {
+        writeln!(output, "TN:")?;
+        writeln!(output, "SF:{}", source)?;
+
+        let mut lines: BTreeMap<usize, u64> = BTreeMap::new();
+        let mut functions_hit = 0;
+        for function in &self.functions {
+            if let Some(line) = function.first_line() {
+                writeln!(output, "FN:{},{}", line, function.name)?;
+            }
+        }
+        for function in &self.functions {
+            if function.first_line().is_some() {
+                writeln!(output, "FNDA:{},{}", function.calls, function.name)?;
+                if function.calls > 0 {
+                    functions_hit += 1;
+                }
+            }
+            for count in function.lines() {
+                let line = lines.entry(count.line).or_insert(0);
+                *line = (*line).max(count.count);
+            }
+        }
+        let functions_found = self.functions.iter().filter(|f| f.first_line().is_some()).count();
+        writeln!(output, "FNF:{}", functions_found)?;
+        writeln!(output, "FNH:{}", functions_hit)?;
+
+        // each branch has two outcomes, numbered 0 for true and 1 for false.
+        let (mut branches_found, mut branches_hit) = (0, 0);
+        let mut block = 0;
+        for function in &self.functions {
+            for branch in function.branches() {
+                // lcov shows '-' for a branch whose line never ran at all.
+                let reached = branch.when_true + branch.when_false > 0;
+                for (outcome, count) in [branch.when_true, branch.when_false].iter().enumerate() {
+                    let taken = if reached { count.to_string() } else { String::from("-") };
+                    writeln!(output, "BRDA:{},{},{},{}", branch.line, block, outcome, taken)?;
+                    branches_found += 1;
+                    if *count > 0 {
+                        branches_hit += 1;
+                    }
+                }
+                block += 1;
+            }
+        }
+        writeln!(output, "BRF:{}", branches_found)?;
+        writeln!(output, "BRH:{}", branches_hit)?;
+
+        for (line, count) in &lines {
+            writeln!(output, "DA:{},{}", line, count)?;
+        }
+        writeln!(output, "LF:{}", lines.len())?;
+        writeln!(output, "LH:{}", lines.values().filter(|count| **count > 0).count())?;
+        writeln!(output, "end_of_record")?;
+        Ok(())
+    }
+
+    // writes how much of each function ran, and the lines that did not.
+    pub fn write_summary(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(output, "{:<20} {:>8} {:>8} {:>10} {:>8}  Missed lines", "Function", "Lines", "%", "Branches", "%")?;
+        let (mut all_lines, mut all_lines_hit, mut all_branches, mut all_branches_hit) = (0, 0, 0, 0);
+        for function in &self.functions {
+            let lines = function.lines();
+            let lines_hit = lines.iter().filter(|l| l.count > 0).count();
+            let branches = function.branches();
+            let branches_hit: usize = branches.iter().map(|b| (b.when_true > 0) as usize + (b.when_false > 0) as usize).sum();
+            let missed: Vec<String> = lines.iter().filter(|l| l.count == 0).map(|l| l.line.to_string()).collect();
+
+            let row = format!("{:<20} {:>8} {:>8} {:>10} {:>8}  {}", function.name,
+                format!("{}/{}", lines_hit, lines.len()), percent(lines_hit, lines.len()),
+                format!("{}/{}", branches_hit, 2 * branches.len()), percent(branches_hit, 2 * branches.len()),
+                missed.join(", "));
+            writeln!(output, "{}", row.trim_end())?;
+
+            all_lines += lines.len();
+            all_lines_hit += lines_hit;
+            all_branches += 2 * branches.len();
+            all_branches_hit += branches_hit;
+        }
+        writeln!(output, "{:<20} {:>8} {:>8} {:>10} {:>8}", "Total",
+            format!("{}/{}", all_lines_hit, all_lines), percent(all_lines_hit, all_lines),
+            format!("{}/{}", all_branches_hit, all_branches), percent(all_branches_hit, all_branches))?;
+        Ok(())
+    }
+}
+
+impl Hook for Coverage {
+    fn before(&mut self, state: &State, _input: &mut dyn BufRead, _output: &mut dyn Write) -> Control {
+        let frame = state.frame(0).unwrap();
+        let id = match self.ids.get(frame.function()) {
+        Some(id) => *id,
+        // a program loaded from a different module.
+        None => return Control::Continue,
+        };
+        let function = &mut self.functions[id];
+
+        if state.depth() > self.depth {
+            function.calls += 1;
+        }
+        self.depth = state.depth();
+
+        let index = frame.index();
+        match function.counts.get_mut(index) {
+        Some(count) => *count += 1,
+        None => {
+            if self.mismatch.is_none() {
+                self.mismatch = Some(format!("{} has no instruction {}, the program is not the one the coverage was made for.", function.name, index));
+            }
+            return Control::Continue;
+        },
+        }
+        if let Some(Instruction::BranchIf(condition, _) | Instruction::BranchIfn(condition, _)) = frame.instruction() {
+            let value = match condition {
+            Operand::Num(num) => Some(*num),
+            Operand::Var(name) => frame.variable(name),
+            };
+            // anything else than 0 or 1 is a runtime error, not an outcome.
+            if let (Some(Some(outcomes)), Some(value @ (0 | 1))) = (function.branches.get_mut(index), value) {
+                outcomes[1 - value as usize] += 1;
+            }
+        }
+        Control::Continue
+    }
+}
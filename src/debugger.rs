@@ -0,0 +1,330 @@
+// src/debugger.rs
+// A command prompt for stepping through a program as it runs. It stops
+// before the first instruction of main and reads commands from its own reader,
+// or from the program's input when it has none:
+//
+// (debug) break 12        stop when source line 12 starts running
+// (debug) break fib       stop when fib is called
+// (debug) break fib:3     stop at label :3 in fib, or :3 in any function
+// (debug) continue
+//
+// let mut debugger = Debugger::new().with_commands(Box::new(io::stdin().lock()));
+// program.run_with_hook(&mut vm, &mut debugger)?;
+
+use std::fmt;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::interpreter::{Control, FrameView, Hook, State};
+use crate::phases::ir::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    // a line in the .tt source.
+    Line(usize),
+    Function(String),
+    // an IR label, in one function or in any of them.
+    Label(Option<String>, String),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        Breakpoint::Line(line) => write!(f, "line {}", line),
+        Breakpoint::Function(name) => write!(f, "function {}", name),
+        Breakpoint::Label(Some(function), label) => write!(f, "label :{} in {}", label, function),
+        Breakpoint::Label(None, label) => write!(f, "label :{}", label),
+        }
+    }
+}
+
+// how far to run before showing the prompt again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    // stop once a new source line starts, going into calls. the depth and
+    // position are where the step started.
+    Step(usize, Option<Span>),
+    // stop once a new source line starts at this depth or above, running calls through.
+    Next(usize, Option<Span>),
+    // stop once the call at this depth has returned.
+    Finish(usize),
+    // only stop at breakpoints.
+    Continue,
+}
+
+pub struct Debugger {
+    breakpoints: Vec<Option<Breakpoint>>,
+    mode: Mode,
+    // the .tt source, to show the line being run.
+    source: Vec<String>,
+    // the depth and source position of the last instruction, to notice a new line starting.
+    last: Option<(usize, Option<Span>)>,
+    // where the commands come from, instead of the program's input.
+    commands: Option<Box<dyn BufRead>>,
+}
+
+const HELP: &str = "\
+Commands:
+    break LINE | FUNCTION | [FUNCTION]:LABEL   set a breakpoint (b)
+    delete [N]                                 remove breakpoint N, or all of them
+    breakpoints                                list the breakpoints
+    step                                       run to the next source line, going into calls (s)
+    next                                       run to the next source line, running calls through (n)
+    finish                                     run until the current function returns
+    continue                                   run until a breakpoint (c)
+    print [NAME]                               show a variable or array, or all of them (p)
+    backtrace                                  show the call stack (bt)
+    quit                                       stop the program (q)
+";
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: vec![],
+            // stop before the first instruction
+            mode: Mode::Step(0, None),
+            source: vec![],
+            last: None,
+            commands: None,
+        }
+    }
+
+    // a debugger that shows the source line of each stop.
+    pub fn with_source(code: &str) -> Debugger {
+        let mut debugger = Debugger::new();
+        debugger.source = code.lines().map(String::from).collect();
+        debugger
+    }
+
+    // reads the commands from this reader, leaving the program's input to the program.
+    pub fn with_commands(mut self, commands: Box<dyn BufRead>) -> Debugger {
+        self.commands = Some(commands);
+        self
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len()
+    }
+
+    // the number of the first breakpoint the program is at, counted from 1.
+    fn breakpoint_hit(&self, state: &State, frame: &FrameView) -> Option<usize> {
+        let span = frame.entry().span;
+        // a call starting, or a source line starting that was not running before.
+        let entered = match self.last {
+        Some((depth, _)) => state.depth() > depth,
+        None => true,
+        };
+        let new_line = match (self.last, span) {
+        (Some((depth, Some(last))), Some(span)) => depth != state.depth() || last.line != span.line,
+        (_, Some(_)) => true,
+        (_, None) => false,
+        };
+
+        for (i, breakpoint) in self.breakpoints.iter().enumerate() {
+            let hit = match breakpoint {
+            Some(Breakpoint::Line(line)) => new_line && span.map(|s| s.line) == Some(*line),
+            Some(Breakpoint::Function(name)) => entered && frame.function() == name,
+            Some(Breakpoint::Label(function, label)) => {
+                frame.label() == Some(label.as_str()) && (function.is_none() || function.as_deref() == Some(frame.function()))
+            }
+            None => false,
+            };
+            if hit {
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+
+    fn show_location(&self, frame: &FrameView, output: &mut dyn Write) {
+        let entry = frame.entry();
+        let _ = writeln!(output, "Stopped {}", entry);
+        if let Some(span) = entry.span {
+            if let Some(text) = self.source.get(span.line - 1) {
+                let _ = writeln!(output, "{:>5} | {}", span.line, text);
+            }
+        }
+        match frame.instruction() {
+        Some(instruction) => { let _ = writeln!(output, "      => {}", instruction); }
+        None => { let _ = writeln!(output, "      => %endfunc"); }
+        }
+    }
+
+    fn print(&self, frame: &FrameView, name: &str, output: &mut dyn Write) {
+        if let Some(value) = frame.variable(name) {
+            let _ = writeln!(output, "{} = {}", name, value);
+        } else if let Some(values) = frame.array(name) {
+            let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            let _ = writeln!(output, "{} = [{}]", name, values.join(", "));
+        } else {
+            let _ = writeln!(output, "No variable or array '{}' in {}.", name, frame.function());
+        }
+    }
+
+    fn parse_breakpoint(text: &str) -> Option<Breakpoint> {
+        if let Ok(line) = text.parse::<usize>() {
+            return Some(Breakpoint::Line(line));
+        }
+        if let Some((function, label)) = text.split_once(':') {
+            if label.is_empty() {
+                return None;
+            }
+            let function = if function.is_empty() { None } else { Some(String::from(function)) };
+            return Some(Breakpoint::Label(function, String::from(label)));
+        }
+        if text.is_empty() {
+            return None;
+        }
+        Some(Breakpoint::Function(String::from(text)))
+    }
+
+    // reads commands until one of them runs the program again.
+    fn prompt(&mut self, state: &State, frame: &FrameView, input: &mut dyn BufRead, output: &mut dyn Write) -> Control {
+        loop {
+            let _ = write!(output, "(debug) ");
+            let _ = output.flush();
+            let mut line = String::new();
+            let read = match self.commands.as_mut() {
+            Some(commands) => commands.read_line(&mut line),
+            None => input.read_line(&mut line),
+            };
+            match read {
+            Ok(0) | Err(_) => return Control::Stop,
+            Ok(_) => {}
+            }
+
+            let mut words = line.trim().splitn(2, ' ');
+            let command = words.next().unwrap_or("");
+            let argument = words.next().unwrap_or("").trim();
+            match command {
+            "" => {}
+
+            "s" | "step" => {
+                self.mode = Mode::Step(state.depth(), frame.entry().span);
+                return Control::Continue;
+            }
+
+            "n" | "next" => {
+                self.mode = Mode::Next(state.depth(), frame.entry().span);
+                return Control::Continue;
+            }
+
+            "finish" => {
+                self.mode = Mode::Finish(state.depth());
+                return Control::Continue;
+            }
+
+            "c" | "continue" => {
+                self.mode = Mode::Continue;
+                return Control::Continue;
+            }
+
+            "q" | "quit" => return Control::Stop,
+
+            "b" | "break" => {
+                match Debugger::parse_breakpoint(argument) {
+                Some(breakpoint) => {
+                    let _ = writeln!(output, "Breakpoint {} at {}.", self.breakpoints.len() + 1, breakpoint);
+                    self.add_breakpoint(breakpoint);
+                }
+                None => { let _ = writeln!(output, "Expected a line number, function or label to break at."); }
+                }
+            }
+
+            "delete" => {
+                if argument.is_empty() {
+                    self.breakpoints.clear();
+                    let _ = writeln!(output, "Deleted all breakpoints.");
+                } else {
+                    match argument.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= self.breakpoints.len() && self.breakpoints[n - 1].is_some() => {
+                        // keep the numbers of the others the same.
+                        self.breakpoints[n - 1] = None;
+                        let _ = writeln!(output, "Deleted breakpoint {}.", n);
+                    }
+                    _ => { let _ = writeln!(output, "No breakpoint {}.", argument); }
+                    }
+                }
+            }
+
+            "breakpoints" => {
+                if self.breakpoints.iter().all(|b| b.is_none()) {
+                    let _ = writeln!(output, "No breakpoints.");
+                }
+                for (i, breakpoint) in self.breakpoints.iter().enumerate() {
+                    if let Some(breakpoint) = breakpoint {
+                        let _ = writeln!(output, "{}: {}", i + 1, breakpoint);
+                    }
+                }
+            }
+
+            "p" | "print" => {
+                if argument.is_empty() {
+                    for name in frame.names() {
+                        self.print(frame, name, output);
+                    }
+                } else {
+                    self.print(frame, argument, output);
+                }
+            }
+
+            "bt" | "backtrace" => {
+                for (i, entry) in state.backtrace().iter().enumerate() {
+                    let _ = writeln!(output, "#{} {}", i, entry);
+                }
+            }
+
+            "h" | "help" => {
+                let _ = write!(output, "{}", HELP);
+            }
+
+            _ => {
+                let _ = writeln!(output, "Unknown command '{}'. Type 'help' for a list.", command);
+            }
+            }
+        }
+    }
+}
+
+// whether the instruction at depth and span starts a different source line
+// from the one at from_depth and from. IR without a source steps one
+// instruction at a time.
+fn new_line(from_depth: usize, from: Option<Span>, depth: usize, span: Option<Span>) -> bool {
+    match (from, span) {
+    (Some(from), Some(span)) => from_depth != depth || from.line != span.line,
+    // an instruction with no position of its own belongs to the line before it
+    (Some(_), None) => false,
+    (None, _) => true,
+    }
+}
+
+impl Hook for Debugger {
+    fn before(&mut self, state: &State, input: &mut dyn BufRead, output: &mut dyn Write) -> Control {
+        let frame = state.frame(0).unwrap();
+        let hit = self.breakpoint_hit(state, &frame);
+        let span = frame.entry().span;
+        let stop = match self.mode {
+        Mode::Step(depth, from) => new_line(depth, from, state.depth(), span),
+        Mode::Next(depth, from) => state.depth() < depth || (state.depth() == depth && new_line(depth, from, depth, span)),
+        Mode::Finish(depth) => state.depth() < depth,
+        Mode::Continue => false,
+        };
+        self.last = Some((state.depth(), span));
+
+        if !stop && hit.is_none() {
+            return Control::Continue;
+        }
+        if let Some(n) = hit {
+            let _ = writeln!(output, "Breakpoint {}, {}.", n, self.breakpoints[n - 1].as_ref().unwrap());
+        }
+        self.show_location(&frame, output);
+        self.prompt(state, &frame, input, output)
+    }
+}
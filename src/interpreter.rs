@@ -1,32 +1,249 @@
-pub fn execute_ir(code: &str) {
-    let tokens = lex_ir(code);
-    let bytecode = match parse_ir(&tokens, &mut 0) {
-    Ok(bytecode) => {
-        println!("Valid IR. Executing Generated Bytecode...");
-        bytecode
-    }
-
-    Err(e) => {
-        println!("***Error. Invalid Bytecode.");
-        println!("------------------");
-        for (i, l) in code.lines().enumerate() {
-            println!("{:03}:  {}", i+1, l);
-            if i+1 == e.line {
-                break;
-            }
+// src/interpreter.rs
+// Runs the three address code. IR text is read with parse_ir, or a Module
+// from the frontend is loaded with Program::load, and then run on a Vm:
+//
+// let program = parse_ir(code)?;
+// let result = program.run(&mut Vm::new())?;
+//
+// A loaded program can also be saved with Program::to_binary and read back
+// with Program::from_binary, see interpreter/binary.rs.
+
+// settings for running the generated bytecode, set from the commandline.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    // report integer overflow as a runtime error instead of wrapping.
+    pub checked_arithmetic: bool,
+    // how many calls can be running at once before it is a stack overflow.
+    pub max_call_depth: usize,
+    // how many instructions the program can run in total, if limited.
+    pub max_instructions: Option<u64>,
+    // how long the program can run for, if limited.
+    pub timeout: Option<Duration>,
+    // how many bytes the arrays of all running calls can take together, if limited.
+    // every element takes 8 bytes.
+    pub max_array_memory: Option<usize>,
+}
+
+impl Default for RunOptions {
+    fn default() -> RunOptions {
+        RunOptions {
+            checked_arithmetic: false,
+            max_call_depth: 100000,
+            max_instructions: None,
+            timeout: None,
+            max_array_memory: None,
         }
-        println!("------------------");
-        println!("{e}");
-        return;
     }
+}
 
-    };
+// the limit a program ran into, for telling those errors apart from its own mistakes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    CallDepth,
+    Instructions,
+    Time,
+    ArrayMemory,
+}
+
+// everything a running program uses besides its own code.
+// %input and %inputc read from input, and the %out instructions write to output.
+pub struct Vm<'a> {
+    pub options: RunOptions,
+    input: Box<dyn BufRead + 'a>,
+    output: Box<dyn Write + 'a>,
+}
+
+impl Vm<'static> {
+    // a vm using standard input and output.
+    pub fn new() -> Vm<'static> {
+        Vm::with_options(RunOptions::default())
+    }
+
+    pub fn with_options(options: RunOptions) -> Vm<'static> {
+        let mut vm = Vm::with_io(io::stdin().lock(), io::stdout());
+        vm.options = options;
+        vm
+    }
+}
+
+impl<'a> Vm<'a> {
+    // a vm reading and writing somewhere else, e.g. a byte slice and a Vec<u8> in tests.
+    pub fn with_io(input: impl BufRead + 'a, output: impl Write + 'a) -> Vm<'a> {
+        Vm {
+            options: RunOptions::default(),
+            input: Box::new(input),
+            output: Box::new(output),
+        }
+    }
+}
+
+// a checked program, ready to run.
+#[derive(Debug)]
+pub struct Program {
+    functions: Vec<FunctionBytecode>,
+}
+
+impl Program {
+    // checks a module and turns it into bytecode the interpreter can run.
+    pub fn load(module: &Module) -> Result<Program, IRError> {
+        Ok(Program { functions: load_module(module)? })
+    }
+
+    // runs main and gives back its return value. it is an i64 since main can
+    // return a long; the command line narrows it to an exit code.
+    pub fn run(&self, vm: &mut Vm) -> Result<i64, RuntimeError> {
+        return self.run_main(vm, None);
+    }
+
+    // runs main like run, letting hook look at the program before every instruction.
+    pub fn run_with_hook(&self, vm: &mut Vm, hook: &mut dyn Hook) -> Result<i64, RuntimeError> {
+        return self.run_main(vm, Some(hook));
+    }
+
+    fn run_main(&self, vm: &mut Vm, hook: Option<&mut dyn Hook>) -> Result<i64, RuntimeError> {
+        let entry_point = match self.functions.iter().find(|func| func.name == "main") {
+        Some(func) => func,
+        None => return runtime_error(String::from("Runtime Error. No main function declared.")),
+        };
+
+        let result = run_bytecode(vm, entry_point, &self.functions, &vec![], hook);
+        let _ = vm.output.flush();
+        return result;
+    }
+}
+
+// something watching a program run, like the debugger.
+pub trait Hook {
+    // called before each instruction, with the program's own input and output.
+    fn before(&mut self, state: &State, input: &mut dyn BufRead, output: &mut dyn Write) -> Control;
+
+    // called once the instruction has run. after a call or return the
+    // innermost call is a different one, and after main returns there is none.
+    fn after(&mut self, _state: &State) {}
+}
+
+// what a hook wants the program to do next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+// the calls running when a hook is called.
+pub struct State<'s> {
+    stack: &'s [Frame<'s>],
+}
+
+impl<'s> State<'s> {
+    // how many calls are running, 1 while only main is.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    // a running call, 0 is the innermost one.
+    pub fn frame(&self, level: usize) -> Option<FrameView<'s>> {
+        if level >= self.stack.len() {
+            return None;
+        }
+        let i = self.stack.len() - 1 - level;
+        let frame = &self.stack[i];
+        // callers are just past their %call.
+        let at = if level == 0 { frame.instr_pointer } else { frame.instr_pointer - 1 };
+        Some(FrameView { frame, at })
+    }
+
+    // where each running call is, innermost first.
+    pub fn backtrace(&self) -> Vec<StackEntry> {
+        backtrace(self.stack)
+    }
+}
+
+// one running call, stopped at an instruction.
+pub struct FrameView<'s> {
+    frame: &'s Frame<'s>,
+    at: usize,
+}
+
+impl<'s> FrameView<'s> {
+    pub fn function(&self) -> &'s str {
+        &self.frame.function.name
+    }
+
+    // the IR line and source position of the instruction.
+    pub fn entry(&self) -> StackEntry {
+        stack_entry(self.frame, self.at)
+    }
 
-    let stdin = io::stdin();
-    run_program(&stdin, &bytecode);
+    // the instruction about to run, None at the end of the function.
+    pub fn instruction(&self) -> Option<&'s Instruction> {
+        self.frame.function.instructions.get(self.at)
+    }
+
+    // where the instruction is in the function's body, counted from 0.
+    pub fn index(&self) -> usize {
+        self.at
+    }
+
+    // which kind of bytecode the instruction was loaded as, e.g. "Add".
+    pub fn kind(&self) -> &'static str {
+        self.frame.function.body[self.at].kind()
+    }
+
+    // the name of the label the call is at, if it is at one.
+    pub fn label(&self) -> Option<&'s str> {
+        match &self.frame.function.body[self.at] {
+        Bytecode::Label(name) => Some(name),
+        _ => None,
+        }
+    }
+
+    pub fn variable(&self, name: &str) -> Option<i64> {
+        match self.frame.function.variables.get(name) {
+        Some(VariableType::IntVar(id)) => Some(self.frame.variables[*id as usize]),
+        _ => None,
+        }
+    }
+
+    pub fn array(&self, name: &str) -> Option<&'s [i64]> {
+        match self.frame.function.variables.get(name) {
+        Some(VariableType::ArrayVar(id)) => Some(&self.frame.arrays[*id as usize]),
+        _ => None,
+        }
+    }
+
+    // the variables and arrays of the function, in the order they were declared.
+    pub fn names(&self) -> Vec<&'s str> {
+        let mut names: Vec<(i32, &str)> = self.frame.function.variables.iter().map(|(name, typ)| {
+            match typ {
+            VariableType::IntVar(id) | VariableType::ArrayVar(id) => (*id, name.as_str()),
+            }
+        }).collect();
+        names.sort();
+        return names.into_iter().map(|(_, name)| name).collect();
+    }
+}
+
+// reads and checks IR text.
+pub fn parse_ir(code: &str) -> Result<Program, IRError> {
+    return Program::load(&parse_module(code)?);
+}
+
+// reads IR text into a Module. only the syntax is checked until it is loaded.
+pub fn parse_module(code: &str) -> Result<Module, IRError> {
+    return parse_tokens(&lex_ir(code), &mut 0);
 }
 
 use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::phases::ir;
+use crate::phases::ir::{BinaryOp, Instruction, Label, Module, Operand, Span, Type};
+
+// saving a program as a .ttb file and reading it back.
+mod binary;
 
 fn lex_ir(mut code: &str) -> Vec<IRTok> {
     let mut tokens: Vec<IRTok> = vec![];
@@ -44,63 +261,68 @@ fn lex_ir(mut code: &str) -> Vec<IRTok> {
 
 const MAX_LINE: usize = 2000000;
 
-fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecode>, IRError> {
+// reads the textual IR into an ir::Module. this only checks the syntax,
+// load_module checks that the identifiers, labels and calls make sense.
+fn parse_tokens(tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Module, IRError> {
     let mut serialized_line: usize = 1;
-    let mut vector: Vec<FunctionBytecode> = vec![];
-    let mut has_main: bool = false;
-    while let Some(bytecode) = parse_func_ir(&mut serialized_line, tokens, idx)? {
-        for func in &vector {
-          if func.name.eq(&bytecode.name) {
-            return error(MAX_LINE, format!("Error. Two functions with the same name {}", func.name));
-          }
+    let mut module = Module::new();
+    module.strings = parse_string_section(&mut serialized_line, tokens, idx)?;
+    while let Some(function) = parse_func_ir(&mut serialized_line, tokens, idx)? {
+        module.add_function(function);
+    }
+
+    return Ok(module);
+}
+
+fn load_module(module: &Module) -> Result<Vec<FunctionBytecode>, IRError> {
+    let mut strings: HashMap<String, String> = HashMap::new();
+    for (i, (name, text)) in module.strings.iter().enumerate() {
+        if strings.contains_key(name) {
+            // the string section is the first lines of the file
+            return error(i + 1, format!("string constant '{}' declared too many times", name));
         }
-        if bytecode.name.eq("main") {
-          has_main = true;
+        strings.insert(name.clone(), text.clone());
+    }
+
+    // functions can be called before they are declared, so give them ids up front.
+    let mut function_ids: HashMap<String, usize> = HashMap::new();
+    for (i, function) in module.functions.iter().enumerate() {
+        if function_ids.contains_key(&function.name) {
+            return error(MAX_LINE, format!("Error. Two functions with the same name {}", function.name));
         }
-        vector.push(bytecode);
+        function_ids.insert(function.name.clone(), i);
     }
 
-    if has_main == false {
+    if !function_ids.contains_key("main") {
       return error(MAX_LINE, format!("Error. Generated code does not have a main."));
     }
 
-    // todo: this is not the correct line numbers. but I dunno how to get better line numbers...
-    for func_id in 0..vector.len() {
-        for instr_id in 0..vector[func_id].body.len() {
-            if let Bytecode::Call(r, call, params) = &vector[func_id].body[instr_id] {
-                if let IRTok::Var(func_name) = &tokens[*call] {
-                    if let Some(call_value) = find_func_id(&vector, func_name) {
-                         if params.len() != vector[call_value].parameters {
-                             return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", vector[call_value].parameters, params.len()));
-                         }
-                         vector[func_id].body[instr_id] = Bytecode::Call(*r, call_value, params.to_vec());
-                    } else {
-                         return error(MAX_LINE, format!("Error. Undeclared function '{}'", func_name));
-                    }
-                } else {
-                    return error(MAX_LINE, format!("Internal Interpreter Error."));
-                }
-            }
-        }
+    let mut vector: Vec<FunctionBytecode> = vec![];
+    for function in &module.functions {
+        vector.push(load_function(module, function, &function_ids, &strings)?);
     }
 
     return Ok(vector);
-
-    fn find_func_id(v: &Vec<FunctionBytecode>, func_name: &str) -> Option<usize> {
-        for (i, function) in v.iter().enumerate() {
-            if function.name.eq(func_name) {
-                return Some(i);
-            }
-        }
-        return None;
-    }
 }
 
-struct IRError {
+// an error in the IR itself, found while reading or loading it.
+#[derive(Debug)]
+pub struct IRError {
     line: usize,
     message: String,
 }
 
+impl IRError {
+    // the IR line the error is on, if it is about a single line.
+    pub fn line(&self) -> Option<usize> {
+        if self.line == MAX_LINE { None } else { Some(self.line) }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
 impl fmt::Display for IRError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.line == MAX_LINE {
@@ -112,19 +334,155 @@ impl fmt::Display for IRError {
 }
 
 fn error<T>(line: usize, message: String) -> Result<T, IRError> {
-    Err(IRError {line: line, message: message})
+    Err(IRError {line, message})
+}
+
+// an error while the program is running, like dividing by zero.
+#[derive(Debug)]
+pub struct RuntimeError {
+    message: String,
+    // the calls being run when it happened, innermost first.
+    backtrace: Vec<StackEntry>,
+    limit: Option<Limit>,
+    // a hook stopped the program, e.g. the debugger's quit.
+    stopped: bool,
+}
+
+// one call in the call stack of a runtime error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackEntry {
+    pub function: String,
+    // the IR line being run, and where in the .tt source it came from.
+    pub line: Option<usize>,
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for StackEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "in {}", self.function)?;
+        if let Some(span) = self.span {
+            write!(f, " at line {}, column {}", span.line, span.column)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, " (IR line {})", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl RuntimeError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // set when the program was stopped by one of the RunOptions limits.
+    pub fn limit(&self) -> Option<Limit> {
+        self.limit
+    }
+
+    // set when a hook stopped the program rather than the program failing,
+    // e.g. quitting the debugger.
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn backtrace(&self) -> &[StackEntry] {
+        &self.backtrace
+    }
+
+    // the IR line the error happened on.
+    pub fn line(&self) -> Option<usize> {
+        self.backtrace.first().and_then(|entry| entry.line)
+    }
+
+    // where in the .tt source the error happened, when the program was compiled with spans.
+    pub fn span(&self) -> Option<Span> {
+        self.backtrace.first().and_then(|entry| entry.span)
+    }
+}
+
+// deep backtraces only show this many calls at each end.
+const BACKTRACE_ENDS: usize = 10;
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.backtrace.is_empty() {
+            write!(f, "\nCall stack:")?;
+        }
+        let len = self.backtrace.len();
+        for (i, entry) in self.backtrace.iter().enumerate() {
+            if len > 2 * BACKTRACE_ENDS && i >= BACKTRACE_ENDS && i < len - BACKTRACE_ENDS {
+                if i == BACKTRACE_ENDS {
+                    write!(f, "\n    ... {} more calls ...", len - 2 * BACKTRACE_ENDS)?;
+                }
+                continue;
+            }
+            write!(f, "\n    {}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+fn runtime_error<T>(message: String) -> Result<T, RuntimeError> {
+    Err(RuntimeError {message, backtrace: vec![], limit: None, stopped: false})
+}
+
+fn limit_error<T>(limit: Limit, message: String) -> Result<T, RuntimeError> {
+    Err(RuntimeError {message, backtrace: vec![], limit: Some(limit), stopped: false})
+}
+
+fn stopped_error<T>() -> Result<T, RuntimeError> {
+    let message = String::from("Runtime Error. The program was stopped before it finished.");
+    Err(RuntimeError {message, backtrace: vec![], limit: None, stopped: true})
 }
 
 use std::fmt;
 
-fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Option<FunctionBytecode>, IRError> {
-    let mut function_bytecode = FunctionBytecode {
-        name:String::from(""),
-        parameters:0,
-        id:0,
-        variables: HashMap::new(),
-        body: vec![],
-    };
+// string constants come before any function:
+// %str $0, "sum = "
+fn parse_string_section(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Vec<(String, String)>, IRError> {
+    let mut strings: Vec<(String, String)> = vec![];
+    loop {
+        match peek(tokens, *idx) {
+        Some(IRTok::EndInstr) => {
+            *serialized_line += 1;
+            *idx += 1;
+        }
+
+        Some(IRTok::Str) => {
+            *idx += 1;
+            let name = match next_result(*serialized_line, tokens, idx)? {
+            IRTok::Var(name) => name,
+            _ => return error(*serialized_line, String::from("invalid string constant. expected format like '%str $0, \"text\"'")),
+            };
+
+            if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
+                return error(*serialized_line, String::from("invalid string constant. expected format like '%str $0, \"text\"'"));
+            }
+
+            let text = match next_result(*serialized_line, tokens, idx)? {
+            IRTok::StrLit(text) => text,
+            _ => return error(*serialized_line, String::from("invalid string constant. expected format like '%str $0, \"text\"'")),
+            };
+
+            strings.push((name.clone(), text.clone()));
+
+            if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::EndInstr) {
+                return error(*serialized_line, String::from("expected newline."));
+            }
+            *serialized_line += 1;
+        }
+
+        _ => break,
+        }
+    }
+
+    return Ok(strings);
+}
+
+fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Option<ir::Function>, IRError> {
+    let mut function = ir::Function::new("");
 
     loop {
         if let Some(IRTok::EndInstr) = peek(tokens, *idx) {
@@ -147,9 +505,10 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
         return Ok(None);
     }
     }
+    function.line = *serialized_line;
     match next_result(*serialized_line, tokens, idx)? {
     IRTok::Var(func_ident) => {
-        function_bytecode.name = func_ident.clone();
+        function.name = func_ident.clone();
     }
 
     _ => return error(*serialized_line, String::from("func IR must have an identifier name such as '%func main'")),
@@ -162,17 +521,11 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
         loop {
             match next_result(*serialized_line, tokens, idx)? {
             IRTok::RParen => break,
-            IRTok::Int => {
+            IRTok::Int | IRTok::Long => {
+                let typ = if matches!(tokens[*idx - 1], IRTok::Long) { Type::Long } else { Type::Int };
                 match next_result(*serialized_line, tokens, idx)? {
                 IRTok::Var(param) => {
-                    if let Some(_) = function_bytecode.variables.get(param) {
-                        let f = format!("identifier {param} already defined");
-                        return error(*serialized_line, f);
-                    } else {
-                        function_bytecode.parameters += 1;
-                        let id = get_id(&mut function_bytecode);
-                        function_bytecode.variables.insert(param.clone(), VariableType::IntVar(id));
-                    }
+                    function.parameter(typ, param);
                     if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::Comma) {
                         *idx += 1;
                     }
@@ -199,185 +552,405 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
 
     *serialized_line += 1;
 
-    let mut labels_hash: HashMap<String, usize> = Default::default();
-    let mut line: usize = 0;
     loop {
-        let bytecode = parse_instruction(serialized_line, line, &mut function_bytecode, &mut labels_hash, tokens, idx)?;
-        if matches!(bytecode, Bytecode::End) {
-            break;
-        }
-        function_bytecode.body.push(bytecode); 
-        line += 1;
+        let instruction = match parse_instruction(serialized_line, tokens, idx)? {
+        Some(instruction) => instruction,
+        None => break,
+        };
+        function.body.push(instruction);
+        // parse_instruction has already moved on to the next line.
+        function.lines.push(*serialized_line - 1);
+        function.spans.push(None);
     }
 
     if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::EndFunc) {
         return error(*serialized_line, String::from("func IR needs to end in '%endfunc'"));
     }
 
-    function_bytecode.body.push(Bytecode::End); 
-    for i in 0..function_bytecode.body.len() {
-        match &function_bytecode.body[i] {
-        Bytecode::Jmp(index) => {
-            if let IRTok::Label(label_name) = &tokens[*index] {
-                if let Some(id) = labels_hash.get(label_name) {
-                    function_bytecode.body[i] = Bytecode::Jmp(*id);
-                } else {
-                    return error(i, format!("Error. invalid label {}", label_name));
-                }
-            } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
-            }
-        }
-        Bytecode::BranchIf(tf, index) => {
-            if let IRTok::Label(label_name) = &tokens[*index] {
-                if let Some(id) = labels_hash.get(label_name) {
-                    function_bytecode.body[i] = Bytecode::BranchIf(tf.clone(), *id);
-                } else {
-                    return error(*serialized_line, format!("Error. invalid label {}", label_name));
-                }
-            } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
-            }
+    return Ok(Some(function));
+}
+
+// line of the i-th instruction in the IR text, if the function came from text.
+fn line_of(function: &ir::Function, i: usize) -> usize {
+    match function.lines.get(i) {
+    Some(line) => *line,
+    None => MAX_LINE,
+    }
+}
+
+fn load_function(module: &Module, function: &ir::Function, function_ids: &HashMap<String, usize>, strings: &HashMap<String, String>) -> Result<FunctionBytecode, IRError> {
+    let mut function_bytecode = FunctionBytecode {
+        name: function.name.clone(),
+        parameters:0,
+        slots: 0,
+        variables: HashMap::new(),
+        long_variables: vec![],
+        arrays: vec![],
+        array_memory: 0,
+        body: vec![],
+        instructions: vec![],
+        lines: vec![],
+        spans: vec![],
+    };
+
+    let header_line = if function.line == 0 { MAX_LINE } else { function.line };
+    for (typ, param) in &function.parameters {
+        if let Some(_) = function_bytecode.variables.get(param) {
+            let f = format!("identifier {param} already defined");
+            return error(header_line, f);
+        } else {
+            function_bytecode.parameters += 1;
+            let id = new_slot(&mut function_bytecode, *typ);
+            function_bytecode.variables.insert(param.clone(), VariableType::IntVar(id));
         }
-        Bytecode::BranchIfn(tf, index) => {
-            if let IRTok::Label(label_name) = &tokens[*index] {
-                if let Some(id) = labels_hash.get(label_name) {
-                    function_bytecode.body[i] = Bytecode::BranchIfn(tf.clone(), *id);
-                } else {
-                    return error(*serialized_line, format!("Error. invalid label {}", label_name));
-                }
+    }
+
+    // labels can be jumped to before they are declared.
+    let mut labels_hash: HashMap<String, usize> = Default::default();
+    for (i, instruction) in function.body.iter().enumerate() {
+        if let Instruction::Label(label) = instruction {
+            if let None = labels_hash.get(&label.0) {
+                labels_hash.insert(label.0.clone(), i);
             } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
+                return error(line_of(function, i), format!("label {} already defined.", label));
             }
         }
-        _ => {}
-        }
     }
-    return Ok(Some(function_bytecode));
+
+    for (i, instruction) in function.body.iter().enumerate() {
+        let bytecode = load_instruction(line_of(function, i), &mut function_bytecode, instruction, module, function_ids, &labels_hash, strings)?;
+        function_bytecode.body.push(bytecode); 
+        function_bytecode.instructions.push(instruction.clone());
+        function_bytecode.lines.push(line_of(function, i));
+        function_bytecode.spans.push(function.span(i));
+    }
+
+    // End stands for the %endfunc line.
+    let end_line = match function.lines.last() {
+    Some(line) => line + 1,
+    None if function.line != 0 => function.line + 1,
+    None => MAX_LINE,
+    };
+    function_bytecode.body.push(Bytecode::End); 
+    function_bytecode.lines.push(end_line);
+    function_bytecode.spans.push(None);
+    return Ok(function_bytecode);
 }
 
 use std::collections::HashMap;
 
+#[derive(Debug)]
 struct FunctionBytecode {
     name: String,
     parameters: usize,
-    id: i32,
+    // every variable and array gets its own slot in the frame, numbered from 0
+    // with the parameters first. names are only looked up while loading.
+    slots: usize,
     variables: HashMap<String, VariableType>,
+    // true for the slots declared with %long, everything else is 32-bit.
+    long_variables: Vec<bool>,
+    // the (slot, length) of each array, allocated when the function is called.
+    arrays: Vec<(i32, i32)>,
+    // bytes taken by all of those arrays together.
+    array_memory: usize,
+    // the IR line and .tt source position of each bytecode, for runtime errors.
+    lines: Vec<usize>,
+    spans: Vec<Option<Span>>,
     body: Vec<Bytecode>,
+    // the instruction each bytecode was loaded from, for hooks. End has none.
+    instructions: Vec<Instruction>,
 }
 
-fn get_id(function: &mut FunctionBytecode) -> i32 {
-    let id = function.id;
-    function.id += 1;
+fn new_slot(function: &mut FunctionBytecode, typ: Type) -> i32 {
+    let id = function.slots as i32;
+    function.slots += 1;
+    function.long_variables.push(typ == Type::Long);
     id
 }
 
-fn read_integer_value(variables: &HashMap<i32, i32>, op: &Op) -> i32 {
+fn read_integer_value(variables: &[i64], op: &Op) -> i64 {
     match op {
     Op::Num(num) => *num,
-    Op::Var(id) => *variables.get(&id).unwrap(),
+    Op::Var(id) => variables[*id as usize],
     }
 }
 
-fn run_program(stdin: &io::Stdin, calls: &Vec<FunctionBytecode>) {
-    let entry_point = {
-        let mut result = None;
-        for func in calls {
-            if func.name.eq("main") {
-                result = Some(func);
-            } 
-        }
-        match result {
-        Some(r) => r,
-        None => {
-            println!("Runtime Error. No main function declared.");
-            return;
+fn write_output(vm: &mut Vm, text: fmt::Arguments) -> Result<(), RuntimeError> {
+    match vm.output.write_fmt(text) {
+    Ok(_) => Ok(()),
+    Err(e) => runtime_error(format!("Runtime Error. Failed to write to standard output. {}", e)),
+    }
+}
+
+// ints and longs are both stored as i64, so anything written to an int
+// is cut back down to 32 bits first.
+fn fit_width(function: &FunctionBytecode, id: i32, num: i64) -> i64 {
+    if function.long_variables[id as usize] {
+        num
+    } else {
+        num as i32 as i64
+    }
+}
+
+fn write_variable(function: &FunctionBytecode, variables: &mut [i64], id: &i32, num: i64) {
+    variables[*id as usize] = fit_width(function, *id, num);
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Sub,
+    Mult,
+    Div,
+    Mod,
+}
+
+impl Arithmetic {
+    fn opcode(&self) -> &'static str {
+        match self {
+        Arithmetic::Add => "%add",
+        Arithmetic::Sub => "%sub",
+        Arithmetic::Mult => "%mult",
+        Arithmetic::Div => "%div",
+        Arithmetic::Mod => "%mod",
         }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+        Arithmetic::Add => "+",
+        Arithmetic::Sub => "-",
+        Arithmetic::Mult => "*",
+        Arithmetic::Div => "/",
+        Arithmetic::Mod => "%",
         }
-    };
-    match run_bytecode(stdin, entry_point, calls, &vec![]) {
-    Ok(n) => println!("Run successful. Exit code {}", n),
-    Err(e) => println!("{}", e),
     }
 }
 
-fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<FunctionBytecode>, parameters: &Vec<i32>) -> Result<i32, IRError>  {
-    let mut variables: HashMap<i32, i32> = HashMap::new();
-    let mut arrays: HashMap<i32, Vec<i32>> = HashMap::new();
-
-    // setup local variables
-    for (_, vartype) in &function.variables {
-         match vartype {
-         VariableType::IntVar(id) => {
-             variables.insert(*id, 0);
-         }
+// ints wrap around at 32 bits and longs at 64 bits, including MIN / -1.
+// with --checked-arithmetic an overflow stops the program instead.
+fn arithmetic(function: &FunctionBytecode, options: &RunOptions, op: Arithmetic, dest: i32, num1: i64, num2: i64) -> Result<i64, RuntimeError> {
+    if num2 == 0 && matches!(op, Arithmetic::Div | Arithmetic::Mod) {
+        let e = String::from("Runtime Error. Attempt to divide by zero.");
+        return runtime_error(e);
+    }
 
-         VariableType::ArrayVar(id, len) => {
-             // create an array of zeroes.
-             let arr = vec![0i32; *len as usize];
-             arrays.insert(*id, arr);
-         }
+    let (num, overflow) = if function.long_variables[dest as usize] {
+        match op {
+        Arithmetic::Add => num1.overflowing_add(num2),
+        Arithmetic::Sub => num1.overflowing_sub(num2),
+        Arithmetic::Mult => num1.overflowing_mul(num2),
+        Arithmetic::Div => num1.overflowing_div(num2),
+        Arithmetic::Mod => num1.overflowing_rem(num2),
+        }
+    } else {
+        let (num1, num2) = (num1 as i32, num2 as i32);
+        let (num, overflow) = match op {
+        Arithmetic::Add => num1.overflowing_add(num2),
+        Arithmetic::Sub => num1.overflowing_sub(num2),
+        Arithmetic::Mult => num1.overflowing_mul(num2),
+        Arithmetic::Div => num1.overflowing_div(num2),
+        Arithmetic::Mod => num1.overflowing_rem(num2),
+        };
+        (num as i64, overflow)
+    };
 
-         }
+    if overflow && options.checked_arithmetic {
+        let e = format!("Runtime Error. Integer overflow in '{}' ({} {} {}) in function '{}'.", op.opcode(), num1, op.symbol(), num2, function.name);
+        return runtime_error(e);
     }
+
+    return Ok(num);
+}
+
+// a function call that has not returned yet.
+struct Frame<'p> {
+    function: &'p FunctionBytecode,
+    // one value per slot, and the contents of the arrays.
+    variables: Vec<i64>,
+    arrays: Vec<Vec<i64>>,
+    instr_pointer: usize,
+    // the caller's variable the return value goes to.
+    dest: i32,
+}
+
+fn new_frame<'p>(function: &'p FunctionBytecode, parameters: &Vec<i64>, dest: i32) -> Result<Frame<'p>, RuntimeError> {
     if parameters.len() != function.parameters {
          let e = format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got {} parameters", function.parameters, parameters.len());
-         return error(MAX_LINE, e);
+         return runtime_error(e);
     }
 
-    // hopefully this covers everything needed for parameter passing...
+    let mut frame = Frame {
+        function,
+        variables: vec![0; function.slots],
+        arrays: vec![vec![]; function.slots],
+        instr_pointer: 0,
+        dest,
+    };
+    for (id, len) in &function.arrays {
+        frame.arrays[*id as usize] = vec![0; *len as usize];
+    }
+
+    // parameters take the first slots.
     for (i, value) in parameters.iter().enumerate() {
-        let variable = variables.get_mut(&(i as i32)).unwrap();
-        *variable = *value;
+        write_variable(function, &mut frame.variables, &(i as i32), *value);
+    }
+    return Ok(frame);
+}
+
+// calls are kept on an explicit stack rather than recursing, so deep
+// recursion in a program gives a runtime error instead of crashing us.
+fn run_bytecode(vm: &mut Vm, function: &FunctionBytecode, calls: &Vec<FunctionBytecode>, parameters: &Vec<i64>, hook: Option<&mut dyn Hook>) -> Result<i64, RuntimeError>  {
+    let memory = reserve_memory(&vm.options, 0, function)?;
+    let mut stack: Vec<Frame> = vec![new_frame(function, parameters, 0)?];
+    match run_frames(vm, calls, &mut stack, memory, hook) {
+    Ok(num) => Ok(num),
+    Err(mut e) => {
+        e.backtrace = backtrace(&stack);
+        Err(e)
+    }
+    }
+}
+
+fn stack_entry(frame: &Frame, at: usize) -> StackEntry {
+    let line = frame.function.lines[at];
+    StackEntry {
+        function: frame.function.name.clone(),
+        line: if line == MAX_LINE { None } else { Some(line) },
+        span: frame.function.spans[at],
+    }
+}
+
+// the innermost call is on its current instruction, the others just past their %call.
+fn backtrace(stack: &[Frame]) -> Vec<StackEntry> {
+    let top = stack.len() - 1;
+    return stack.iter().enumerate().rev().map(|(i, frame)| {
+        let at = if i == top { frame.instr_pointer } else { frame.instr_pointer - 1 };
+        stack_entry(frame, at)
+    }).collect();
+}
+
+// checked before a call allocates its arrays, so a huge array cannot run us out of memory.
+fn reserve_memory(options: &RunOptions, memory: usize, callee: &FunctionBytecode) -> Result<usize, RuntimeError> {
+    let memory = memory + callee.array_memory;
+    if let Some(max) = options.max_array_memory {
+        if memory > max {
+            let e = format!("Runtime Error. Out of memory in function '{}'. Its arrays would take the program past {} bytes.", callee.name, max);
+            return limit_error(Limit::ArrayMemory, e);
+        }
     }
+    return Ok(memory);
+}
+
+// how many instructions run between looking at the clock, which costs more than most instructions.
+const CLOCK_INTERVAL: u32 = 4096;
+
+fn run_frames<'p>(vm: &mut Vm, calls: &'p Vec<FunctionBytecode>, stack: &mut Vec<Frame<'p>>, mut memory: usize, mut hook: Option<&mut dyn Hook>) -> Result<i64, RuntimeError> {
+    // without a limit there is more fuel than any program will ever use.
+    let mut fuel: u64 = vm.options.max_instructions.unwrap_or(u64::MAX);
+    let deadline = vm.options.timeout.map(|timeout| Instant::now() + timeout);
+    let mut ticks: u32 = 0;
+    // the hook hears about an instruction having run just before the next one.
+    let mut ran = false;
 
     // a lot of unwraps, but we already checked everything
     // so this should work.
     // execute instructions.
-    let mut instr_pointer: usize = 0;
-    loop {
-        let instr = &function.body[instr_pointer];
+    let result = loop {
+        if fuel == 0 {
+            let e = format!("Runtime Error. Out of fuel. The program ran more than {} instructions.", vm.options.max_instructions.unwrap_or(u64::MAX));
+            return limit_error(Limit::Instructions, e);
+        }
+        fuel -= 1;
+
+        if let Some(deadline) = deadline {
+            ticks += 1;
+            if ticks == CLOCK_INTERVAL {
+                ticks = 0;
+                if Instant::now() >= deadline {
+                    let e = format!("Runtime Error. Out of time. The program ran for more than {} milliseconds.", vm.options.timeout.unwrap_or_default().as_millis());
+                    return limit_error(Limit::Time, e);
+                }
+            }
+        }
+
+        if let Some(hook) = hook.as_mut() {
+            if ran {
+                hook.after(&State { stack });
+            }
+            ran = true;
+            if hook.before(&State { stack }, &mut *vm.input, &mut *vm.output) == Control::Stop {
+                return stopped_error();
+            }
+        }
+
+        let depth = stack.len();
+        let frame = stack.last_mut().unwrap();
+        let function = frame.function;
+        let instr = &function.body[frame.instr_pointer];
         match instr {
         Bytecode::End => {
-            break;
+            // falling off the end of a function returns 0.
+            let done = stack.pop().unwrap();
+            memory -= done.function.array_memory;
+            match stack.last_mut() {
+            Some(caller) => write_variable(caller.function, &mut caller.variables, &done.dest, 0),
+            None => break 0,
+            }
         }
 
         Bytecode::Int(id) => {
-            let value = variables.get_mut(id).unwrap();
-            *value = 0;
-            instr_pointer += 1;
+            frame.variables[*id as usize] = 0;
+            frame.instr_pointer += 1;
         }
 
-        Bytecode::IntArray(id, len) => {
-            let array = arrays.get_mut(id).unwrap();
-            for i in 0..*len {
-                 let idx = i as usize;
-                 array[idx] ^= array[idx];
-            }
-            instr_pointer += 1;
+        Bytecode::IntArray(id) => {
+            frame.arrays[*id as usize].fill(0);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Out(value) => {
-            let num = read_integer_value(&variables, value);
-            println!("{}", num);
-            instr_pointer += 1;
+            let num = read_integer_value(&frame.variables, value);
+            write_output(vm, format_args!("{}\n", num))?;
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::OutStr(text) => {
+            write_output(vm, format_args!("{}", text))?;
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::OutInt(value) => {
+            let num = read_integer_value(&frame.variables, value);
+            write_output(vm, format_args!("{}", num))?;
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::OutLine => {
+            write_output(vm, format_args!("\n"))?;
+            frame.instr_pointer += 1;
         }
 
         Bytecode::In(id) => {
+            // a prompt written with print("...") has no newline, so make sure it shows up.
+            let _ = vm.output.flush();
             let mut buf = String::with_capacity(64);
             loop {
-                match stdin.read_line(&mut buf) {
+                match vm.input.read_line(&mut buf) {
+                Ok(0) => {
+                     return runtime_error(String::from("Runtime Error. Reached the end of the input while reading a number for '%input'."));
+                }
+
                 Ok(_) => {
                      let token = buf.trim_end();
-                     match token.parse::<i32>() {
-                     Ok(num) => {
-                          let dest = variables.get_mut(id).unwrap();
-                          *dest = num;
+                     match token.parse::<i64>() {
+                     Ok(num) if fit_width(function, *id, num) == num => {
+                          write_variable(function, &mut frame.variables, id, num);
                           break;
                      }
 
-                     Err(_) => {
-                         println!("User Input Error. '{}' is not a valid number.", token);
+                     _ => {
+                         write_output(vm, format_args!("User Input Error. '{}' is not a valid number.\n", token))?;
                          buf.clear();
                      }
 
@@ -385,229 +958,479 @@ fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<Func
                 }
 
                 Err(e) => {
-                     println!("Error. Failed to read from standard input correctly.");
-                     println!("{e}");
-                     println!("Please try again.");
+                     return runtime_error(format!("Runtime Error. Failed to read from standard input. {}", e));
                 }
 
                 }
             }
-            instr_pointer += 1;
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::OutChar(value) => {
+            let num = read_integer_value(&frame.variables, value);
+            let byte = [num as u8];
+            if let Err(e) = vm.output.write_all(&byte) {
+                return runtime_error(format!("Runtime Error. Failed to write to standard output. {}", e));
+            }
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::InChar(id) => {
+            let _ = vm.output.flush();
+            let mut byte = [0u8];
+            let num = match vm.input.read(&mut byte) {
+            Ok(0) => -1,
+            Ok(_) => byte[0] as i64,
+            Err(e) => return runtime_error(format!("Runtime Error. Failed to read from standard input. {}", e)),
+            };
+            write_variable(function, &mut frame.variables, id, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Mov(MemWrite::IntVar(dest), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num;
-            instr_pointer += 1;
+            let num = read_memory(&frame.variables, &frame.arrays, src)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = arrays.get_mut(dest).unwrap();
-            let index = read_integer_value(&variables, index);
+            let num = read_memory(&frame.variables, &frame.arrays, src)?;
+            let num = fit_width(function, *dest, num);
+            let dest = &mut frame.arrays[*dest as usize];
+            let index = read_integer_value(&frame.variables, index);
             if index < 0 {
                 let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", index, dest.len());
-                return error(MAX_LINE, e);
+                return runtime_error(e);
             }
             let i = index as usize;
             if i < dest.len() {
                 dest[i] = num;
-                instr_pointer += 1;
+                frame.instr_pointer += 1;
             } else {
                 let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", i, dest.len());
-                return error(MAX_LINE, e);
+                return runtime_error(e);
             }
         }
 
         Bytecode::Add(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 + num2;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            let num = arithmetic(function, &vm.options, Arithmetic::Add, *dest, num1, num2)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Sub(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 - num2;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            let num = arithmetic(function, &vm.options, Arithmetic::Sub, *dest, num1, num2)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Mult(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 * num2;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            let num = arithmetic(function, &vm.options, Arithmetic::Mult, *dest, num1, num2)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Div(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            if num2 == 0 {
-                let e = String::from("Error. Attempt to divide by zero.");
-                return error(MAX_LINE, e);
-            }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 / num2;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            let num = arithmetic(function, &vm.options, Arithmetic::Div, *dest, num1, num2)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Mod(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            if num2 == 0 {
-                let e = String::from("Error. Attempt to divide by zero.");
-                return error(MAX_LINE, e);
-            }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 % num2;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            let num = arithmetic(function, &vm.options, Arithmetic::Mod, *dest, num1, num2)?;
+            write_variable(function, &mut frame.variables, dest, num);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::LessThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 < num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 < num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::LessEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 <= num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 <= num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Equal(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 == num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 == num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::NotEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 != num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 != num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::GreaterThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 > num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 > num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::GreaterEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 >= num2) as i32;
-            instr_pointer += 1;
+            let num1 = read_integer_value(&frame.variables, src1);
+            let num2 = read_integer_value(&frame.variables, src2);
+            write_variable(function, &mut frame.variables, dest, (num1 >= num2) as i64);
+            frame.instr_pointer += 1;
         }
 
         Bytecode::Label(_) => {
-            instr_pointer += 1;
+            frame.instr_pointer += 1;
+        }
+
+        Bytecode::Jmp(jump) => {
+            frame.instr_pointer = *jump;
+        }
+
+        Bytecode::BranchIf(src1, jump) => {
+            let num1 = read_integer_value(&frame.variables, src1);
+            match num1 {
+            0 => {frame.instr_pointer += 1;}
+            1 => {frame.instr_pointer = *jump;}
+            _ => {
+                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
+                return runtime_error(e);
+            }
+            }
+        }
+
+        Bytecode::BranchIfn(src1, jump) => {
+            let num1 = read_integer_value(&frame.variables, src1);
+            match num1 {
+            0 => {frame.instr_pointer = *jump;}
+            1 => {frame.instr_pointer += 1;}
+            _ => {
+                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
+                return runtime_error(e);
+            }
+            }
+        }
+
+        Bytecode::Call(dest, function_index, parameters) => {
+             let callee = &calls[*function_index];
+             let mut pass = vec![];
+             for p in parameters {
+                  let num1: i64 = read_integer_value(&frame.variables, p);
+                  pass.push(num1);
+             }
+
+             if depth >= vm.options.max_call_depth {
+                 let e = format!("Runtime Error. Stack overflow in function '{}'. More than {} calls deep.", callee.name, vm.options.max_call_depth);
+                 return limit_error(Limit::CallDepth, e);
+             }
+             memory = reserve_memory(&vm.options, memory, callee)?;
+
+             // the caller carries on after the call once the callee returns.
+             frame.instr_pointer += 1;
+             stack.push(new_frame(callee, &pass, *dest)?);
+        }
+
+        Bytecode::Return(src1) => {
+            let num = read_integer_value(&frame.variables, src1);
+            let done = stack.pop().unwrap();
+            memory -= done.function.array_memory;
+            match stack.last_mut() {
+            Some(caller) => write_variable(caller.function, &mut caller.variables, &done.dest, num),
+            None => break num,
+            }
+        }
+
+        }
+    };
+
+    // main has returned, so the stack is empty.
+    if let Some(hook) = hook.as_mut() {
+        hook.after(&State { stack });
+    }
+    return Ok(result);
+}
+
+fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<Op, IRError> {
+    if let Some(id) = function.variables.get(ident) {
+         match id {
+         VariableType::IntVar(id) => Ok(Op::Var(*id)),
+
+         VariableType::ArrayVar(_) => {
+             let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
+             return error(line, f);
+         }
+
+         }
+    } else {
+         let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
+         return error(line, f);
+    }
+}
+
+fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<i32, IRError> {
+    if let Some(id) = function.variables.get(ident) {
+         match id {
+         VariableType::IntVar(id) => Ok(*id),
+
+         VariableType::ArrayVar(_) => {
+             let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
+             return error(line, f);
+         }
+
+         }
+    } else {
+         let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
+         return error(line, f);
+    }
+}
+
+fn load_operand(line: usize, function: &FunctionBytecode, operand: &Operand) -> Result<Op, IRError> {
+    match operand {
+    Operand::Var(ident) => lookup_integer_variable_id(line, function, ident),
+    Operand::Num(num) => Ok(Op::Num(*num)),
+    }
+}
+
+// the value read by a '%mov', which can be a number or a variable but not a whole array
+fn load_mov_source(line: usize, function: &FunctionBytecode, operand: &Operand) -> Result<MemRead, IRError> {
+    match operand {
+    Operand::Var(ident) => {
+        if let Some(id) = function.variables.get(ident) {
+             match id {
+             VariableType::IntVar(id) => Ok(MemRead::IntVar(*id)),
+ 
+             VariableType::ArrayVar(_) => {
+                 let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
+                 return error(line, f);
+             }
+ 
+             }
+        } else {
+             let f = format!("invalid instruction. identifier '{}' has not been declared", ident);
+             return error(line, f);
+        }
+    }
+
+    Operand::Num(num) => Ok(MemRead::Number(*num)),
+    }
+}
+
+fn lookup_array_id(line: usize, function: &FunctionBytecode, array: &String) -> Result<i32, IRError> {
+    if let Some(id) = function.variables.get(array) {
+        match id {
+        VariableType::ArrayVar(id) => Ok(*id),
+        _ => {
+           let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
+           return error(line, f);
         }
 
-        Bytecode::Jmp(jump) => {
-            instr_pointer = *jump;
-        }
+        }
+    } else {
+        let f = format!("invalid instruction. no such identifier '{}'.", array);
+        return error(line, f);
+    }
+}
+
+// checks a single instruction against the declarations seen so far and turns it into bytecode.
+fn load_instruction(line: usize, function: &mut FunctionBytecode, instruction: &Instruction, module: &Module, function_ids: &HashMap<String, usize>, labels_hash: &HashMap<String, usize>, strings: &HashMap<String, String>) -> Result<Bytecode, IRError> {
+    let bytecode: Bytecode;
+    match instruction {
+
+    // declarations.
+    Instruction::Declare(typ, ident) => {
+        if let Some(_) = function.variables.get(ident) {
+             let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
+             return error(line, f);
+        } else {
+             let id = new_slot(function, *typ);
+             function.variables.insert(ident.clone(), VariableType::IntVar(id));
+             bytecode = Bytecode::Int(id);
+        }
+    }
+
+    Instruction::DeclareArray(typ, ident, num) => {
+        if *num <= 0 {
+          return error(line, String::from("array size cannot be less than or equal to zero."));
+        }
+
+        if *num > i32::MAX as i64 {
+          return error(line, format!("array size {num} is too large."));
+        }
+        let num = *num as i32;
+
+        if let Some(_) = function.variables.get(ident) {
+             let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
+             return error(line, f);
+        } else {
+             let id = new_slot(function, *typ);
+             function.variables.insert(ident.clone(), VariableType::ArrayVar(id));
+             function.arrays.push((id, num));
+             function.array_memory += num as usize * 8;
+             bytecode = Bytecode::IntArray(id);
+        }
+    }
+
+    // function calling routines.
+    Instruction::Call(dest, func_name, arguments) => {
+        let op = lookup_variable_dest_id(line, function, dest)?;
+
+        let call_value = match function_ids.get(func_name) {
+        Some(call_value) => *call_value,
+        None => return error(line, format!("Error. Undeclared function '{}'", func_name)),
+        };
+
+        let expected = module.functions[call_value].parameters.len();
+        if arguments.len() != expected {
+            return error(line, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", expected, arguments.len()));
+        }
+
+        let mut parameters = vec![];
+        for argument in arguments {
+            parameters.push(load_operand(line, function, argument)?);
+        }
+        bytecode = Bytecode::Call(op, call_value, parameters);
+    }
+
+    Instruction::Return(value) => {
+        bytecode = Bytecode::Return(load_operand(line, function, value)?);
+    }
+
+    // input/output routines.
+    Instruction::Out(value) => {
+        bytecode = Bytecode::Out(load_operand(line, function, value)?);
+    }
+
+    Instruction::OutStr(name) => {
+        let text = match strings.get(name) {
+        Some(text) => text.clone(),
+        None => return error(line, format!("invalid instruction. string constant '{}' has not been declared.", name)),
+        };
+        bytecode = Bytecode::OutStr(text);
+    }
+
+    Instruction::OutInt(value) => {
+        bytecode = Bytecode::OutInt(load_operand(line, function, value)?);
+    }
+
+    Instruction::OutLine => {
+        bytecode = Bytecode::OutLine;
+    }
+
+    Instruction::In(dest) => {
+        bytecode = Bytecode::In(lookup_variable_dest_id(line, function, dest)?);
+    }
 
-        Bytecode::BranchIf(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
-            match num1 {
-            0 => {instr_pointer += 1;}
-            1 => {instr_pointer = *jump;}
-            _ => {
-                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
-                return error(MAX_LINE, e);
-            }
-            }
-        }
+    Instruction::OutChar(value) => {
+        bytecode = Bytecode::OutChar(load_operand(line, function, value)?);
+    }
 
-        Bytecode::BranchIfn(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
-            match num1 {
-            0 => {instr_pointer = *jump;}
-            1 => {instr_pointer += 1;}
-            _ => {
-                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
-                return error(MAX_LINE, e);
-            }
-            }
-        }
+    Instruction::InChar(dest) => {
+        bytecode = Bytecode::InChar(lookup_variable_dest_id(line, function, dest)?);
+    }
 
-        Bytecode::Call(dest, function_index, parameters) => {
-             let function = &calls[*function_index];
-             let mut pass = vec![];
-             for p in parameters {
-                  let num1: i32 = read_integer_value(&variables, p);
-                  pass.push(num1);
-             }
+    // moves.
+    Instruction::Mov(dest, src) => {
+        let dest = lookup_variable_dest_id(line, function, dest)?;
+        let src = load_mov_source(line, function, src)?;
+        bytecode = Bytecode::Mov(MemWrite::IntVar(dest), src);
+    }
 
-             let eax = run_bytecode(stdin, function, calls, &pass)?;
-             let dest = variables.get_mut(dest).unwrap();
-             *dest = eax;
-             instr_pointer += 1;
-        }
+    Instruction::Load(dest, array, element) => {
+        let dest = lookup_variable_dest_id(line, function, dest)?;
+        let array = lookup_array_id(line, function, array)?;
+        let element = load_operand(line, function, element)?;
+        bytecode = Bytecode::Mov(MemWrite::IntVar(dest), MemRead::ArrayRead(array, element));
+    }
 
-        Bytecode::Return(src1) => {
-            let num = read_integer_value(&variables, src1);
-            return Ok(num);
-        }
+    Instruction::Store(array, element, src) => {
+        let array = lookup_array_id(line, function, array)?;
+        let element = load_operand(line, function, element)?;
+        let src = load_mov_source(line, function, src)?;
+        bytecode = Bytecode::Mov(MemWrite::ArrayWrite(array, element), src);
+    }
 
-        }
+    // mathematical and comparison operators.
+    Instruction::Binary(op, dest, src1, src2) => {
+        let dest = lookup_variable_dest_id(line, function, dest)?;
+        let src1 = load_operand(line, function, src1)?;
+        let src2 = load_operand(line, function, src2)?;
+        bytecode = match op {
+        BinaryOp::Add => Bytecode::Add(dest, src1, src2),
+        BinaryOp::Sub => Bytecode::Sub(dest, src1, src2),
+        BinaryOp::Mult => Bytecode::Mult(dest, src1, src2),
+        BinaryOp::Div => Bytecode::Div(dest, src1, src2),
+        BinaryOp::Mod => Bytecode::Mod(dest, src1, src2),
+        BinaryOp::LessThan => Bytecode::LessThan(dest, src1, src2),
+        BinaryOp::LessEqual => Bytecode::LessEqual(dest, src1, src2),
+        BinaryOp::NotEqual => Bytecode::NotEqual(dest, src1, src2),
+        BinaryOp::Equal => Bytecode::Equal(dest, src1, src2),
+        BinaryOp::GreaterEqual => Bytecode::GreaterEqual(dest, src1, src2),
+        BinaryOp::GreaterThan => Bytecode::GreaterThan(dest, src1, src2),
+        };
     }
 
-    return Ok(0);
-}
+    // labels/branching
+    Instruction::Label(label) => {
+        bytecode = Bytecode::Label(label.0.clone());
+    }
 
-fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<Op, IRError> {
-    if let Some(id) = function.variables.get(ident) {
-         match id {
-         VariableType::IntVar(id) => Ok(Op::Var(*id)),
+    Instruction::Jump(label) => {
+        bytecode = Bytecode::Jmp(find_label(line, labels_hash, label)?);
+    }
 
-         VariableType::ArrayVar(_,_) => {
-             let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
-             return error(line, f);
-         }
+    Instruction::BranchIf(condition, label) => {
+        let condition = load_operand(line, function, condition)?;
+        bytecode = Bytecode::BranchIf(condition, find_label(line, labels_hash, label)?);
+    }
 
-         }
-    } else {
-         let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
-         return error(line, f);
+    Instruction::BranchIfn(condition, label) => {
+        let condition = load_operand(line, function, condition)?;
+        bytecode = Bytecode::BranchIfn(condition, find_label(line, labels_hash, label)?);
     }
-}
 
-fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<i32, IRError> {
-    if let Some(id) = function.variables.get(ident) {
-         match id {
-         VariableType::IntVar(id) => Ok(*id),
+    }
 
-         VariableType::ArrayVar(_,_) => {
-             let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
-             return error(line, f);
-         }
+    return Ok(bytecode);
 
-         }
-    } else {
-         let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
-         return error(line, f);
+    fn find_label(line: usize, labels_hash: &HashMap<String, usize>, label: &Label) -> Result<usize, IRError> {
+        match labels_hash.get(&label.0) {
+        Some(id) => Ok(*id),
+        None => error(line, format!("Error. invalid label {}", label)),
+        }
+    }
+}
+
+// a variable or a number, as used for most sources.
+fn parse_operand(serialized_line: usize, tokens: &Vec<IRTok>, idx: &mut usize, message: &str) -> Result<Operand, IRError> {
+    match next_result(serialized_line, tokens, idx)? {
+    IRTok::Var(ident) => Ok(Operand::Var(ident.clone())),
+    IRTok::Num(num) => Ok(Operand::Num(*num)),
+    _ => error(serialized_line, String::from(message)),
     }
 }
 
-fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut FunctionBytecode, labels_hash: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Bytecode, IRError> {
+// a label token such as ':3', stored without the ':'
+fn label_name(name: &String) -> Label {
+    Label(String::from(name.trim_start_matches(':')))
+}
+
+// reads one line of a function body. returns None at '%endfunc', which is left for the caller.
+fn parse_instruction(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Option<Instruction>, IRError> {
 
     // get rid of newlines.
     loop {
@@ -619,29 +1442,24 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         }
     }
 
-    let bytecode: Bytecode;
+    let instruction: Instruction;
     let opcode = peek_result(*serialized_line, tokens, *idx)?;
     match opcode {
 
     // declarations.
-    IRTok::Int => {
+    IRTok::Int | IRTok::Long => {
+        let typ = if matches!(opcode, IRTok::Long) { Type::Long } else { Type::Int };
         *idx += 1;
         let ident = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => ident,
         _ => return error(*serialized_line, String::from("invalid instruction. expected identifier like '%int variable'")),
         };
 
-        if let Some(_) = function.variables.get(ident) {
-             let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
-             return error(*serialized_line, f);
-        } else {
-             let id = get_id(function);
-             function.variables.insert(ident.clone(), VariableType::IntVar(id));
-             bytecode = Bytecode::Int(id);
-        }
+        instruction = Instruction::Declare(typ, ident.clone());
     }
 
-    IRTok::IntArray => {
+    IRTok::IntArray | IRTok::LongArray => {
+        let typ = if matches!(opcode, IRTok::LongArray) { Type::Long } else { Type::Int };
         *idx += 1;
         let ident = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => ident,
@@ -660,25 +1478,14 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         _ => {return error(*serialized_line, String::from("invalid instruction. expected format like '%int[] array, 10'"));}
         };
 
-        if num <= 0 {
-          return error(*serialized_line, String::from("array size cannot be less than or equal to zero."));
-        }
-
-        if let Some(_) = function.variables.get(ident) {
-             let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
-             return error(*serialized_line, f);
-        } else {
-             let id = get_id(function);
-             function.variables.insert(ident.clone(), VariableType::ArrayVar(id, num));
-             bytecode = Bytecode::IntArray(id, num);
-        }
+        instruction = Instruction::DeclareArray(typ, ident.clone(), num);
     }
 
     // function calling routines.
     IRTok::Call => {
         *idx += 1;
-        let op = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
+        let dest = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(ident) => ident,
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%call value, function(a,b)'")),
         };
 
@@ -686,12 +1493,8 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             return error(*serialized_line, String::from("invalid instruction. missing comma ',' in between '%call value, function(a,b)'"));
         }
 
-        let func_id = match peek_result(*serialized_line, tokens, *idx)? {
-        IRTok::Var(_) => {
-            let func_id = *idx;
-            *idx += 1;
-            func_id 
-        }
+        let func_name = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(func_name) => func_name,
         _ => return error(*serialized_line, String::from("invalid instruction. expected function name from '%call value, function(a,b)'")),
         };
 
@@ -699,14 +1502,13 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             return error(*serialized_line, String::from("invalid instruction. expected '(' in between '%call value, function(a,b)'"));
         }
 
-        let mut parameters = vec![];
+        let mut arguments = vec![];
         loop {
             match peek_result(*serialized_line, tokens, *idx)? {
             IRTok::RParen => break,
             IRTok::Var(ident) => {
                 *idx += 1;
-                let param = lookup_integer_variable_id(*serialized_line, function, ident)?;
-                parameters.push(param);
+                arguments.push(Operand::Var(ident.clone()));
                 if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::Comma) {
                     *idx += 1;
                 }
@@ -714,7 +1516,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
             IRTok::Num(num) => {
                 *idx += 1;
-                parameters.push(Op::Num(*num));
+                arguments.push(Operand::Num(*num));
                 if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::Comma) {
                     *idx += 1;
                 }
@@ -727,105 +1529,82 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::RParen) {
             return error(*serialized_line, String::from("invalid instruction. missing ')' in between '%call value, function(a,b)'"));
         }
-        bytecode = Bytecode::Call(op, func_id, parameters);
+        instruction = Instruction::Call(dest.clone(), func_name.clone(), arguments);
     }
 
     IRTok::Return => {
         *idx += 1;
-        let op = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
-        IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%ret variable'")),
-        };
-        bytecode = Bytecode::Return(op);
+        instruction = Instruction::Return(parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%ret variable'")?);
     }
 
     // input/output routines.
     IRTok::Out => {
         *idx += 1;
-        let src = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
-        IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%out variable'")),
+        instruction = Instruction::Out(parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%out variable'")?);
+    }
+
+    IRTok::OutStr => {
+        *idx += 1;
+        let name = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(name) => name,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%out_str $0'")),
         };
-        bytecode = Bytecode::Out(src);
+        instruction = Instruction::OutStr(name.clone());
+    }
+
+    IRTok::OutInt => {
+        *idx += 1;
+        instruction = Instruction::OutInt(parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%out_int variable'")?);
+    }
+
+    IRTok::OutLine => {
+        *idx += 1;
+        instruction = Instruction::OutLine;
     }
 
     IRTok::In => {
         *idx += 1;
-        let src = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
+        let dest = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(ident) => ident,
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%input variable'")),
         };
-        bytecode = Bytecode::In(src);
+        instruction = Instruction::In(dest.clone());
+    }
+
+    IRTok::OutChar => {
+        *idx += 1;
+        instruction = Instruction::OutChar(parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%outc variable'")?);
+    }
+
+    IRTok::InChar => {
+        *idx += 1;
+        let dest = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(ident) => ident,
+        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%inputc variable'")),
+        };
+        instruction = Instruction::InChar(dest.clone());
     }
 
     // mathematical operators.
     IRTok::Mov => {
         *idx += 1;
         match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => {
-            let dest = lookup_variable_dest_id(*serialized_line, function, ident)?;
-
+        IRTok::Var(dest) => {
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
                 return error(*serialized_line, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
             }
             
-            let src = match next_result(*serialized_line, tokens, idx)? {
-            IRTok::Var(ident) => {
-                if let Some(id) = function.variables.get(ident) {
-                     match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
-         
-                     VariableType::ArrayVar(_,_) => {
-                         let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                         return error(*serialized_line, f);
-                     }
-         
-                     }
-                } else {
-                     let f = format!("invalid instruction. identifier '{}' has not been declared", ident);
-                     return error(*serialized_line, f);
-                }
-            }
+            instruction = match next_result(*serialized_line, tokens, idx)? {
+            IRTok::Var(ident) => Instruction::Mov(dest.clone(), Operand::Var(ident.clone())),
 
             IRTok::LBrace => {
                 match (next_result(*serialized_line, tokens,idx)?, next_result(*serialized_line, tokens,idx)?, next_result(*serialized_line, tokens,idx)?, next_result(*serialized_line, tokens,idx)?) {
                 (IRTok::Var(array), IRTok::Plus, IRTok::Num(index), IRTok::RBrace) => {
-                    if let Some(id) = function.variables.get(array) {
-                        match id {
-                        VariableType::ArrayVar(id,_) => {
-                            MemRead::ArrayRead(*id, Op::Num(*index))
-                        }
-                        _ => {
-                           let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
-                           return error(*serialized_line, f);
-                        }
-
-                        }
-                    } else {
-                        let f = format!("invalid instruction. no such identifier '{}'.", array);
-                        return error(*serialized_line, f);
-                    }
+                    Instruction::Load(dest.clone(), array.clone(), Operand::Num(*index))
                 }
 
                 (IRTok::Var(array), IRTok::Plus, IRTok::Var(variable), IRTok::RBrace) => {
-                    if let Some(id) = function.variables.get(array) {
-                        match id {
-                        VariableType::ArrayVar(id,_) => {
-                            
-                            MemRead::ArrayRead(*id, lookup_integer_variable_id(*serialized_line, function, variable)?)
-                        }
-                        _ => {
-                           let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
-                           return error(*serialized_line, f);
-                        }
-
-                        }
-                    } else {
-                        let f = format!("invalid instruction. no such identifier '{}'.", array);
-                        return error(*serialized_line, f);
-                    }
+                    Instruction::Load(dest.clone(), array.clone(), Operand::Var(variable.clone()))
                 }
 
                 _ => return error(*serialized_line, String::from("invalid '%mov' statement. expected '%mov var, [array + index]'")),
@@ -834,33 +1613,14 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
             }
 
-            IRTok::Num(num) => MemRead::Number(*num),
+            IRTok::Num(num) => Instruction::Mov(dest.clone(), Operand::Num(*num)),
             _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
             };
-
-            bytecode = Bytecode::Mov(MemWrite::IntVar(dest), src);
         }
 
         IRTok::LBrace => {
-            let dest = match next_result(*serialized_line, tokens, idx)? {
-            IRTok::Var(ident) => {
-                if let Some(id) = function.variables.get(ident) {
-                    match id {
-                    VariableType::IntVar(_) => {
-                        let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                        return error(*serialized_line, f);
-                    }
-         
-                    VariableType::ArrayVar(dest,_) => {
-                        *dest
-                    }
-
-                    }
-                } else {
-                    return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
-                }
-            }
-
+            let array = match next_result(*serialized_line, tokens, idx)? {
+            IRTok::Var(ident) => ident,
             _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
             };
 
@@ -868,11 +1628,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
-            let index = match next_result(*serialized_line, tokens, idx)? {
-            IRTok::Var(id) => lookup_integer_variable_id(*serialized_line, function, id)?,
-            IRTok::Num(num) => Op::Num(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
-            };
+            let element = parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%mov [array + 10], 10'")?;
 
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::RBrace) {
                 return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
@@ -882,29 +1638,9 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
-            let src = match next_result(*serialized_line, tokens, idx)? {
-            IRTok::Var(ident) => {
-                if let Some(id) = function.variables.get(ident) {
-                     match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
-         
-                     VariableType::ArrayVar(_,_) => {
-                         let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                         return error(*serialized_line, f);
-                     }
-         
-                     }
-                } else {
-                     let f = format!("invalid instruction. identifier '{}' has not been defined.", ident);
-                     return error(*serialized_line, f);
-                }
-            }
-
-            IRTok::Num(num) => MemRead::Number(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
-            };
+            let src = parse_operand(*serialized_line, tokens, idx, "invalid instruction. expected format like '%mov variable, 10'")?;
 
-            bytecode = Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src);
+            instruction = Instruction::Store(array.clone(), element, src);
         }
 
         _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
@@ -912,81 +1648,32 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
  
     }
 
-    IRTok::Add => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Add(dest, src1, src2);
-    }
-
-    IRTok::Sub => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Sub(dest, src1, src2);
-    }
-
-    IRTok::Mult => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Mult(dest, src1, src2);
-    }
-
-    IRTok::Div => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Div(dest, src1, src2);
-    }
-
-    IRTok::Mod => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Mod(dest, src1, src2);
-    }
-
-    // comparison operators.
-    IRTok::LessThan => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::LessThan(dest, src1, src2);
-    }
-
-    IRTok::LessEqual => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::LessEqual(dest, src1, src2);
-    }
-
-    IRTok::NotEqual => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::NotEqual(dest, src1, src2);
-    }
-
-    IRTok::Equal => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Equal(dest, src1, src2);
-    }
-
-    IRTok::GreaterEqual => {
-        *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::GreaterEqual(dest, src1, src2);
-    }
-
-    IRTok::GreaterThan => {
+    IRTok::Add | IRTok::Sub | IRTok::Mult | IRTok::Div | IRTok::Mod
+    | IRTok::LessThan | IRTok::LessEqual | IRTok::NotEqual | IRTok::Equal | IRTok::GreaterEqual | IRTok::GreaterThan => {
+        let op = match opcode {
+        IRTok::Add => BinaryOp::Add,
+        IRTok::Sub => BinaryOp::Sub,
+        IRTok::Mult => BinaryOp::Mult,
+        IRTok::Div => BinaryOp::Div,
+        IRTok::Mod => BinaryOp::Mod,
+        IRTok::LessThan => BinaryOp::LessThan,
+        IRTok::LessEqual => BinaryOp::LessEqual,
+        IRTok::NotEqual => BinaryOp::NotEqual,
+        IRTok::Equal => BinaryOp::Equal,
+        IRTok::GreaterEqual => BinaryOp::GreaterEqual,
+        _ => BinaryOp::GreaterThan,
+        };
         *idx += 1;
-        let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::GreaterThan(dest, src1, src2);
+        let (dest, src1, src2) = addr_code3(*serialized_line, tokens, idx)?;
+        instruction = Instruction::Binary(op, dest, src1, src2);
     }
 
     // labels/branching
-    //Label,
     IRTok::Jump => {
         *idx += 1;
-        match peek_result(*serialized_line, tokens, *idx)? {
-        IRTok::Label(_) => {
-            bytecode = Bytecode::Jmp(*idx);
-            *idx += 1;
+        match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Label(name) => {
+            instruction = Instruction::Jump(label_name(name));
         }
 
         _ => return error(*serialized_line, String::from("%jmp requires a label such as '%jmp :label'")),
@@ -995,12 +1682,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
     IRTok::Label(name) => {
         *idx += 1;
-        if let None = labels_hash.get(name) {
-            labels_hash.insert(name.clone(), line);
-        } else {
-            return error(*serialized_line, format!("label {} already defined.", name));
-        }
-        bytecode = Bytecode::Label(line);
+        instruction = Instruction::Label(label_name(name));
         if !matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::EndInstr) {
             return error(*serialized_line, format!("invalid opcode '{}'. labels can be declared using ':label'", name));
         }
@@ -1008,20 +1690,15 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
     IRTok::BranchIf => {
         *idx += 1;
-        let dest = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
-        IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("%branch_if requires an identifier 'TF'")),
-        };
+        let condition = parse_operand(*serialized_line, tokens, idx, "%branch_if requires an identifier 'TF'")?;
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
             return error(*serialized_line, String::from("missing comma ',' from %branch_if instruction."));
         }
 
-        match peek_result(*serialized_line, tokens, *idx)? {
-        IRTok::Label(_) => {
-            bytecode = Bytecode::BranchIf(dest, *idx);
-            *idx += 1;
+        match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Label(name) => {
+            instruction = Instruction::BranchIf(condition, label_name(name));
         }
         _ => return error(*serialized_line, String::from("%branch_if requires a label ':label'. (e.g. '%branch_if TF, :label')")),
         }
@@ -1029,28 +1706,22 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
     IRTok::BranchIfNot => {
         *idx += 1;
-        let dest = match next_result(*serialized_line, tokens, idx)? {
-        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
-        IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("%branch_ifn requires an identifier 'TF'")),
-        };
+        let condition = parse_operand(*serialized_line, tokens, idx, "%branch_ifn requires an identifier 'TF'")?;
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
             return error(*serialized_line, String::from("missing ',' from %branch_ifn instruction."));
         }
 
-        match peek_result(*serialized_line, tokens, *idx)? {
-        IRTok::Label(_) => {
-            bytecode = Bytecode::BranchIfn(dest, *idx);
-            *idx += 1;
+        match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Label(name) => {
+            instruction = Instruction::BranchIfn(condition, label_name(name));
         }
         _ => return error(*serialized_line, String::from("%branch_ifn requires a label ':label'. (e.g. '%branch_ifn TF, :label')")),
         }
     }
 
     IRTok::EndFunc => {
-        bytecode = Bytecode::End;
-        return Ok(bytecode);
+        return Ok(None);
     }
 
     IRTok::InvalidInstruction(err) => {
@@ -1069,12 +1740,12 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
 
     *serialized_line += 1;
 
-    return Ok(bytecode);
+    return Ok(Some(instruction));
 }
 
-fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op, Op), IRError> {
+fn addr_code3(serialized_line: usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(String, Operand, Operand), IRError> {
     let dest = match next_result(serialized_line, tokens, idx)? {
-    IRTok::Var(ident) => lookup_variable_dest_id(serialized_line, function, ident)?,
+    IRTok::Var(ident) => ident.clone(),
     _ => return error(serialized_line, String::from("invalid dest.")),
     };
 
@@ -1082,21 +1753,13 @@ fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<
         return error(serialized_line, String::from("invalid instruction. expected comma between dest and src1."));
     }
     
-    let src1 = match next_result(serialized_line, tokens, idx)? {
-    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
-    IRTok::Num(num) => Op::Num(*num),
-    _ => return error(serialized_line, String::from("invalid src1.")),
-    };
+    let src1 = parse_operand(serialized_line, tokens, idx, "invalid src1.")?;
 
     if !matches!(next_result(serialized_line, tokens, idx)?, IRTok::Comma) {
         return error(serialized_line, String::from("invalid instruction. expected comma between src1 and src2."));
     }
 
-    let src2 = match next_result(serialized_line, tokens, idx)? {
-    IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
-    IRTok::Num(num) => Op::Num(*num),
-    _ => return error(serialized_line, String::from("invalid src2.")),
-    };
+    let src2 = parse_operand(serialized_line, tokens, idx, "invalid src2.")?;
 
     return Ok((dest, src1, src2));
 }
@@ -1156,10 +1819,18 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         "%endfunc" => Some(EndFunc),
         "%int" => Some(Int),
         "%int[]" => Some(IntArray),
+        "%long" => Some(Long),
+        "%long[]" => Some(LongArray),
         "%call" => Some(Call),
         "%ret" => Some(Return),
         "%out" => Some(Out),
+        "%out_str" => Some(OutStr),
+        "%out_int" => Some(OutInt),
+        "%out_nl" => Some(OutLine),
+        "%str" => Some(Str),
         "%input" => Some(In),
+        "%outc" => Some(OutChar),
+        "%inputc" => Some(InChar),
         "%mov" => Some(Mov),
         "%add" => Some(Add),
         "%sub" => Some(Sub),
@@ -1179,25 +1850,54 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         }
     }
 
+    fn string_literal(code: &str) -> (Option<IRTok>, &str) {
+        let mut text = String::new();
+        let mut escaped = false;
+        for (i, c) in code.char_indices().skip(1) {
+            if escaped {
+                match c {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                '0' => text.push('\0'),
+                '\\' => text.push('\\'),
+                '"' => text.push('"'),
+                _ => return (Some(IRTok::InvalidInstruction(String::from(&code[..i + c.len_utf8()]))), &code[i + c.len_utf8()..]),
+                }
+                escaped = false;
+                continue;
+            }
+
+            match c {
+            '\\' => escaped = true,
+            '"' => return (Some(IRTok::StrLit(text)), &code[i + 1..]),
+            '\n' => return (Some(IRTok::InvalidInstruction(String::from(&code[..i]))), &code[i..]),
+            _ => text.push(c),
+            }
+        }
+
+        return (Some(IRTok::InvalidInstruction(String::from(code))), "");
+    }
+
+    // a number too big for 64 bits is reported by the parser like any other bad token.
     fn num_literal(s: &str) -> Option<IRTok> {
-        match s.parse::<i32>() {
+        match s.parse::<i64>() {
         Ok(val) => Some(IRTok::Num(val)),
-        Err(_) => None,
+        Err(_) => Some(IRTok::InvalidInstruction(String::from(s))),
         }
     }
 
     // skip left whitespace.
-    for (i, c) in code.chars().enumerate() {
-        if c.is_whitespace() && c != '\n' {
-            continue;
-        }
-        code = &code[i..];
-        break;
+    code = code.trim_start_matches(|c: char| c.is_whitespace() && c != '\n');
+
+    // string literals can hold any character, so they skip the state machine.
+    if code.starts_with('"') {
+        return string_literal(code);
     }
  
     let mut state = StateMachine::Initial;
 
-    for (i, c) in code.chars().enumerate() {
+    for (i, c) in code.char_indices() {
         state = match state {
 
         StateMachine::Initial => {
@@ -1294,10 +1994,17 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         return (Some(tok), "");
     }
 
+    // the last line of a file written by hand may not end with a newline.
+    StateMachine::Label => {
+        return (Some(IRTok::Label(String::from(code))), "");
+    }
 
-    _ => {
-        println!("{:?} {}", state, code);
-        todo!()
+    StateMachine::Num => {
+        return (num_literal(code), "");
+    }
+
+    StateMachine::Initial | StateMachine::Comments => {
+        return (None, "");
     }
 
     }
@@ -1336,6 +2043,109 @@ mod ir_tests {
 
         let code = "; This is a comment\n%mov";
         assert!(matches!(lex_ir_token(code), (Some(IRTok::EndInstr), "%mov")));
+
+        assert!(matches!(lex_ir_token("%outc"), (Some(IRTok::OutChar), _)));
+        assert!(matches!(lex_ir_token("%inputc"), (Some(IRTok::InChar), _)));
+        assert!(matches!(lex_ir_token("%str"), (Some(IRTok::Str), _)));
+        assert!(matches!(lex_ir_token("%out_str"), (Some(IRTok::OutStr), _)));
+        assert!(matches!(lex_ir_token("%out_int"), (Some(IRTok::OutInt), _)));
+        assert!(matches!(lex_ir_token("%out_nl\n"), (Some(IRTok::OutLine), "\n")));
+        assert!(matches!(lex_ir_token(" \"a; b\\n\\\"\"\n"), (Some(IRTok::StrLit(text)), "\n") if text == "a; b\n\""));
+        assert!(matches!(lex_ir_token("\"abc\n"), (Some(IRTok::InvalidInstruction(_)), "\n")));
+    }
+
+    #[test]
+    fn ir_strings() {
+        let code = "%str $0, \"x = \"\n%func main()\n%out_str $0\n%out_int 5\n%out_nl\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+        assert!(matches!(&functions[0].body[0], Bytecode::OutStr(text) if text == "x = "));
+        assert!(matches!(functions[0].body[1], Bytecode::OutInt(Op::Num(5))));
+        assert!(matches!(functions[0].body[2], Bytecode::OutLine));
+
+        // undeclared string constant
+        let code = "%func main()\n%out_str $0\n%endfunc\n";
+        assert!(parse_ir(code).is_err());
+
+        // duplicate string constant
+        let code = "%str $0, \"a\"\n%str $0, \"b\"\n%func main()\n%endfunc\n";
+        assert!(parse_ir(code).is_err());
+    }
+
+    #[test]
+    fn ir_long() {
+        assert!(matches!(lex_ir_token("%long"), (Some(IRTok::Long), _)));
+        assert!(matches!(lex_ir_token("%long[]"), (Some(IRTok::LongArray), _)));
+        assert!(matches!(lex_ir_token("3000000000\n"), (Some(IRTok::Num(3000000000)), _)));
+
+        // the same value is kept in a long but cut down to 32 bits in an int
+        let code = "%func main()\n%long a\n%int b\n%mov a, 3000000000\n%mov b, a\n%sub a, a, b\n%ret a\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+        let result = run_bytecode(&mut Vm::new(), &functions[0], &functions, &vec![], None).ok().unwrap();
+        assert_eq!(result, 3000000000 - (3000000000i64 as i32 as i64));
+
+        // long parameters and return values pass through calls untouched
+        let code = "%func twice(%long n)\n%long t\n%add t, n, n\n%ret t\n%endfunc\n%func main()\n%long r\n%call r, twice(3000000000)\n%ret r\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+        let result = run_bytecode(&mut Vm::new(), &functions[1], &functions, &vec![], None).ok().unwrap();
+        assert_eq!(result, 6000000000);
+    }
+
+    #[test]
+    fn ir_overflow() {
+        let code = "%func main()\n%int a\n%long b\n%add a, 2147483647, 1\n%mult b, 9223372036854775807, 2\n%ret a\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+
+        // wrapping is the default
+        let result = run_bytecode(&mut Vm::new(), &functions[0], &functions, &vec![], None).ok().unwrap();
+        assert_eq!(result, i32::MIN as i64);
+
+        // checked mode names the instruction and the function
+        let options = RunOptions { checked_arithmetic: true, ..RunOptions::default() };
+        match run_bytecode(&mut Vm::with_options(options.clone()), &functions[0], &functions, &vec![], None) {
+        Ok(_) => panic!("expected an overflow error"),
+        Err(e) => {
+            assert!(e.message.contains("'%add'"));
+            assert!(e.message.contains("'main'"));
+        }
+        }
+
+        // MIN / -1 only overflows in checked mode
+        let code = "%func main()\n%int a\n%int m\n%sub m, 0, 1\n%sub a, m, 2147483647\n%div a, a, m\n%ret a\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+        let result = run_bytecode(&mut Vm::new(), &functions[0], &functions, &vec![], None).ok().unwrap();
+        assert_eq!(result, i32::MIN as i64);
+        assert!(run_bytecode(&mut Vm::with_options(options.clone()), &functions[0], &functions, &vec![], None).is_err());
+
+        // values that fit are unaffected
+        let code = "%func main()\n%long a\n%mult a, 3000000000, 3\n%ret a\n%endfunc\n";
+        let functions = parse_ir(code).unwrap().functions;
+        let result = run_bytecode(&mut Vm::with_options(options.clone()), &functions[0], &functions, &vec![], None).ok().unwrap();
+        assert_eq!(result, 9000000000);
+    }
+
+    #[test]
+    fn ir_module_round_trip() {
+        use crate::phases::lexer::lex;
+        use crate::phases::semantic_parser::parse_program_ir;
+
+        // printing a module from the frontend and reading it back gives the same module
+        let code = "func f(int a, long b) -> long { long[2] c; c[1] = long(a) + b; return c[1]; }
+                    func main() { int i; i = 0; while i < 3 { print(\"i=\", f(i, 2L)); i = i + 1; } }";
+        let module = parse_program_ir(&lex(code).unwrap(), &mut 0).unwrap();
+        let text = module.to_string();
+        let parsed = parse_module(&text).unwrap();
+        assert_eq!(parsed, module);
+        assert!(load_module(&module).is_ok());
+
+        // loading reports the same line for a module as for its text
+        let code = "%func main()\n%int a\n%mov a, b\n%endfunc\n";
+        let module = parse_module(code).unwrap();
+        assert_eq!(module.functions[0].line, 1);
+        assert_eq!(module.functions[0].lines, vec![2, 3]);
+        match load_module(&module) {
+        Ok(_) => panic!("expected an undeclared identifier error"),
+        Err(e) => assert_eq!(e.line, 3),
+        }
     }
 
     #[test]
@@ -1401,6 +2211,8 @@ enum IRTok {
     // declarations.
     Int,
     IntArray,
+    Long,
+    LongArray,
 
     // function calling routines.
     Call,
@@ -1408,7 +2220,15 @@ enum IRTok {
 
     // input/output routines.
     Out,
+    OutStr,
+    OutInt,
+    OutLine,
     In,
+    OutChar,
+    InChar,
+
+    // string constants.
+    Str,
 
     // mathematical operators.
     Mov,
@@ -1443,20 +2263,21 @@ enum IRTok {
 
     InvalidInstruction(String),
 
-    Num(i32),
+    Num(i64),
     Var(String),
+    StrLit(String),
 }
 
 #[derive(Debug, Clone)]
 enum Op {
-    Num(i32),
+    Num(i64),
     Var(i32),
 }
 
 #[derive(Debug)]
 enum VariableType {
     IntVar(i32),
-    ArrayVar(i32, i32),
+    ArrayVar(i32),
 }
 
 #[derive(Debug)]
@@ -1468,22 +2289,22 @@ enum MemWrite {
 #[derive(Debug)]
 enum MemRead {
     IntVar(i32),
-    Number(i32),
+    Number(i64),
     ArrayRead(i32, Op),
 }
 
 // TODO: array bounds check.
-fn read_memory(variables: &HashMap<i32, i32>, arrays: &HashMap<i32, Vec<i32>>, read: &MemRead) -> Result<i32, IRError> {
+fn read_memory(variables: &[i64], arrays: &[Vec<i64>], read: &MemRead) -> Result<i64, RuntimeError> {
     match read {
-    MemRead::IntVar(id) => Ok(*variables.get(&id).unwrap()),
+    MemRead::IntVar(id) => Ok(variables[*id as usize]),
     MemRead::Number(number) => Ok(*number),
     MemRead::ArrayRead(id, index) => {
-        let array = arrays.get(&id).unwrap();
+        let array = &arrays[*id as usize];
         let variable = read_integer_value(&variables, &index);
         if variable >= 0 && (variable as usize) < array.len() {
             Ok(array[variable as usize])
         } else {
-            error(MAX_LINE, format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", variable, array.len()))
+            runtime_error(format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", variable, array.len()))
         }
     }
     }
@@ -1495,15 +2316,20 @@ enum Bytecode {
 
     // EndFunc
     End,
-    Label(usize),
+    Label(String),
 
     // declarations.
     Int(i32),
-    IntArray(i32, i32),
+    IntArray(i32),
 
     // input/output routines.
     Out(Op),
+    OutStr(String),
+    OutInt(Op),
+    OutLine,
     In(i32),
+    OutChar(Op),
+    InChar(i32),
 
     // mathematical operators.
     Mov(MemWrite, MemRead),
@@ -1529,4 +2355,127 @@ enum Bytecode {
     BranchIfn(Op, usize),
 }
 
+impl Bytecode {
+    fn kind(&self) -> &'static str {
+        match self {
+        Bytecode::End => "End",
+        Bytecode::Label(_) => "Label",
+        Bytecode::Int(_) => "Int",
+        Bytecode::IntArray(_) => "IntArray",
+        Bytecode::Out(_) => "Out",
+        Bytecode::OutStr(_) => "OutStr",
+        Bytecode::OutInt(_) => "OutInt",
+        Bytecode::OutLine => "OutLine",
+        Bytecode::In(_) => "In",
+        Bytecode::OutChar(_) => "OutChar",
+        Bytecode::InChar(_) => "InChar",
+        Bytecode::Mov(_, _) => "Mov",
+        Bytecode::Add(_, _, _) => "Add",
+        Bytecode::Sub(_, _, _) => "Sub",
+        Bytecode::Mult(_, _, _) => "Mult",
+        Bytecode::Div(_, _, _) => "Div",
+        Bytecode::Mod(_, _, _) => "Mod",
+        Bytecode::Call(_, _, _) => "Call",
+        Bytecode::LessThan(_, _, _) => "LessThan",
+        Bytecode::LessEqual(_, _, _) => "LessEqual",
+        Bytecode::NotEqual(_, _, _) => "NotEqual",
+        Bytecode::Equal(_, _, _) => "Equal",
+        Bytecode::GreaterEqual(_, _, _) => "GreaterEqual",
+        Bytecode::GreaterThan(_, _, _) => "GreaterThan",
+        Bytecode::Return(_) => "Return",
+        Bytecode::Jmp(_) => "Jmp",
+        Bytecode::BranchIf(_, _) => "BranchIf",
+        Bytecode::BranchIfn(_, _) => "BranchIfn",
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        Op::Num(num) => write!(f, "{num}"),
+        Op::Var(id) => write!(f, "s{id}"),
+        }
+    }
+}
+
+impl fmt::Display for MemWrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        MemWrite::IntVar(id) => write!(f, "s{id}"),
+        MemWrite::ArrayWrite(id, index) => write!(f, "s{id}[{index}]"),
+        }
+    }
+}
+
+impl fmt::Display for MemRead {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        MemRead::IntVar(id) => write!(f, "s{id}"),
+        MemRead::Number(num) => write!(f, "{num}"),
+        MemRead::ArrayRead(id, index) => write!(f, "s{id}[{index}]"),
+        }
+    }
+}
+
+// one bytecode, with calls showing the name of the function and jumps the
+// index they go to.
+fn write_bytecode(f: &mut fmt::Formatter, bytecode: &Bytecode, functions: &[FunctionBytecode]) -> fmt::Result {
+    let kind = bytecode.kind();
+    match bytecode {
+    Bytecode::End | Bytecode::OutLine => write!(f, "{kind}"),
+    Bytecode::Label(name) => write!(f, "{kind} {name}"),
+    Bytecode::Int(id) | Bytecode::IntArray(id) | Bytecode::In(id) | Bytecode::InChar(id) => write!(f, "{kind} s{id}"),
+    Bytecode::Out(value) | Bytecode::OutInt(value) | Bytecode::OutChar(value) | Bytecode::Return(value) => write!(f, "{kind} {value}"),
+    Bytecode::OutStr(text) => write!(f, "{kind} {}", ir::escape_string(text)),
+    Bytecode::Mov(dest, src) => write!(f, "{kind} {dest}, {src}"),
+    Bytecode::Add(dest, a, b) | Bytecode::Sub(dest, a, b) | Bytecode::Mult(dest, a, b)
+    | Bytecode::Div(dest, a, b) | Bytecode::Mod(dest, a, b)
+    | Bytecode::LessThan(dest, a, b) | Bytecode::LessEqual(dest, a, b) | Bytecode::NotEqual(dest, a, b)
+    | Bytecode::Equal(dest, a, b) | Bytecode::GreaterEqual(dest, a, b) | Bytecode::GreaterThan(dest, a, b) => write!(f, "{kind} s{dest}, {a}, {b}"),
+    Bytecode::Call(dest, function, arguments) => {
+        let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+        write!(f, "{kind} s{dest}, {}({})", functions[*function].name, arguments.join(", "))
+    }
+    Bytecode::Jmp(target) => write!(f, "{kind} @{target}"),
+    Bytecode::BranchIf(condition, target) | Bytecode::BranchIfn(condition, target) => write!(f, "{kind} {condition}, @{target}"),
+    }
+}
+
+// a listing of the loaded bytecode, one function after another:
+//
+// func add: 2 parameters, 3 slots, 0 array bytes
+//   slots: s0 a, s1 b, s2 t0
+//      0  Int s2
+//      1  Add s2, s0, s1
+//      2  Return s2
+//      3  End
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for function in &self.functions {
+            writeln!(f, "func {}: {} parameters, {} slots, {} array bytes", function.name, function.parameters, function.slots, function.array_memory)?;
+
+            let mut names = vec![String::new(); function.slots];
+            for (name, variable) in &function.variables {
+                match variable {
+                VariableType::IntVar(id) => names[*id as usize] = name.clone(),
+                VariableType::ArrayVar(id) => names[*id as usize] = format!("{name}[]"),
+                }
+            }
+            let slots: Vec<String> = names.iter().enumerate().map(|(id, name)| {
+                let long = if function.long_variables[id] { " (long)" } else { "" };
+                format!("s{id} {name}{long}")
+            }).collect();
+            if !slots.is_empty() {
+                writeln!(f, "  slots: {}", slots.join(", "))?;
+            }
 
+            for (i, bytecode) in function.body.iter().enumerate() {
+                write!(f, "  {:>4}  ", i)?;
+                write_bytecode(f, bytecode, &self.functions)?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,658 @@
+// src/interpreter/binary.rs
+// The .ttb file format: a loaded program saved as bytecode, so it can be run
+// again without going through the frontend. Numbers are LEB128 varints, with
+// signed ones zigzagged first, and a string is its length then its UTF-8 bytes.
+//
+// magic       "TTBC"
+// version     2 bytes, little endian
+// flags       1 byte, bit 0 is set when there is a debug section
+// constants   count, then each string. names, labels and %out_str text
+// functions   count, then for each one:
+//               name (a constant), parameters, slots,
+//               a bit per slot for the long ones, packed 8 to a byte,
+//               arrays count, then (slot, length) for each,
+//               bytecodes count, then an opcode byte and operands for each
+// debug       for each function:
+//               variables count, then (name, slot) for each,
+//               then for each bytecode its IR line and source line and column (0 for none),
+//               then for each bytecode but the final End the instruction's text
+//
+// Everything read back is checked, so a broken or hand-made file gives an
+// error instead of crashing the interpreter.
+
+use std::collections::HashMap;
+
+use super::*;
+
+const MAGIC: &[u8; 4] = b"TTBC";
+const VERSION: u16 = 1;
+const FLAG_DEBUG: u8 = 1;
+
+// the tag byte in front of each kind of operand.
+const OP_NUM: u8 = 0;
+const OP_VAR: u8 = 1;
+const WRITE_VAR: u8 = 0;
+const WRITE_ARRAY: u8 = 1;
+const READ_VAR: u8 = 0;
+const READ_NUM: u8 = 1;
+const READ_ARRAY: u8 = 2;
+
+impl Program {
+    // the program as the bytes of a .ttb file. the debug section keeps the
+    // names, lines and instructions the debugger, tracer and errors show.
+    pub fn to_binary(&self, debug: bool) -> Vec<u8> {
+        let mut constants = Constants { strings: vec![], ids: HashMap::new() };
+        let mut functions = vec![];
+        write_unsigned(&mut functions, self.functions.len() as u64);
+        for function in &self.functions {
+            write_function(&mut functions, &mut constants, function);
+        }
+
+        let mut debug_info = vec![];
+        if debug {
+            for function in &self.functions {
+                write_debug_info(&mut debug_info, &mut constants, function);
+            }
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(if debug { FLAG_DEBUG } else { 0 });
+        write_unsigned(&mut bytes, constants.strings.len() as u64);
+        for text in &constants.strings {
+            write_string(&mut bytes, text);
+        }
+        bytes.extend(functions);
+        bytes.extend(debug_info);
+        bytes
+    }
+
+    // false for a program read from a .ttb file written without its debug
+    // section, which hooks cannot show instructions or variables for.
+    pub fn has_debug_info(&self) -> bool {
+        self.functions.iter().all(|function| function.instructions.len() + 1 == function.body.len())
+    }
+
+    // reads a .ttb file written by to_binary.
+    pub fn from_binary(bytes: &[u8]) -> Result<Program, IRError> {
+        let mut reader = Reader { bytes, at: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return error(MAX_LINE, String::from("not a bytecode file, it does not start with 'TTBC'."));
+        }
+        let version = u16::from_le_bytes([reader.byte()?, reader.byte()?]);
+        if version != VERSION {
+            return error(MAX_LINE, format!("bytecode file version {version} is not supported, only version {VERSION} is."));
+        }
+        let flags = reader.byte()?;
+        if flags & !FLAG_DEBUG != 0 {
+            return error(MAX_LINE, format!("unknown bytecode file flags {flags:#04x}."));
+        }
+
+        let mut constants = vec![];
+        for _ in 0..reader.count()? {
+            constants.push(reader.string()?);
+        }
+
+        let mut functions = vec![];
+        for _ in 0..reader.count()? {
+            functions.push(read_function(&mut reader, &constants)?);
+        }
+
+        let mut function_ids: HashMap<&str, usize> = HashMap::new();
+        for (i, function) in functions.iter().enumerate() {
+            if function_ids.insert(&function.name, i).is_some() {
+                return error(MAX_LINE, format!("Two functions with the same name {}", function.name));
+            }
+        }
+        if !function_ids.contains_key("main") {
+            return error(MAX_LINE, String::from("bytecode file does not have a main."));
+        }
+        for function in &functions {
+            check_function(function, functions.len())?;
+        }
+
+        if flags & FLAG_DEBUG != 0 {
+            for function in functions.iter_mut() {
+                read_debug_info(&mut reader, &constants, function)?;
+            }
+        }
+
+        if reader.at != bytes.len() {
+            return error(MAX_LINE, format!("{} bytes left over at the end of the bytecode file.", bytes.len() - reader.at));
+        }
+        Ok(Program { functions })
+    }
+}
+
+// each string is written once and referred to by its index.
+struct Constants {
+    strings: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+impl Constants {
+    fn id(&mut self, text: &str) -> u64 {
+        if let Some(id) = self.ids.get(text) {
+            return *id as u64;
+        }
+        let id = self.strings.len();
+        self.strings.push(String::from(text));
+        self.ids.insert(String::from(text), id);
+        id as u64
+    }
+}
+
+fn write_unsigned(bytes: &mut Vec<u8>, mut num: u64) {
+    loop {
+        let low = (num & 0x7f) as u8;
+        num >>= 7;
+        if num == 0 {
+            bytes.push(low);
+            return;
+        }
+        bytes.push(low | 0x80);
+    }
+}
+
+// zigzag, so small negative numbers stay short: 0, -1, 1, -2 become 0, 1, 2, 3.
+fn write_signed(bytes: &mut Vec<u8>, num: i64) {
+    write_unsigned(bytes, ((num << 1) ^ (num >> 63)) as u64);
+}
+
+fn write_string(bytes: &mut Vec<u8>, text: &str) {
+    write_unsigned(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+fn write_op(bytes: &mut Vec<u8>, op: &Op) {
+    match op {
+    Op::Num(num) => {
+        bytes.push(OP_NUM);
+        write_signed(bytes, *num);
+    }
+    Op::Var(id) => {
+        bytes.push(OP_VAR);
+        write_unsigned(bytes, *id as u64);
+    }
+    }
+}
+
+fn write_function(bytes: &mut Vec<u8>, constants: &mut Constants, function: &FunctionBytecode) {
+    write_unsigned(bytes, constants.id(&function.name));
+    write_unsigned(bytes, function.parameters as u64);
+    write_unsigned(bytes, function.slots as u64);
+    for chunk in function.long_variables.chunks(8) {
+        let mut bits = 0u8;
+        for (i, long) in chunk.iter().enumerate() {
+            if *long {
+                bits |= 1 << i;
+            }
+        }
+        bytes.push(bits);
+    }
+
+    write_unsigned(bytes, function.arrays.len() as u64);
+    for (id, length) in &function.arrays {
+        write_unsigned(bytes, *id as u64);
+        write_unsigned(bytes, *length as u64);
+    }
+
+    write_unsigned(bytes, function.body.len() as u64);
+    for bytecode in &function.body {
+        write_bytecode_binary(bytes, constants, bytecode);
+    }
+}
+
+// the opcode is the bytecode's place in the Bytecode enum.
+fn write_bytecode_binary(bytes: &mut Vec<u8>, constants: &mut Constants, bytecode: &Bytecode) {
+    match bytecode {
+    Bytecode::End => bytes.push(0),
+    Bytecode::Label(name) => {
+        bytes.push(1);
+        write_unsigned(bytes, constants.id(name));
+    }
+    Bytecode::Int(id) => {
+        bytes.push(2);
+        write_unsigned(bytes, *id as u64);
+    }
+    Bytecode::IntArray(id) => {
+        bytes.push(3);
+        write_unsigned(bytes, *id as u64);
+    }
+    Bytecode::Out(value) => {
+        bytes.push(4);
+        write_op(bytes, value);
+    }
+    Bytecode::OutStr(text) => {
+        bytes.push(5);
+        write_unsigned(bytes, constants.id(text));
+    }
+    Bytecode::OutInt(value) => {
+        bytes.push(6);
+        write_op(bytes, value);
+    }
+    Bytecode::OutLine => bytes.push(7),
+    Bytecode::In(id) => {
+        bytes.push(8);
+        write_unsigned(bytes, *id as u64);
+    }
+    Bytecode::OutChar(value) => {
+        bytes.push(9);
+        write_op(bytes, value);
+    }
+    Bytecode::InChar(id) => {
+        bytes.push(10);
+        write_unsigned(bytes, *id as u64);
+    }
+    Bytecode::Mov(dest, src) => {
+        bytes.push(11);
+        match dest {
+        MemWrite::IntVar(id) => {
+            bytes.push(WRITE_VAR);
+            write_unsigned(bytes, *id as u64);
+        }
+        MemWrite::ArrayWrite(id, index) => {
+            bytes.push(WRITE_ARRAY);
+            write_unsigned(bytes, *id as u64);
+            write_op(bytes, index);
+        }
+        }
+        match src {
+        MemRead::IntVar(id) => {
+            bytes.push(READ_VAR);
+            write_unsigned(bytes, *id as u64);
+        }
+        MemRead::Number(num) => {
+            bytes.push(READ_NUM);
+            write_signed(bytes, *num);
+        }
+        MemRead::ArrayRead(id, index) => {
+            bytes.push(READ_ARRAY);
+            write_unsigned(bytes, *id as u64);
+            write_op(bytes, index);
+        }
+        }
+    }
+    Bytecode::Add(dest, a, b) => write_three(bytes, 12, *dest, a, b),
+    Bytecode::Sub(dest, a, b) => write_three(bytes, 13, *dest, a, b),
+    Bytecode::Mult(dest, a, b) => write_three(bytes, 14, *dest, a, b),
+    Bytecode::Div(dest, a, b) => write_three(bytes, 15, *dest, a, b),
+    Bytecode::Mod(dest, a, b) => write_three(bytes, 16, *dest, a, b),
+    Bytecode::Call(dest, function, arguments) => {
+        bytes.push(17);
+        write_unsigned(bytes, *dest as u64);
+        write_unsigned(bytes, *function as u64);
+        write_unsigned(bytes, arguments.len() as u64);
+        for argument in arguments {
+            write_op(bytes, argument);
+        }
+    }
+    Bytecode::LessThan(dest, a, b) => write_three(bytes, 18, *dest, a, b),
+    Bytecode::LessEqual(dest, a, b) => write_three(bytes, 19, *dest, a, b),
+    Bytecode::NotEqual(dest, a, b) => write_three(bytes, 20, *dest, a, b),
+    Bytecode::Equal(dest, a, b) => write_three(bytes, 21, *dest, a, b),
+    Bytecode::GreaterEqual(dest, a, b) => write_three(bytes, 22, *dest, a, b),
+    Bytecode::GreaterThan(dest, a, b) => write_three(bytes, 23, *dest, a, b),
+    Bytecode::Return(value) => {
+        bytes.push(24);
+        write_op(bytes, value);
+    }
+    Bytecode::Jmp(target) => {
+        bytes.push(25);
+        write_unsigned(bytes, *target as u64);
+    }
+    Bytecode::BranchIf(condition, target) => {
+        bytes.push(26);
+        write_op(bytes, condition);
+        write_unsigned(bytes, *target as u64);
+    }
+    Bytecode::BranchIfn(condition, target) => {
+        bytes.push(27);
+        write_op(bytes, condition);
+        write_unsigned(bytes, *target as u64);
+    }
+    }
+}
+
+fn write_three(bytes: &mut Vec<u8>, opcode: u8, dest: i32, a: &Op, b: &Op) {
+    bytes.push(opcode);
+    write_unsigned(bytes, dest as u64);
+    write_op(bytes, a);
+    write_op(bytes, b);
+}
+
+fn write_debug_info(bytes: &mut Vec<u8>, constants: &mut Constants, function: &FunctionBytecode) {
+    // sorted by slot, so the same program always gives the same bytes.
+    let mut variables: Vec<(i32, &String)> = function.variables.iter().map(|(name, typ)| {
+        match typ {
+        VariableType::IntVar(id) | VariableType::ArrayVar(id) => (*id, name),
+        }
+    }).collect();
+    variables.sort();
+    write_unsigned(bytes, variables.len() as u64);
+    for (id, name) in variables {
+        write_unsigned(bytes, constants.id(name));
+        write_unsigned(bytes, id as u64);
+    }
+
+    for (line, span) in function.lines.iter().zip(&function.spans) {
+        write_unsigned(bytes, if *line == MAX_LINE { 0 } else { *line as u64 });
+        match span {
+        Some(span) => {
+            write_unsigned(bytes, span.line as u64);
+            write_unsigned(bytes, span.column as u64);
+        }
+        None => write_unsigned(bytes, 0),
+        }
+    }
+    for instruction in &function.instructions {
+        write_unsigned(bytes, constants.id(&instruction.to_string()));
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    at: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, count: usize) -> Result<&'b [u8], IRError> {
+        if self.bytes.len() - self.at < count {
+            return error(MAX_LINE, String::from("bytecode file ends too early."));
+        }
+        let taken = &self.bytes[self.at..self.at + count];
+        self.at += count;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, IRError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn unsigned(&mut self) -> Result<u64, IRError> {
+        let mut num: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift == 63 && byte > 1 {
+                return error(MAX_LINE, String::from("number too large in bytecode file."));
+            }
+            num |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(num);
+            }
+            shift += 7;
+        }
+    }
+
+    fn signed(&mut self) -> Result<i64, IRError> {
+        let num = self.unsigned()?;
+        Ok((num >> 1) as i64 ^ -((num & 1) as i64))
+    }
+
+    // how many of something follow. each one takes at least a byte, which
+    // stops a broken count from asking for more memory than the file could fill.
+    fn count(&mut self) -> Result<usize, IRError> {
+        let count = self.unsigned()?;
+        if count > (self.bytes.len() - self.at) as u64 {
+            return error(MAX_LINE, String::from("bytecode file ends too early."));
+        }
+        Ok(count as usize)
+    }
+
+    fn string(&mut self) -> Result<String, IRError> {
+        let length = self.count()?;
+        match std::str::from_utf8(self.take(length)?) {
+        Ok(text) => Ok(String::from(text)),
+        Err(_) => error(MAX_LINE, String::from("string constant in bytecode file is not valid UTF-8.")),
+        }
+    }
+
+    fn constant(&mut self, constants: &[String]) -> Result<String, IRError> {
+        let id = self.unsigned()?;
+        match constants.get(id as usize) {
+        Some(text) => Ok(text.clone()),
+        None => error(MAX_LINE, format!("constant {id} is not in the bytecode file.")),
+        }
+    }
+
+    // a slot, index or length, which all have to fit in an i32.
+    fn id(&mut self) -> Result<i32, IRError> {
+        let num = self.unsigned()?;
+        if num > i32::MAX as u64 {
+            return error(MAX_LINE, format!("number {num} too large in bytecode file."));
+        }
+        Ok(num as i32)
+    }
+
+    fn op(&mut self) -> Result<Op, IRError> {
+        match self.byte()? {
+        OP_NUM => Ok(Op::Num(self.signed()?)),
+        OP_VAR => Ok(Op::Var(self.id()?)),
+        tag => error(MAX_LINE, format!("unknown operand kind {tag} in bytecode file.")),
+        }
+    }
+
+    fn three(&mut self) -> Result<(i32, Op, Op), IRError> {
+        Ok((self.id()?, self.op()?, self.op()?))
+    }
+}
+
+fn read_function(reader: &mut Reader, constants: &[String]) -> Result<FunctionBytecode, IRError> {
+    let name = reader.constant(constants)?;
+    let parameters = reader.id()? as usize;
+    let slots = reader.id()? as usize;
+    let bits = reader.take(slots.div_ceil(8))?;
+    let long_variables = (0..slots).map(|id| bits[id / 8] & (1 << (id % 8)) != 0).collect();
+
+    let mut arrays = vec![];
+    let mut array_memory = 0;
+    for _ in 0..reader.count()? {
+        let id = reader.id()?;
+        let length = reader.id()?;
+        arrays.push((id, length));
+        array_memory += length as usize * 8;
+    }
+
+    let mut body = vec![];
+    for _ in 0..reader.count()? {
+        body.push(read_bytecode(reader, constants)?);
+    }
+
+    // without a debug section there are no names or lines to show.
+    let lines = vec![MAX_LINE; body.len()];
+    let spans = vec![None; body.len()];
+    Ok(FunctionBytecode {
+        name, parameters, slots, variables: HashMap::new(), long_variables, arrays, array_memory,
+        lines, spans, body, instructions: vec![],
+    })
+}
+
+fn read_bytecode(reader: &mut Reader, constants: &[String]) -> Result<Bytecode, IRError> {
+    let bytecode = match reader.byte()? {
+    0 => Bytecode::End,
+    1 => Bytecode::Label(reader.constant(constants)?),
+    2 => Bytecode::Int(reader.id()?),
+    3 => Bytecode::IntArray(reader.id()?),
+    4 => Bytecode::Out(reader.op()?),
+    5 => Bytecode::OutStr(reader.constant(constants)?),
+    6 => Bytecode::OutInt(reader.op()?),
+    7 => Bytecode::OutLine,
+    8 => Bytecode::In(reader.id()?),
+    9 => Bytecode::OutChar(reader.op()?),
+    10 => Bytecode::InChar(reader.id()?),
+    11 => {
+        let dest = match reader.byte()? {
+        WRITE_VAR => MemWrite::IntVar(reader.id()?),
+        WRITE_ARRAY => MemWrite::ArrayWrite(reader.id()?, reader.op()?),
+        tag => return error(MAX_LINE, format!("unknown destination kind {tag} in bytecode file.")),
+        };
+        let src = match reader.byte()? {
+        READ_VAR => MemRead::IntVar(reader.id()?),
+        READ_NUM => MemRead::Number(reader.signed()?),
+        READ_ARRAY => MemRead::ArrayRead(reader.id()?, reader.op()?),
+        tag => return error(MAX_LINE, format!("unknown source kind {tag} in bytecode file.")),
+        };
+        Bytecode::Mov(dest, src)
+    }
+    12 => { let (dest, a, b) = reader.three()?; Bytecode::Add(dest, a, b) }
+    13 => { let (dest, a, b) = reader.three()?; Bytecode::Sub(dest, a, b) }
+    14 => { let (dest, a, b) = reader.three()?; Bytecode::Mult(dest, a, b) }
+    15 => { let (dest, a, b) = reader.three()?; Bytecode::Div(dest, a, b) }
+    16 => { let (dest, a, b) = reader.three()?; Bytecode::Mod(dest, a, b) }
+    17 => {
+        let dest = reader.id()?;
+        let function = reader.id()? as usize;
+        let mut arguments = vec![];
+        for _ in 0..reader.count()? {
+            arguments.push(reader.op()?);
+        }
+        Bytecode::Call(dest, function, arguments)
+    }
+    18 => { let (dest, a, b) = reader.three()?; Bytecode::LessThan(dest, a, b) }
+    19 => { let (dest, a, b) = reader.three()?; Bytecode::LessEqual(dest, a, b) }
+    20 => { let (dest, a, b) = reader.three()?; Bytecode::NotEqual(dest, a, b) }
+    21 => { let (dest, a, b) = reader.three()?; Bytecode::Equal(dest, a, b) }
+    22 => { let (dest, a, b) = reader.three()?; Bytecode::GreaterEqual(dest, a, b) }
+    23 => { let (dest, a, b) = reader.three()?; Bytecode::GreaterThan(dest, a, b) }
+    24 => Bytecode::Return(reader.op()?),
+    25 => Bytecode::Jmp(reader.id()? as usize),
+    26 => Bytecode::BranchIf(reader.op()?, reader.id()? as usize),
+    27 => Bytecode::BranchIfn(reader.op()?, reader.id()? as usize),
+    opcode => return error(MAX_LINE, format!("unknown opcode {opcode} in bytecode file.")),
+    };
+    Ok(bytecode)
+}
+
+// the same guarantees load_function gives, which run_frames counts on.
+fn check_function(function: &FunctionBytecode, function_count: usize) -> Result<(), IRError> {
+    let name = &function.name;
+    if function.parameters > function.slots {
+        return error(MAX_LINE, format!("function {name} has more parameters than slots."));
+    }
+
+    let mut is_array = vec![false; function.slots];
+    for (id, length) in &function.arrays {
+        let id = *id as usize;
+        if id < function.parameters || id >= function.slots || is_array[id] {
+            return error(MAX_LINE, format!("function {name} has an array in slot {id}, which cannot hold one."));
+        }
+        if *length <= 0 {
+            return error(MAX_LINE, format!("function {name} has an array of length {length}."));
+        }
+        is_array[id] = true;
+    }
+
+    // a slot holding a number, and one holding an array.
+    let number = |at: usize, id: i32| -> Result<(), IRError> {
+        match is_array.get(id as usize) {
+        Some(false) => Ok(()),
+        _ => error(MAX_LINE, format!("bytecode {at} of function {name} uses s{id}, which is not a variable.")),
+        }
+    };
+    let array = |at: usize, id: i32| -> Result<(), IRError> {
+        match is_array.get(id as usize) {
+        Some(true) => Ok(()),
+        _ => error(MAX_LINE, format!("bytecode {at} of function {name} uses s{id}, which is not an array.")),
+        }
+    };
+    let op = |at: usize, op: &Op| -> Result<(), IRError> {
+        match op {
+        Op::Var(id) => number(at, *id),
+        Op::Num(_) => Ok(()),
+        }
+    };
+    let target = |at: usize, target: usize| -> Result<(), IRError> {
+        if target >= function.body.len() {
+            return error(MAX_LINE, format!("bytecode {at} of function {name} jumps to {target}, past the end of the function."));
+        }
+        Ok(())
+    };
+
+    for (at, bytecode) in function.body.iter().enumerate() {
+        match bytecode {
+        Bytecode::End | Bytecode::Label(_) | Bytecode::OutStr(_) | Bytecode::OutLine => {}
+        Bytecode::Int(id) | Bytecode::In(id) | Bytecode::InChar(id) => number(at, *id)?,
+        Bytecode::IntArray(id) => array(at, *id)?,
+        Bytecode::Out(value) | Bytecode::OutInt(value) | Bytecode::OutChar(value) | Bytecode::Return(value) => op(at, value)?,
+        Bytecode::Mov(dest, src) => {
+            match dest {
+            MemWrite::IntVar(id) => number(at, *id)?,
+            MemWrite::ArrayWrite(id, index) => {
+                array(at, *id)?;
+                op(at, index)?;
+            }
+            }
+            match src {
+            MemRead::IntVar(id) => number(at, *id)?,
+            MemRead::Number(_) => {}
+            MemRead::ArrayRead(id, index) => {
+                array(at, *id)?;
+                op(at, index)?;
+            }
+            }
+        }
+        Bytecode::Add(dest, a, b) | Bytecode::Sub(dest, a, b) | Bytecode::Mult(dest, a, b)
+        | Bytecode::Div(dest, a, b) | Bytecode::Mod(dest, a, b)
+        | Bytecode::LessThan(dest, a, b) | Bytecode::LessEqual(dest, a, b) | Bytecode::NotEqual(dest, a, b)
+        | Bytecode::Equal(dest, a, b) | Bytecode::GreaterEqual(dest, a, b) | Bytecode::GreaterThan(dest, a, b) => {
+            number(at, *dest)?;
+            op(at, a)?;
+            op(at, b)?;
+        }
+        Bytecode::Call(dest, callee, arguments) => {
+            number(at, *dest)?;
+            if *callee >= function_count {
+                return error(MAX_LINE, format!("bytecode {at} of function {name} calls function {callee}, which is not in the file."));
+            }
+            for argument in arguments {
+                op(at, argument)?;
+            }
+        }
+        Bytecode::Jmp(to) => target(at, *to)?,
+        Bytecode::BranchIf(condition, to) | Bytecode::BranchIfn(condition, to) => {
+            op(at, condition)?;
+            target(at, *to)?;
+        }
+        }
+    }
+
+    // running off the end of the body would go past the last bytecode.
+    if !matches!(function.body.last(), Some(Bytecode::End)) {
+        return error(MAX_LINE, format!("function {name} does not finish with End."));
+    }
+    Ok(())
+}
+
+fn read_debug_info(reader: &mut Reader, constants: &[String], function: &mut FunctionBytecode) -> Result<(), IRError> {
+    let arrays: Vec<i32> = function.arrays.iter().map(|(id, _)| *id).collect();
+    for _ in 0..reader.count()? {
+        let name = reader.constant(constants)?;
+        let id = reader.id()?;
+        if id as usize >= function.slots {
+            return error(MAX_LINE, format!("variable {name} of function {} is in s{id}, which it does not have.", function.name));
+        }
+        let typ = if arrays.contains(&id) { VariableType::ArrayVar(id) } else { VariableType::IntVar(id) };
+        function.variables.insert(name, typ);
+    }
+
+    for at in 0..function.body.len() {
+        let line = reader.unsigned()? as usize;
+        function.lines[at] = if line == 0 { MAX_LINE } else { line };
+        let span_line = reader.unsigned()? as usize;
+        if span_line != 0 {
+            function.spans[at] = Some(Span { line: span_line, column: reader.unsigned()? as usize });
+        }
+    }
+
+    // End has no instruction.
+    for _ in 1..function.body.len() {
+        let text = reader.constant(constants)?;
+        let tokens = lex_ir(&format!("{text}\n"));
+        match parse_instruction(&mut 1, &tokens, &mut 0) {
+        Ok(Some(instruction)) => function.instructions.push(instruction),
+        _ => return error(MAX_LINE, format!("'{text}' in the debug section of function {} is not an instruction.", function.name)),
+        }
+    }
+    Ok(())
+}
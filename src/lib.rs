@@ -1 +1,8 @@
 pub mod phases; // Make phases available
+pub mod interpreter;
+pub mod debugger;
+pub mod tracer;
+pub mod profiler;
+pub mod coverage;
+pub mod repl;
+pub mod backend;
@@ -6,36 +6,330 @@ use std::env;
 // used to interact with the file system
 use std::fs;
 
+use std::process::ExitCode;
+
+use rustcompiler::phases::ir::Module;
 use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::parser;
 use rustcompiler::phases::semantic_parser::*;
+use rustcompiler::backend::{c, llvm, wasm, x86};
+use rustcompiler::coverage::Coverage;
+use rustcompiler::debugger::Debugger;
+use rustcompiler::interpreter;
+use rustcompiler::profiler::Profiler;
+use rustcompiler::repl::Repl;
+use rustcompiler::tracer::{TraceFormat, Tracer};
+use rustcompiler::interpreter::{parse_module, IRError, Program, RuntimeError, Vm};
 
 mod phases;
-mod interpreter; 
+
+// exit codes for when the program could not be run. once it runs, the exit
+// code is what main returned, unless that is not an exit code of its own.
+const EXIT_USAGE: u8 = 2; // bad commandline arguments or unreadable file
+const EXIT_LEX: u8 = 3;
+const EXIT_PARSE: u8 = 4;
+const EXIT_SEMANTIC: u8 = 5;
+const EXIT_IR: u8 = 6;
+const EXIT_RUNTIME: u8 = 7;
+const EXIT_RETURN: u8 = 8; // main returned one of the codes above, or a value outside 0-255
+
 
 
+// the flags that only make sense when the program is run.
+const RUN_FLAGS: [&str; 15] = ["--checked-arithmetic", "--input", "--debug", "--trace", "--trace-format", "--trace-function", "--trace-file",
+    "--coverage", "--coverage-file", "--profile", "--profile-folded", "--max-call-depth", "--max-instructions", "--timeout", "--max-array-memory"];
+
+// what build writes out, picked with --emit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+    Bytecode,
+    // a .ttb file, with or without its debug section.
+    Binary { debug: bool },
+    Asm,
+    // the assembly linked into a native executable, a.out without -o.
+    Executable,
+    C,
+    Wat,
+    Llvm,
+}
 
+// how to run the program, from the commandline flags.
+struct RunFlags {
+    options: interpreter::RunOptions,
+    // what the program reads, when not standard input.
+    input: Option<Box<dyn std::io::BufRead>>,
+    debug: bool,
+    trace: bool,
+    trace_format: TraceFormat,
+    trace_function: Option<String>,
+    trace_output: Box<dyn std::io::Write>,
+    profile: bool,
+    profile_folded: Option<String>,
+    coverage: bool,
+    coverage_file: Option<String>,
+}
 
 //The reading of file, command args and lex call are same as example 
-fn main() {
+fn main() -> ExitCode {
 
     // Let us get commandline arguments and store them in a Vec<String>
     let args: Vec<String> = env::args().collect();
-    if args.len() == 1 {
-        println!("Please provide an input file through the commandline arguments for the lex.");
-        return;
+
+    // the first argument can be a subcommand. without one the file is
+    // compiled and run, showing every stage on the way.
+    let (command, args) = match args.get(1).map(|arg| arg.as_str()) {
+    Some(command @ ("lex" | "parse" | "check" | "build" | "run" | "repl")) => (command, &args[2..]),
+    _ => ("", &args[1..]),
+    };
+
+    if command == "repl" {
+        if !args.is_empty() {
+            eprintln!("repl does not take any arguments.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        println!("Teh Terik. Type :help for help, :quit to leave.");
+        Repl::new().run_prompt(&mut std::io::stdin().lock(), &mut std::io::stdout());
+        return ExitCode::SUCCESS;
+    }
+
+    // flags can go anywhere, everything else is the input file.
+    let mut options = interpreter::RunOptions::default();
+    let mut debug = false;
+    // any of the --trace flags turns tracing on.
+    let mut trace = false;
+    let mut trace_format = TraceFormat::Human;
+    let mut trace_function: Option<String> = None;
+    let mut trace_file: Option<String> = None;
+    let mut input_file: Option<String> = None;
+    let mut profile = false;
+    let mut profile_folded: Option<String> = None;
+    let mut coverage = false;
+    let mut coverage_file: Option<String> = None;
+    let mut emit: Option<Emit> = None;
+    let mut output: Option<String> = None;
+    let mut strip = false;
+    // the first flag for running the program, which only lex, parse, check and build reject.
+    let mut run_flag: Option<&String> = None;
+    let mut files: Vec<&String> = vec![];
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        // limits are given as --flag=number
+        let (flag, value) = match arg.split_once('=') {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (arg.as_str(), None),
+        };
+
+        // -o and --emit can also take their value as the next argument
+        let value = match (flag, value) {
+        ("-o" | "--emit", None) if i < args.len() => {
+            i += 1;
+            Some(args[i - 1].as_str())
+        }
+        _ => value,
+        };
+
+        if RUN_FLAGS.contains(&flag) && run_flag.is_none() {
+            run_flag = Some(arg);
+        }
+
+        match (flag, value) {
+        ("-o", Some(value)) => output = Some(String::from(value)),
+        ("--emit", Some(value)) => {
+            emit = match value {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "ir" => Some(Emit::Ir),
+            "bytecode" => Some(Emit::Bytecode),
+            "binary" => Some(Emit::Binary { debug: true }),
+            "asm" => Some(Emit::Asm),
+            "exe" => Some(Emit::Executable),
+            "c" => Some(Emit::C),
+            "wat" => Some(Emit::Wat),
+            "llvm" => Some(Emit::Llvm),
+            _ => {
+                eprintln!("Invalid value '{value}' for --emit. Expected 'tokens', 'ast', 'ir', 'bytecode', 'binary', 'asm', 'exe', 'c', 'wat' or 'llvm'.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+            };
+        }
+        ("-o" | "--emit", None) => {
+            eprintln!("{flag} expects a value.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        ("--strip", None) => strip = true,
+        ("--checked-arithmetic", None) => options.checked_arithmetic = true,
+        ("--debug", None) => debug = true,
+        ("--trace", None) => trace = true,
+        ("--trace-format", Some(value)) => {
+            trace = true;
+            trace_format = match value {
+            "human" => TraceFormat::Human,
+            "json" => TraceFormat::Json,
+            _ => {
+                eprintln!("Invalid value '{value}' for --trace-format. Expected 'human' or 'json'.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+            };
+        }
+        ("--trace-function", Some(value)) => {
+            trace = true;
+            trace_function = Some(String::from(value));
+        }
+        ("--trace-file", Some(value)) => {
+            trace = true;
+            trace_file = Some(String::from(value));
+        }
+        ("--input", Some(value)) => input_file = Some(String::from(value)),
+        ("--coverage", None) => coverage = true,
+        ("--coverage-file", Some(value)) => {
+            coverage = true;
+            coverage_file = Some(String::from(value));
+        }
+        ("--profile", None) => profile = true,
+        ("--profile-folded", Some(value)) => {
+            profile = true;
+            profile_folded = Some(String::from(value));
+        }
+        ("--max-call-depth", Some(value)) => match value.parse::<usize>() {
+            Ok(n) if n > 0 => options.max_call_depth = n,
+            _ => {
+                eprintln!("Invalid value '{value}' for --max-call-depth. Expected a number of calls above 0.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        ("--max-instructions", Some(value)) => match value.parse::<u64>() {
+            Ok(n) => options.max_instructions = Some(n),
+            Err(_) => {
+                eprintln!("Invalid value '{value}' for --max-instructions. Expected a number of instructions.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        ("--timeout", Some(value)) => match value.parse::<u64>() {
+            Ok(n) => options.timeout = Some(std::time::Duration::from_millis(n)),
+            Err(_) => {
+                eprintln!("Invalid value '{value}' for --timeout. Expected a number of milliseconds.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        ("--max-array-memory", Some(value)) => match value.parse::<usize>() {
+            Ok(n) => options.max_array_memory = Some(n),
+            Err(_) => {
+                eprintln!("Invalid value '{value}' for --max-array-memory. Expected a number of bytes.");
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        _ if arg.starts_with("--") => {
+            eprintln!("Unknown flag '{arg}'.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        _ => files.push(arg),
+        }
+    }
+
+    if let Some(flag) = run_flag {
+        if !command.is_empty() && command != "run" {
+            eprintln!("{flag} can only be used when running a program, not with {command}.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+
+    if emit.is_some() && command != "build" {
+        eprintln!("--emit can only be used with build.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if output.is_some() && !matches!(command, "lex" | "parse" | "build") {
+        eprintln!("-o can only be used with lex, parse and build.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if let Some(Emit::Binary { debug }) = &mut emit {
+        if output.is_none() {
+            eprintln!("--emit=binary needs a file to write to, given with -o.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        *debug = !strip;
+    } else if strip {
+        eprintln!("--strip can only be used with --emit=binary.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if [debug, trace, profile, coverage].iter().filter(|on| **on).count() > 1 {
+        eprintln!("Only one of --debug, --trace, --profile and --coverage can be used at a time.");
+        return ExitCode::from(EXIT_USAGE);
     }
 
-    if args.len() > 2 {
-        println!("Too many commandline arguments.");
-        return;
+    // the trace goes to standard error unless a file is given, to keep it apart from the program's output.
+    let trace_output: Box<dyn std::io::Write> = match &trace_file {
+    Some(path) => match fs::File::create(path) {
+        Ok(file) => Box::new(std::io::BufWriter::new(file)),
+        Err(error) => {
+            eprintln!("**Error. Trace file \"{}\": {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    },
+    None => Box::new(std::io::stderr()),
+    };
+
+    // under --debug standard input carries the debugger's commands, so the
+    // program only gets input from --input.
+    let input: Option<Box<dyn std::io::BufRead>> = match &input_file {
+    Some(path) => match fs::File::open(path) {
+        Ok(file) => Some(Box::new(std::io::BufReader::new(file))),
+        Err(error) => {
+            eprintln!("**Error. Input file \"{}\": {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    },
+    None if debug => Some(Box::new(std::io::empty())),
+    None => None,
+    };
+
+    if files.is_empty() {
+        eprintln!("Please provide an input file through the commandline arguments for the lex.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if files.len() > 1 {
+        eprintln!("Too many commandline arguments.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    let filename = files[0];
+    let flags = RunFlags {
+        options, input, debug, trace, trace_format, trace_function, trace_output, profile, profile_folded, coverage, coverage_file,
+    };
+
+    // how far each subcommand goes.
+    let emit = match command {
+    "lex" => Some(Emit::Tokens),
+    "parse" => Some(Emit::Ast),
+    "build" => Some(emit.unwrap_or(Emit::Ir)),
+    _ => None,
+    };
+
+    // saved bytecode is read as it is, everything else is text.
+    if is_binary_file(filename) {
+        return match fs::read(filename) {
+        Ok(bytes) => run_binary(command, emit, &output, flags, filename, &bytes),
+        Err(error) => {
+            eprintln!("**Error. File \"{}\": {}", filename, error);
+            ExitCode::from(EXIT_USAGE)
+        }
+        };
     }
 
     // read the entire file contents, storing them inside 'code' as a string.
-    let filename = &args[1];
     let code = match fs::read_to_string(filename) { //this is a rust style code block, keep that in mind 
       Err(error) => {
-          println!("**Error. File \"{}\": {}", filename, error);
-          return;
+          eprintln!("**Error. File \"{}\": {}", filename, error);
+          return ExitCode::from(EXIT_USAGE);
       }
 
       Ok(code) => {
@@ -43,13 +337,23 @@ fn main() {
       } 
     };
 
-    let tokens = match lex(&code) {
+    // without a subcommand every stage is shown. a .ir file has no stages
+    // to show, so it is just run.
+    if command.is_empty() && !is_ir_file(filename) {
+        return show_all(filename, &code, flags);
+    }
+    run_command(command, emit, &output, flags, filename, &code)
+}
+
+// compiles and runs the file, showing the file, the result and the IR.
+fn show_all(filename: &str, code: &str, flags: RunFlags) -> ExitCode {
+    let (tokens, spans) = match lex_with_spans(code) {
       Err(error_message) => {
           println!("**Error**");
           println!("----------------------");
           println!("{}", error_message);
           println!("----------------------");
-          return;
+          return ExitCode::from(EXIT_LEX);
       }
 
       Ok(data) => data,
@@ -74,25 +378,340 @@ fn main() {
 
     //parser part added from phase 2 
     let mut index: usize = 0;
-    match parse_program(&tokens, &mut index) {
+    match parse_program_with_spans(&tokens, &spans, &mut index) {
 
-    Ok(generated_code) => {
+    Ok(module) => {
         println!("Program Parsed Successfully.");
-        interpreter::execute_ir(&generated_code);
-        print!("{}", generated_code);
+        let status = match Program::load(&module) {
+        Ok(program) => {
+            println!("Valid IR. Executing Generated Bytecode...");
+            match execute(&program, Some(&module), flags, code, filename) {
+            Ok(n) => {
+                println!("Run successful. Exit code {}", n);
+                exit_status(n)
+            }
+
+            // quitting the debugger is not the program failing
+            Err(e) if e.stopped() => ExitCode::SUCCESS,
+            Err(e) => {
+                report_runtime_error(&mut std::io::stdout(), code, &module.to_string(), &e);
+                ExitCode::from(EXIT_RUNTIME)
+            }
+            }
+        }
 
+        Err(e) => {
+            report_invalid(&mut std::io::stdout(), &module.to_string(), &e);
+            ExitCode::from(EXIT_IR)
+        }
+        };
+        print!("{}", module);
+        status
     }
 
     Err(message) => {
         println!("**Error**");
         println!("----------------------");
-        if tokens.len() == 0 {
+        if tokens.is_empty() {
             println!("No code has been provided.");
         } else {
             println!("Error: {message}");
             println!("----------------------");
         }
+
+        ExitCode::from(compile_error_code(&message))
     }
 
   }
 }
+
+// main's return value as the exit code. one that would be taken for an error
+// above, or would be cut down to 8 bits, exits with EXIT_RETURN instead.
+fn exit_status(value: i64) -> ExitCode {
+    match u8::try_from(value) {
+    Ok(code) if !(EXIT_USAGE..=EXIT_RETURN).contains(&code) => ExitCode::from(code),
+    _ => {
+        eprintln!("main returned {}, which is not an exit code of its own, so exiting with {}.", value, EXIT_RETURN);
+        ExitCode::from(EXIT_RETURN)
+    }
+    }
+}
+
+// the semantic checks happen while parsing, the message says which it was.
+fn compile_error_code(message: &str) -> u8 {
+    if message.starts_with("Semantic Analysis") {
+        return EXIT_SEMANTIC;
+    }
+    EXIT_PARSE
+}
+
+// runs main, with the debugger, tracer, profiler or coverage if asked for.
+// coverage needs the module, which a .ttb file does not have.
+fn execute(program: &Program, module: Option<&Module>, flags: RunFlags, code: &str, filename: &str) -> Result<i64, RuntimeError> {
+    let mut vm = match flags.input {
+    Some(input) => {
+        let mut vm = Vm::with_io(input, std::io::stdout());
+        vm.options = flags.options;
+        vm
+    }
+    None => Vm::with_options(flags.options),
+    };
+    if flags.debug {
+        println!("Debugging. Type 'help' for a list of commands.");
+        let mut debugger = Debugger::with_source(code).with_commands(Box::new(std::io::stdin().lock()));
+        return program.run_with_hook(&mut vm, &mut debugger);
+    } else if flags.trace {
+        let mut tracer = Tracer::new(flags.trace_format, flags.trace_output);
+        tracer.function = flags.trace_function;
+        return program.run_with_hook(&mut vm, &mut tracer);
+    } else if flags.profile {
+        let mut profiler = Profiler::new();
+        let result = program.run_with_hook(&mut vm, &mut profiler);
+        if let Err(error) = write_profile(&profiler, &flags.profile_folded) {
+            eprintln!("**Error. Could not write the profile: {}", error);
+        }
+        return result;
+    } else if let (true, Some(module)) = (flags.coverage, module) {
+        let mut coverage = Coverage::new(module);
+        let result = program.run_with_hook(&mut vm, &mut coverage);
+        if let Some(mismatch) = coverage.mismatch() {
+            eprintln!("**Warning. The coverage is incomplete: {}", mismatch);
+        }
+        if let Err(error) = write_coverage(&coverage, &flags.coverage_file, filename) {
+            eprintln!("**Error. Could not write the coverage report: {}", error);
+        }
+        return result;
+    }
+    program.run(&mut vm)
+}
+
+// lex, parse, check, build and run each go as far as their stage. what a
+// stage makes goes to standard output or the -o file, errors go to standard error.
+fn run_command(command: &str, emit: Option<Emit>, output: &Option<String>, flags: RunFlags, filename: &str, code: &str) -> ExitCode {
+    // errors are shown against the IR, which for a .ir file is the file itself.
+    let (module, ir) = if is_ir_file(filename) {
+        if matches!(emit, Some(Emit::Tokens | Emit::Ast)) {
+            eprintln!("{filename}: tokens and syntax trees can only be made from .tt files.");
+            return ExitCode::from(EXIT_USAGE);
+        }
+        match parse_module(code) {
+        Ok(module) => (module, String::from(code)),
+        Err(e) => {
+            report_invalid(&mut std::io::stderr(), code, &e);
+            return ExitCode::from(EXIT_IR);
+        }
+        }
+    } else {
+        match compile(emit, output, filename, code) {
+        Ok(module) => {
+            let ir = module.to_string();
+            (module, ir)
+        }
+        Err(status) => return status,
+        }
+    };
+
+    let program = match Program::load(&module) {
+    Ok(program) => program,
+    Err(e) => {
+        report_invalid(&mut std::io::stderr(), &ir, &e);
+        return ExitCode::from(EXIT_IR);
+    }
+    };
+
+    match emit {
+    Some(Emit::Ir) => return write_artifact(output, module.to_string().as_bytes()),
+    Some(Emit::Bytecode) => return write_artifact(output, program.to_string().as_bytes()),
+    Some(Emit::Binary { debug }) => return write_artifact(output, &program.to_binary(debug)),
+    Some(Emit::Asm) => return write_artifact(output, x86::generate(&module).as_bytes()),
+    Some(Emit::Executable) => {
+        let executable = output.as_deref().unwrap_or("a.out");
+        if let Err(message) = x86::link(&x86::generate(&module), executable) {
+            eprintln!("**Error. Could not build \"{}\": {}", executable, message);
+            return ExitCode::from(EXIT_USAGE);
+        }
+        return ExitCode::SUCCESS;
+    }
+    Some(Emit::C) => return write_artifact(output, c::generate(&module).as_bytes()),
+    Some(Emit::Wat) => return write_artifact(output, wasm::generate(&module).as_bytes()),
+    Some(Emit::Llvm) => return write_artifact(output, llvm::generate(&module).as_bytes()),
+    _ => {}
+    }
+
+    if command == "check" {
+        return ExitCode::SUCCESS;
+    }
+
+    match execute(&program, Some(&module), flags, code, filename) {
+    Ok(n) => exit_status(n),
+    Err(e) if e.stopped() => ExitCode::SUCCESS,
+    Err(e) => {
+        report_runtime_error(&mut std::io::stderr(), code, &ir, &e);
+        ExitCode::from(EXIT_RUNTIME)
+    }
+    }
+}
+
+// lexes and parses a .tt file. lex and parse stop here after writing out the
+// tokens or syntax tree, and like any error that gives back the exit code.
+fn compile(emit: Option<Emit>, output: &Option<String>, filename: &str, code: &str) -> Result<Module, ExitCode> {
+    let (tokens, spans) = match lex_with_spans(code) {
+    Ok(data) => data,
+    Err(message) => {
+        eprintln!("{filename}: {message}");
+        return Err(ExitCode::from(EXIT_LEX));
+    }
+    };
+
+    if emit == Some(Emit::Tokens) {
+        let mut text = String::new();
+        for (token, span) in tokens.iter().zip(&spans) {
+            text += &format!("{}:{} {:?}\n", span.line, span.column, token);
+        }
+        return Err(write_artifact(output, text.as_bytes()));
+    }
+
+    if emit == Some(Emit::Ast) {
+        return match parser::parse_program(&tokens, &mut 0) {
+        Ok(program) => Err(write_artifact(output, program.to_string().as_bytes())),
+        Err(message) => {
+            eprintln!("{filename}: {message}");
+            Err(ExitCode::from(EXIT_PARSE))
+        }
+        };
+    }
+
+    match parse_program_with_spans(&tokens, &spans, &mut 0) {
+    Ok(module) => Ok(module),
+    Err(message) => {
+        eprintln!("{filename}: {message}");
+        Err(ExitCode::from(compile_error_code(&message)))
+    }
+    }
+}
+
+// runs, checks or rebuilds a .ttb file. it has no source or IR, so errors
+// can only give the lines its debug section kept.
+fn run_binary(command: &str, emit: Option<Emit>, output: &Option<String>, flags: RunFlags, filename: &str, bytes: &[u8]) -> ExitCode {
+    if !matches!(emit, None | Some(Emit::Bytecode | Emit::Binary { .. })) {
+        eprintln!("{filename}: a .ttb file can only be built into bytecode or another .ttb file.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+    if flags.coverage {
+        eprintln!("{filename}: --coverage needs the .tt or .ir file, a .ttb file does not keep the source.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    let program = match Program::from_binary(bytes) {
+    Ok(program) => program,
+    Err(e) => {
+        eprintln!("{filename}: {e}");
+        return ExitCode::from(EXIT_IR);
+    }
+    };
+
+    match emit {
+    Some(Emit::Bytecode) => return write_artifact(output, program.to_string().as_bytes()),
+    Some(Emit::Binary { debug }) => return write_artifact(output, &program.to_binary(debug && program.has_debug_info())),
+    _ => {}
+    }
+
+    if (flags.debug || flags.trace) && !program.has_debug_info() {
+        eprintln!("{filename}: was built with --strip, so there are no instructions for --debug or --trace to show.");
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    if command == "check" {
+        return ExitCode::SUCCESS;
+    }
+
+    match execute(&program, None, flags, "", filename) {
+    Ok(n) => exit_status(n),
+    Err(e) if e.stopped() => ExitCode::SUCCESS,
+    Err(e) => {
+        report_runtime_error(&mut std::io::stderr(), "", "", &e);
+        ExitCode::from(EXIT_RUNTIME)
+    }
+    }
+}
+
+// hand-written or saved IR, read with parse_module instead of the frontend.
+fn is_ir_file(filename: &str) -> bool {
+    filename.ends_with(".ir")
+}
+
+// bytecode saved by build --emit=binary.
+fn is_binary_file(filename: &str) -> bool {
+    filename.ends_with(".ttb")
+}
+
+// writes what a stage made to the -o file, or standard output without one.
+fn write_artifact(output: &Option<String>, bytes: &[u8]) -> ExitCode {
+    match output {
+    Some(path) => {
+        if let Err(error) = fs::write(path, bytes) {
+            eprintln!("**Error. File \"{}\": {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+    None => {
+        let _ = std::io::Write::write_all(&mut std::io::stdout(), bytes);
+    }
+    }
+    ExitCode::SUCCESS
+}
+
+// prints the profile tables, and writes the folded stacks if asked to.
+fn write_profile(profiler: &Profiler, folded: &Option<String>) -> std::io::Result<()> {
+    println!("----------------------");
+    profiler.write_report(&mut std::io::stdout())?;
+    println!("----------------------");
+    if let Some(path) = folded {
+        let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+        profiler.write_folded(&mut file)?;
+    }
+    Ok(())
+}
+
+// prints the coverage of each function, and writes the lcov file if asked to.
+fn write_coverage(coverage: &Coverage, file: &Option<String>, source: &str) -> std::io::Result<()> {
+    println!("----------------------");
+    coverage.write_summary(&mut std::io::stdout())?;
+    println!("----------------------");
+    if let Some(path) = file {
+        let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+        coverage.write_lcov(&mut file, source)?;
+    }
+    Ok(())
+}
+
+// prints the IR up to the line the error is on.
+fn report_invalid(out: &mut dyn std::io::Write, code: &str, e: &IRError) {
+    let _ = writeln!(out, "***Error. Invalid Bytecode.");
+    let _ = writeln!(out, "------------------");
+    for (i, l) in code.lines().enumerate() {
+        let _ = writeln!(out, "{:03}:  {}", i+1, l);
+        if Some(i+1) == e.line() {
+            break;
+        }
+    }
+    let _ = writeln!(out, "------------------");
+    let _ = writeln!(out, "{e}");
+}
+
+// prints the error with the source line it happened on, or the IR line
+// if the instruction did not come from a statement.
+fn report_runtime_error(out: &mut dyn std::io::Write, code: &str, ir: &str, e: &RuntimeError) {
+    let _ = writeln!(out, "{e}");
+    let (text, line) = match (e.span(), e.line()) {
+    (Some(span), _) => (code, span.line),
+    (None, Some(line)) => (ir, line),
+    (None, None) => return,
+    };
+
+    if let Some(l) = text.lines().nth(line - 1) {
+        let _ = writeln!(out, "------------------");
+        let _ = writeln!(out, "{:03}:  {}", line, l);
+        let _ = writeln!(out, "------------------");
+    }
+}
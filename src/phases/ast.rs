@@ -0,0 +1,196 @@
+#![allow(dead_code)]
+
+// src/phases/ast.rs
+// The syntax tree the parser builds, before any names or types are checked.
+// Printing it gives one s-expression per statement, nested blocks indented:
+//
+// (func fib ((int n)) int
+//   (if (< n 2)
+//     (then
+//       (return n)))
+//   (return (+ (call fib (- n 1)) (call fib (- n 2)))))
+
+use std::fmt;
+
+pub use super::ir::{BinaryOp, Type};
+use super::ir::escape_string;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReturnType {
+    Unspecified,    // no '->', may return a value or not
+    Int,
+    Long,
+    Void,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Declaration>,
+    pub return_type: ReturnType,
+    pub body: Vec<Statement>,
+}
+
+// int x or int[10] x
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub typ: Type,
+    pub length: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintArgument {
+    Str(String),
+    Expr(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Declare(Declaration),
+    // the target is a variable or an array element
+    Assign(Expression, Expression),
+    Call(String, Vec<Expression>),
+    Return(Option<Expression>),
+    Print(Vec<PrintArgument>),
+    Read(Expression),
+    PrintChar(Expression),
+    ReadChar(Expression),
+    If(Expression, Vec<Statement>, Option<Vec<Statement>>),
+    While(Expression, Vec<Statement>),
+    Break,
+    Continue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Num(i32),
+    LongNum(i64),
+    Var(String),
+    Index(String, Box<Expression>),
+    Call(String, Vec<Expression>),
+    Cast(Type, Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        Expression::Num(num) => write!(f, "{num}"),
+        Expression::LongNum(num) => write!(f, "{num}L"),
+        Expression::Var(name) => write!(f, "{name}"),
+        Expression::Index(array, index) => write!(f, "(index {array} {index})"),
+        Expression::Call(function, arguments) => {
+            write!(f, "(call {function}")?;
+            for argument in arguments {
+                write!(f, " {argument}")?;
+            }
+            write!(f, ")")
+        }
+        Expression::Cast(typ, value) => write!(f, "({} {value})", typ.name()),
+        Expression::Binary(op, left, right) => write!(f, "({} {left} {right})", op.symbol()),
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.length {
+        Some(length) => write!(f, "{}[{length}] {}", self.typ.name(), self.name),
+        None => write!(f, "{} {}", self.typ.name(), self.name),
+        }
+    }
+}
+
+// the lines of a block, each one indented by depth levels. the last line of
+// each statement gets its closing parentheses, so they are not on lines of their own.
+fn write_block(f: &mut fmt::Formatter, block: &[Statement], depth: usize, closing: &str) -> fmt::Result {
+    for (i, statement) in block.iter().enumerate() {
+        let closing = if i + 1 == block.len() { closing } else { "" };
+        write_statement(f, statement, depth, closing)?;
+    }
+    Ok(())
+}
+
+fn write_statement(f: &mut fmt::Formatter, statement: &Statement, depth: usize, closing: &str) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    match statement {
+    Statement::Declare(declaration) => writeln!(f, "{indent}(declare {declaration}){closing}"),
+    Statement::Assign(target, value) => writeln!(f, "{indent}(assign {target} {value}){closing}"),
+    Statement::Call(function, arguments) => writeln!(f, "{indent}{}{closing}", Expression::Call(function.clone(), arguments.clone())),
+    Statement::Return(None) => writeln!(f, "{indent}(return){closing}"),
+    Statement::Return(Some(value)) => writeln!(f, "{indent}(return {value}){closing}"),
+    Statement::Print(arguments) => {
+        let arguments: Vec<String> = arguments.iter().map(|argument| match argument {
+        PrintArgument::Str(text) => escape_string(text),
+        PrintArgument::Expr(value) => value.to_string(),
+        }).collect();
+        writeln!(f, "{indent}(print {}){closing}", arguments.join(" "))
+    }
+    Statement::Read(target) => writeln!(f, "{indent}(read {target}){closing}"),
+    Statement::PrintChar(value) => writeln!(f, "{indent}(printc {value}){closing}"),
+    Statement::ReadChar(target) => writeln!(f, "{indent}(readc {target}){closing}"),
+    Statement::If(condition, then, otherwise) => {
+        writeln!(f, "{indent}(if {condition}")?;
+        let then_closing = if otherwise.is_none() { format!("){closing}") } else { String::new() };
+        write_nested(f, "then", then, depth + 1, &then_closing)?;
+        match otherwise {
+        Some(otherwise) => write_nested(f, "else", otherwise, depth + 1, &format!("){closing}")),
+        None => Ok(()),
+        }
+    }
+    Statement::While(condition, body) => {
+        if body.is_empty() {
+            return writeln!(f, "{indent}(while {condition}){closing}");
+        }
+        writeln!(f, "{indent}(while {condition}")?;
+        write_block(f, body, depth + 1, &format!("){closing}"))
+    }
+    Statement::Break => writeln!(f, "{indent}(break){closing}"),
+    Statement::Continue => writeln!(f, "{indent}(continue){closing}"),
+    }
+}
+
+// (then ...) and (else ...) of an if.
+fn write_nested(f: &mut fmt::Formatter, name: &str, block: &[Statement], depth: usize, closing: &str) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    if block.is_empty() {
+        return writeln!(f, "{indent}({name}){closing}");
+    }
+    writeln!(f, "{indent}({name}")?;
+    write_block(f, block, depth + 1, &format!("){closing}"))
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| format!("({p})")).collect();
+        let return_type = match self.return_type {
+        ReturnType::Unspecified => "",
+        ReturnType::Int => " int",
+        ReturnType::Long => " long",
+        ReturnType::Void => " void",
+        };
+        if self.body.is_empty() {
+            return writeln!(f, "(func {} ({}){return_type})", self.name, parameters.join(" "));
+        }
+        writeln!(f, "(func {} ({}){return_type}", self.name, parameters.join(" "))?;
+        write_block(f, &self.body, 1, ")")
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{function}")?;
+        }
+        Ok(())
+    }
+}
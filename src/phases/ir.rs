@@ -0,0 +1,430 @@
+#![allow(dead_code)]
+
+// src/phases/ir.rs
+// Typed in-memory form of the three address code.
+// The semantic parser builds a Module directly, and printing it gives the
+// same text format the interpreter reads:
+//
+// %str $0, "sum = "
+// %func add(%int a, %int b)
+// %int t0
+// %add t0, a, b
+// %ret t0
+// %endfunc
+
+use std::fmt;
+
+pub use super::lexer::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Int,    // %int, 32-bit
+    Long,   // %long, 64-bit
+}
+
+// a jump target, printed with a leading ':' (e.g. :3)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label(pub String);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Var(String),
+    Num(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mult,
+    Div,
+    Mod,
+    LessThan,
+    LessEqual,
+    NotEqual,
+    Equal,
+    GreaterEqual,
+    GreaterThan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    // declarations.
+    Declare(Type, String),              // %int a
+    DeclareArray(Type, String, i64),    // %int[] a, 10
+
+    // function calling routines.
+    Call(String, String, Vec<Operand>), // %call dest, function(a,b)
+    Return(Operand),                    // %ret a
+
+    // input/output routines.
+    Out(Operand),                       // %out a
+    OutStr(String),                     // %out_str $0
+    OutInt(Operand),                    // %out_int a
+    OutLine,                            // %out_nl
+    In(String),                         // %input a
+    OutChar(Operand),                   // %outc a
+    InChar(String),                     // %inputc a
+
+    // moves.
+    Mov(String, Operand),               // %mov a, b
+    Load(String, String, Operand),      // %mov a, [array + index]
+    Store(String, Operand, Operand),    // %mov [array + index], a
+
+    // mathematical and comparison operators.
+    Binary(BinaryOp, String, Operand, Operand),
+
+    // labels/branching
+    Label(Label),
+    Jump(Label),
+    BranchIf(Operand, Label),
+    BranchIfn(Operand, Label),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<(Type, String)>,
+    pub body: Vec<Instruction>,
+    // line of the %func header in the IR text, 0 if not known.
+    pub line: usize,
+    // line of each instruction in the IR text, used for error messages.
+    // see Module::number_lines for functions that never were text.
+    pub lines: Vec<usize>,
+    // where in the .tt source each instruction came from, when it is known.
+    pub spans: Vec<Option<Span>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub strings: Vec<(String, String)>, // string constants, ($0, text)
+    pub functions: Vec<Function>,
+}
+
+// a run of instructions being built up, e.g. the code computing an expression.
+// blocks are appended to each other in the order the code should run.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub spans: Vec<Option<Span>>,
+}
+
+impl Block {
+    pub fn new() -> Block {
+        Block { instructions: vec![], spans: vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn push(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+        self.spans.push(None);
+    }
+
+    pub fn append(&mut self, other: Block) {
+        self.instructions.extend(other.instructions);
+        self.spans.extend(other.spans);
+    }
+
+    // marks the instructions that do not know where they came from yet,
+    // so a statement claims everything except its nested statements.
+    pub fn at(&mut self, span: Option<Span>) {
+        for s in self.spans.iter_mut() {
+            if s.is_none() {
+                *s = span;
+            }
+        }
+    }
+
+    pub fn declare(&mut self, typ: Type, name: &str) {
+        self.push(Instruction::Declare(typ, String::from(name)));
+    }
+
+    pub fn declare_array(&mut self, typ: Type, name: &str, length: i64) {
+        self.push(Instruction::DeclareArray(typ, String::from(name), length));
+    }
+
+    pub fn call(&mut self, dest: &str, function: &str, arguments: Vec<Operand>) {
+        self.push(Instruction::Call(String::from(dest), String::from(function), arguments));
+    }
+
+    pub fn ret(&mut self, value: Operand) {
+        self.push(Instruction::Return(value));
+    }
+
+    pub fn out(&mut self, value: Operand) {
+        self.push(Instruction::Out(value));
+    }
+
+    pub fn out_str(&mut self, constant: &str) {
+        self.push(Instruction::OutStr(String::from(constant)));
+    }
+
+    pub fn out_int(&mut self, value: Operand) {
+        self.push(Instruction::OutInt(value));
+    }
+
+    pub fn out_line(&mut self) {
+        self.push(Instruction::OutLine);
+    }
+
+    pub fn input(&mut self, dest: &str) {
+        self.push(Instruction::In(String::from(dest)));
+    }
+
+    pub fn out_char(&mut self, value: Operand) {
+        self.push(Instruction::OutChar(value));
+    }
+
+    pub fn input_char(&mut self, dest: &str) {
+        self.push(Instruction::InChar(String::from(dest)));
+    }
+
+    pub fn mov(&mut self, dest: &str, src: Operand) {
+        self.push(Instruction::Mov(String::from(dest), src));
+    }
+
+    pub fn load(&mut self, dest: &str, array: &str, index: Operand) {
+        self.push(Instruction::Load(String::from(dest), String::from(array), index));
+    }
+
+    pub fn store(&mut self, array: &str, index: Operand, src: Operand) {
+        self.push(Instruction::Store(String::from(array), index, src));
+    }
+
+    pub fn binary(&mut self, op: BinaryOp, dest: &str, src1: Operand, src2: Operand) {
+        self.push(Instruction::Binary(op, String::from(dest), src1, src2));
+    }
+
+    pub fn label(&mut self, label: &Label) {
+        self.push(Instruction::Label(label.clone()));
+    }
+
+    pub fn jump(&mut self, label: &Label) {
+        self.push(Instruction::Jump(label.clone()));
+    }
+
+    pub fn branch_if(&mut self, condition: Operand, label: &Label) {
+        self.push(Instruction::BranchIf(condition, label.clone()));
+    }
+
+    pub fn branch_ifn(&mut self, condition: Operand, label: &Label) {
+        self.push(Instruction::BranchIfn(condition, label.clone()));
+    }
+}
+
+impl Function {
+    pub fn new(name: &str) -> Function {
+        Function {
+            name: String::from(name),
+            parameters: vec![],
+            body: vec![],
+            line: 0,
+            lines: vec![],
+            spans: vec![],
+        }
+    }
+
+    pub fn parameter(&mut self, typ: Type, name: &str) {
+        self.parameters.push((typ, String::from(name)));
+    }
+
+    pub fn append(&mut self, block: Block) {
+        self.body.extend(block.instructions);
+        self.spans.extend(block.spans);
+    }
+
+    // where the instruction at index came from, if known.
+    pub fn span(&self, index: usize) -> Option<Span> {
+        self.spans.get(index).copied().flatten()
+    }
+}
+
+impl Module {
+    pub fn new() -> Module {
+        Module { strings: vec![], functions: vec![] }
+    }
+
+    pub fn add_string(&mut self, name: &str, text: &str) {
+        self.strings.push((String::from(name), String::from(text)));
+    }
+
+    pub fn add_function(&mut self, function: Function) {
+        self.functions.push(function);
+    }
+
+    // fill in the line numbers each instruction would have once printed.
+    pub fn number_lines(&mut self) {
+        let mut line = self.strings.len() + 1;
+        for function in &mut self.functions {
+            function.line = line;
+            line += 1; // %func header
+            function.lines = (line..line + function.body.len()).collect();
+            line += function.body.len() + 1; // %endfunc
+        }
+    }
+}
+
+impl Instruction {
+    // the variables the instruction reads, in the order they appear.
+    pub fn sources(&self) -> Vec<&str> {
+        use Instruction::*;
+        let operands: Vec<&Operand> = match self {
+        Call(_, _, arguments) => arguments.iter().collect(),
+        Return(value) | Out(value) | OutInt(value) | OutChar(value) => vec![value],
+        Mov(_, src) => vec![src],
+        Load(_, _, index) => vec![index],
+        Store(_, index, src) => vec![index, src],
+        Binary(_, _, src1, src2) => vec![src1, src2],
+        BranchIf(condition, _) | BranchIfn(condition, _) => vec![condition],
+        _ => vec![],
+        };
+        operands.into_iter().filter_map(|operand| match operand {
+        Operand::Var(name) => Some(name.as_str()),
+        Operand::Num(_) => None,
+        }).collect()
+    }
+
+    // the variable the instruction writes. a %call writes it when the call returns,
+    // and a store writes an array element rather than a variable.
+    pub fn dest(&self) -> Option<&str> {
+        use Instruction::*;
+        match self {
+        Declare(_, name) | Call(name, _, _) | In(name) | InChar(name) => Some(name),
+        Mov(dest, _) | Load(dest, _, _) | Binary(_, dest, _, _) => Some(dest),
+        _ => None,
+        }
+    }
+}
+
+impl Type {
+    pub fn name(&self) -> &'static str {
+        match self {
+        Type::Int => "int",
+        Type::Long => "long",
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn opcode(&self) -> &'static str {
+        match self {
+        BinaryOp::Add => "%add",
+        BinaryOp::Sub => "%sub",
+        BinaryOp::Mult => "%mult",
+        BinaryOp::Div => "%div",
+        BinaryOp::Mod => "%mod",
+        BinaryOp::LessThan => "%lt",
+        BinaryOp::LessEqual => "%le",
+        BinaryOp::NotEqual => "%neq",
+        BinaryOp::Equal => "%eq",
+        BinaryOp::GreaterEqual => "%ge",
+        BinaryOp::GreaterThan => "%gt",
+        }
+    }
+
+    // the operator as it is written in a .tt file.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mult => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::LessThan => "<",
+        BinaryOp::LessEqual => "<=",
+        BinaryOp::NotEqual => "!=",
+        BinaryOp::Equal => "==",
+        BinaryOp::GreaterEqual => ">=",
+        BinaryOp::GreaterThan => ">",
+        }
+    }
+}
+
+// escape a string constant so the IR lexer reads back the same text
+pub fn escape_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+        '\n' => escaped += "\\n",
+        '\t' => escaped += "\\t",
+        '\r' => escaped += "\\r",
+        '\0' => escaped += "\\0",
+        '\\' => escaped += "\\\\",
+        '"' => escaped += "\\\"",
+        _ => escaped.push(c),
+        }
+    }
+    escaped += "\"";
+    escaped
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":{}", self.0)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        Operand::Var(name) => write!(f, "{name}"),
+        Operand::Num(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match self {
+        Declare(typ, name) => write!(f, "%{} {name}", typ.name()),
+        DeclareArray(typ, name, length) => write!(f, "%{}[] {name}, {length}", typ.name()),
+        Call(dest, function, arguments) => {
+            let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+            write!(f, "%call {dest}, {function}({})", arguments.join(","))
+        }
+        Return(value) => write!(f, "%ret {value}"),
+        Out(value) => write!(f, "%out {value}"),
+        OutStr(constant) => write!(f, "%out_str {constant}"),
+        OutInt(value) => write!(f, "%out_int {value}"),
+        OutLine => write!(f, "%out_nl"),
+        In(dest) => write!(f, "%input {dest}"),
+        OutChar(value) => write!(f, "%outc {value}"),
+        InChar(dest) => write!(f, "%inputc {dest}"),
+        Mov(dest, src) => write!(f, "%mov {dest}, {src}"),
+        Load(dest, array, index) => write!(f, "%mov {dest}, [{array} + {index}]"),
+        Store(array, index, src) => write!(f, "%mov [{array} + {index}], {src}"),
+        Binary(op, dest, src1, src2) => write!(f, "{} {dest}, {src1}, {src2}", op.opcode()),
+        Label(label) => write!(f, "{label}"),
+        Jump(label) => write!(f, "%jmp {label}"),
+        BranchIf(condition, label) => write!(f, "%branch_if {condition}, {label}"),
+        BranchIfn(condition, label) => write!(f, "%branch_ifn {condition}, {label}"),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter().map(|(typ, name)| format!("%{} {name}", typ.name())).collect();
+        writeln!(f, "%func {}({})", self.name, parameters.join(", "))?;
+        for instruction in &self.body {
+            writeln!(f, "{instruction}")?;
+        }
+        writeln!(f, "%endfunc")
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, text) in &self.strings {
+            writeln!(f, "%str {name}, {}", escape_string(text))?;
+        }
+        for function in &self.functions {
+            write!(f, "{function}")?;
+        }
+        Ok(())
+    }
+}
@@ -15,14 +15,20 @@ pub enum Token {
     Equality,         
     NotEqual,         
     Num(i32),         
+    LongNum(i64),     
     Ident(String),    
+    Str(String),      
     If,               
     While,            
     Read,             
+    ReadChar,         
     Func,             
     Return,           
     Int,              
+    Long,             
+    Void,             
     Print,            
+    PrintChar,        
     Else,             
     Break,            
     Continue,         
@@ -34,15 +40,25 @@ pub enum Token {
     RightBracket,     
     Comma,            
     Semicolon,        
+    Arrow,            
     End,              
 }
 
+// where a token starts in the source, both counted from 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
 // Make the function public so it can be used in main.rs
 pub fn create_identifier(code: &str) -> Token {
     match code {
         "func" => Token::Func,
         "return" => Token::Return,
         "int" => Token::Int,
+        "long" => Token::Long,
+        "void" => Token::Void,
         "print" => Token::Print,
         "else" => Token::Else,
         "break" => Token::Break,
@@ -50,17 +66,35 @@ pub fn create_identifier(code: &str) -> Token {
         "while" => Token::While,
         "if" => Token::If,
         "read" => Token::Read,
+        "printc" => Token::PrintChar,
+        "readc" => Token::ReadChar,
         _ => Token::Ident(String::from(code)), 
     }
 }
 
 // Make the lex function public
 pub fn lex(code: &str) -> Result<Vec<Token>, String> {
+    let (tokens, _) = lex_with_spans(code)?;
+    Ok(tokens)
+}
+
+// same as lex, but also gives back where each token starts
+pub fn lex_with_spans(code: &str) -> Result<(Vec<Token>, Vec<Span>), String> {
     let bytes = code.as_bytes();
     let mut tokens: Vec<Token> = vec![];
+    let mut spans: Vec<Span> = vec![];
     let mut i = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut start = Span { line: 1, column: 1 };
 
     while i < bytes.len() {
+        // a token added last time round started where that character was
+        while spans.len() < tokens.len() {
+            spans.push(start);
+        }
+        start = Span { line, column: i - line_start + 1 };
+
         let c = bytes[i] as char;
 
         match c {
@@ -87,6 +121,13 @@ pub fn lex(code: &str) -> Result<Vec<Token>, String> {
                 }
                 let end = i;
 
+                // 10L is a long literal
+                let long_suffix = i < bytes.len() && bytes[i] == b'L'
+                    && !(i + 1 < bytes.len() && ((bytes[i + 1] as char).is_alphanumeric() || bytes[i + 1] == b'_'));
+                if long_suffix {
+                    i += 1;
+                }
+
                 if i < bytes.len() && ((bytes[i] as char).is_alphabetic() || bytes[i] == b'_') {
                     return Err(format!(
                         "Lexer: Invalid variable name starting with a number at: {}",
@@ -95,14 +136,105 @@ pub fn lex(code: &str) -> Result<Vec<Token>, String> {
                 }
 
                 let string_token = &code[start..end];
-                let number_value = string_token.parse::<i32>().unwrap();
-                tokens.push(Token::Num(number_value));
+                let number_value = match string_token.parse::<i64>() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        return Err(format!("Lexer: Number {} is too large for a long", string_token));
+                    }
+                };
+
+                // literals too big for an int are inferred to be long
+                if long_suffix || number_value > i32::MAX as i64 {
+                    tokens.push(Token::LongNum(number_value));
+                } else {
+                    tokens.push(Token::Num(number_value as i32));
+                }
             }
             '#' => {
                 while i < bytes.len() && bytes[i] != b'\n' {
                     i += 1;
                 }
             }
+            '\'' => {
+                // character literals are just their byte value, 'a' == 97
+                let start = i;
+                let value: i32;
+                if i + 1 >= bytes.len() || bytes[i + 1] == b'\'' || bytes[i + 1] == b'\n' {
+                    return Err(format!("Lexer: Empty or unterminated character literal at: {}", &code[start..(i + 2).min(bytes.len())]));
+                }
+
+                if bytes[i + 1] == b'\\' {
+                    if i + 2 >= bytes.len() {
+                        return Err(String::from("Lexer: Unterminated character literal"));
+                    }
+                    value = match bytes[i + 2] {
+                        b'n' => b'\n' as i32,
+                        b't' => b'\t' as i32,
+                        b'r' => b'\r' as i32,
+                        b'0' => 0,
+                        b'\\' => b'\\' as i32,
+                        b'\'' => b'\'' as i32,
+                        b'"' => b'"' as i32,
+                        _ => {
+                            return Err(format!("Lexer: Invalid escape sequence '\\{}' in character literal", bytes[i + 2] as char));
+                        }
+                    };
+                    i += 3;
+                } else if bytes[i + 1].is_ascii() {
+                    value = bytes[i + 1] as i32;
+                    i += 2;
+                } else {
+                    return Err(String::from("Lexer: Character literals must be a single ASCII character"));
+                }
+
+                if i >= bytes.len() || bytes[i] != b'\'' {
+                    return Err(String::from("Lexer: Character literals must contain exactly one character and end with '"));
+                }
+                i += 1;
+
+                tokens.push(Token::Num(value));
+            }
+            '"' => {
+                let start = i;
+                i += 1;
+                let mut text: Vec<u8> = vec![];
+                loop {
+                    if i >= bytes.len() || bytes[i] == b'\n' {
+                        return Err(format!("Lexer: Unterminated string literal starting at: {}", &code[start..i]));
+                    }
+
+                    match bytes[i] {
+                        b'"' => {
+                            i += 1;
+                            break;
+                        }
+                        b'\\' => {
+                            if i + 1 >= bytes.len() {
+                                return Err(format!("Lexer: Unterminated string literal starting at: {}", &code[start..i + 1]));
+                            }
+                            match bytes[i + 1] {
+                                b'n' => text.push(b'\n'),
+                                b't' => text.push(b'\t'),
+                                b'r' => text.push(b'\r'),
+                                b'0' => text.push(0),
+                                b'\\' => text.push(b'\\'),
+                                b'"' => text.push(b'"'),
+                                _ => {
+                                    return Err(format!("Lexer: Invalid escape sequence '\\{}' in string literal", bytes[i + 1] as char));
+                                }
+                            }
+                            i += 2;
+                        }
+                        byte => {
+                            text.push(byte);
+                            i += 1;
+                        }
+                    }
+                }
+
+                // only whole characters were copied, so this is still valid utf-8
+                tokens.push(Token::Str(String::from_utf8(text).unwrap()));
+            }
             '<' => {
                 if i + 1 < bytes.len() && bytes[i + 1] == b'=' {
                     tokens.push(Token::LessEqual);
@@ -139,6 +271,10 @@ pub fn lex(code: &str) -> Result<Vec<Token>, String> {
                 i += 1;
             }
             ' ' | '\n' => {
+                if c == '\n' {
+                    line += 1;
+                    line_start = i + 1;
+                }
                 i += 1;
             }
             '+' => {
@@ -146,6 +282,11 @@ pub fn lex(code: &str) -> Result<Vec<Token>, String> {
                 i += 1;
             }
             '-' => {
+                if i + 1 < bytes.len() && bytes[i + 1] == b'>' {
+                    tokens.push(Token::Arrow);
+                    i += 2;
+                    continue;
+                }
                 tokens.push(Token::Subtract);
                 i += 1;
             }
@@ -199,6 +340,10 @@ pub fn lex(code: &str) -> Result<Vec<Token>, String> {
         }
     }
 
+    while spans.len() < tokens.len() {
+        spans.push(start);
+    }
     tokens.push(Token::End);
-    Ok(tokens)
+    spans.push(Span { line, column: i - line_start + 1 });
+    Ok((tokens, spans))
 }
@@ -2,3 +2,5 @@
 pub mod lexer;
 pub mod parser;
 pub mod semantic_parser;
+pub mod ir;
+pub mod ast;
@@ -1,66 +1,68 @@
-
 #![allow(dead_code)]
 // src/parser.rs
 // Import lexer functions or structs
 use super::lexer::*;  // Adjust based on your actual lexer implementation
+use super::ast::*;
 
 
 // parse programs with multiple functions
-// loop over everything, outputting generated code.
-pub fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+// loop over everything, building the tree of each function.
+pub fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<Program, String> {
   assert!(tokens.len() >= 1 && matches!(tokens[tokens.len() - 1], Token::End));
+  let mut program = Program { functions: vec![] };
   while !at_end(tokens, *index) {
     match parse_function(tokens, index) {
-    Ok(()) => {}
+    Ok(function) => program.functions.push(function),
     Err(e) => { return Err(e); }
     }
   }
-  return Ok(());
+  return Ok(program);
 }
-  
+
 pub fn at_end(tokens: &Vec<Token>, index: usize) -> bool {
   match tokens[index] {
     Token::End => { true }
     _ => { false }
   }
 }
-  
+
   // parse function such as:
-  // func main(int a, int b) {
+  // func main(int a, int b) -> int {
   //    # ... statements here...
   //    # ...
   // }
   // a loop is done to handle statements.
-  
-pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Function, String> {
   match tokens[*index] {
     Token::Func => *index += 1,
     _ => return Err(String::from("Parser: Functions must begin with 'func'")),
   }
 
-  match tokens[*index] {
-    Token::Ident(_) => *index += 1,
+  let name = match &tokens[*index] {
+    Token::Ident(name) => name.clone(),
     _ => return Err(String::from("Parser: Functions must have a function identifier")),
-  }
+  };
+  *index += 1;
 
   match tokens[*index] {
     Token::LeftParen => *index += 1,
     _ => return Err(String::from("Parser: Funtion expects '('")),
   }
 
-  // Handling parameter declarations (e.g., func(int example, int a, int b))
+  // Handling parameter declarations (e.g., func(int example, int a, long b))
+  let mut parameters: Vec<Declaration> = vec![];
   while !matches!(tokens[*index], Token::RightParen) {
     match parse_declaration(tokens, index) {
-      Ok(()) => {}
+      Ok(parameter) => parameters.push(parameter),
       Err(e) => return Err(e),
     }
 
+    // If there's a comma, advance and parse the next parameter
     if tokens[*index] == Token::Comma {
       *index += 1;
-      match parse_declaration(tokens, index) {
-        Ok(()) => {}
-        Err(e) => return Err(e),
-      }
+    } else {
+      break;
     }
   }
 
@@ -69,14 +71,29 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<(), Stri
     _ => return Err(String::from("Parser: Function expects ')'")),
   }
 
+  // Optional return type annotation: func f() -> int, func f() -> long, func f() -> void
+  let mut return_type = ReturnType::Unspecified;
+  if tokens[*index] == Token::Arrow {
+    *index += 1;
+
+    return_type = match tokens[*index] {
+      Token::Int => ReturnType::Int,
+      Token::Long => ReturnType::Long,
+      Token::Void => ReturnType::Void,
+      _ => return Err(String::from("Parser: Function return type must be 'int', 'long' or 'void'")),
+    };
+    *index += 1;
+  }
+
   match tokens[*index] {
     Token::LeftCurly => *index += 1,
     _ => return Err(String::from("Parser: Function expects '{'")),
   }
 
-  while !matches!(tokens[*index], Token::RightCurly) {
+  let mut body: Vec<Statement> = vec![];
+  while !matches!(tokens[*index], Token::RightCurly | Token::End) {
     match parse_statement(tokens, index) {
-      Ok(()) => {}
+      Ok(statement) => body.push(statement),
       Err(e) => return Err(e),
     }
   }
@@ -86,24 +103,27 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<(), Stri
     _ => return Err(String::from("Parser: Function expects '}'")),
   }
 
-  Ok(())
+  Ok(Function { name, parameters, return_type, body })
 }
-  
-  
+
+
   //our declaration for in function declarations
   // Declaration parsing for function parameter declarations
-pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
-  match tokens[*index] {
-    Token::Int => *index += 1,
-    _ => return Err(String::from("Parser: Function declaration statements must begin with 'int' keyword")),
-  }
+pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<Declaration, String> {
+  let typ = match tokens[*index] {
+    Token::Int => Type::Int,
+    Token::Long => Type::Long,
+    _ => return Err(String::from("Parser: Function declaration statements must begin with 'int' or 'long' keyword")),
+  };
+  *index += 1;
 
   // Handle `int [num] ident` logic
+  let mut length: Option<i32> = None;
   if tokens[*index] == Token::LeftBracket {
     *index += 1;
-    
+
     match tokens[*index] {
-      Token::Num(_) => *index += 1,
+      Token::Num(num) => { length = Some(num); *index += 1; }
       _ => return Err(String::from("Parser: Function declarations of arrays must have Type [Num] Ident form")),
     }
 
@@ -113,15 +133,16 @@ pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<(), S
     }
   }
 
-  match tokens[*index] {
-    Token::Ident(_) => *index += 1,
+  let name = match &tokens[*index] {
+    Token::Ident(name) => name.clone(),
     _ => return Err(String::from("Parser: Function declarations must have an identifier")),
-  }
+  };
+  *index += 1;
 
-  Ok(())
+  Ok(Declaration { typ, length, name })
 }
 
-  
+
   // parsing a statement such as:
   // int a;
   // a = a + b;
@@ -130,13 +151,21 @@ pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<(), S
   // read(a)
   // returns epsilon if '}'
 
-pub fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+pub fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::Int => parse_declaration_statement(tokens, index),
-    Token::Ident(_) => parse_assignment_statement(tokens, index),
+    Token::Int | Token::Long => parse_declaration_statement(tokens, index),
+    Token::Ident(_) => {
+      if tokens[*index + 1] == Token::LeftParen {
+        parse_call_statement(tokens, index)
+      } else {
+        parse_assignment_statement(tokens, index)
+      }
+    }
     Token::Return => parse_return_statement(tokens, index),
     Token::Print => parse_print_statement(tokens, index),
     Token::Read => parse_read_statement(tokens, index),
+    Token::PrintChar => parse_print_char_statement(tokens, index),
+    Token::ReadChar => parse_read_char_statement(tokens, index),
 
     // Control flow statements
     Token::If => parse_if_statement(tokens, index),
@@ -149,20 +178,23 @@ pub fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), Str
     _ => Err(String::from("Parser: Invalid statement")),
   }
 }
-  
-  
-pub fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
-  match tokens[*index] {
-    Token::Int => *index += 1,
-    _ => return Err(String::from("Parser: Declaration statements must begin with 'int' keyword")),
-  }
+
+
+pub fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
+  let typ = match tokens[*index] {
+    Token::Int => Type::Int,
+    Token::Long => Type::Long,
+    _ => return Err(String::from("Parser: Declaration statements must begin with 'int' or 'long' keyword")),
+  };
+  *index += 1;
 
   // Handle `int [num] ident` logic
+  let mut length: Option<i32> = None;
   if tokens[*index] == Token::LeftBracket {
     *index += 1;
-    
+
     match tokens[*index] {
-      Token::Num(_) => *index += 1,
+      Token::Num(num) => { length = Some(num); *index += 1; }
       _ => return Err(String::from("Parser: Declarations of arrays must have Type [Num] Ident form")),
     }
 
@@ -172,32 +204,35 @@ pub fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Re
     }
   }
 
-  match tokens[*index] {
-    Token::Ident(_) => *index += 1,
+  let name = match &tokens[*index] {
+    Token::Ident(name) => name.clone(),
     _ => return Err(String::from("Parser: Declarations must have an identifier")),
-  }
+  };
+  *index += 1;
 
   match tokens[*index] {
     Token::Semicolon => *index += 1,
     _ => return Err(String::from("Parser: Declarations statements must end with a semicolon")),
   }
 
-  Ok(())
+  Ok(Statement::Declare(Declaration { typ, length, name }))
 }
 
 
-pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
-  match tokens[*index] {
-    Token::Ident(_) => *index += 1,
+pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
+  let name = match &tokens[*index] {
+    Token::Ident(name) => name.clone(),
     _ => return Err(String::from("Parser: Assignment statements must begin with an identifier")),
-  }
+  };
+  *index += 1;
 
   // Support for array indexing assignment (e.g., arr[expression] = var)
+  let mut target = Expression::Var(name.clone());
   if tokens[*index] == Token::LeftBracket {
     *index += 1;
 
     match parse_expression(tokens, index) {
-      Ok(()) => {},
+      Ok(element) => target = Expression::Index(name, Box::new(element)),
       Err(e) => return Err(e),
     }
 
@@ -212,294 +247,388 @@ pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Res
     _ => return Err(String::from("Parser: Assignment statement is missing the '=' operator")),
   }
 
-  match parse_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
-  }
+  let value = parse_expression(tokens, index)?;
 
   match tokens[*index] {
     Token::Semicolon => *index += 1,
     _ => return Err(String::from("Parser: Assignment statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(Statement::Assign(target, value))
+}
+
+
+// a call whose value is not used, e.g. to a void function: greet(3);
+pub fn parse_call_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
+  match tokens[*index] {
+    Token::Ident(_) => {}
+    _ => return Err(String::from("Parser: Call statements must begin with a function identifier")),
+  }
+
+  let call = match parse_term(tokens, index) {
+    Ok(Expression::Call(function, arguments)) => Statement::Call(function, arguments),
+    Ok(_) => return Err(String::from("Parser: Call statements must call a function")),
+    Err(e) => return Err(e),
+  };
+
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Call statements must end with a semicolon ';'")),
+  }
+
+  Ok(call)
 }
 
-fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
   Token::Return => {*index += 1;}
   _ => {return Err(String::from("Parser: Return statements must begin with a return keyword"));}
   }
 
-  match parse_expression(tokens, index) {
-  Ok(()) => {},
-  Err(e) => {return Err(e);}
+  // bare return;
+  if tokens[*index] == Token::Semicolon {
+    *index += 1;
+    return Ok(Statement::Return(None));
   }
 
+  let value = parse_expression(tokens, index)?;
+
   match tokens[*index] {
     Token::Semicolon => {*index += 1;}
     _ => {return Err(String::from("Parser: Return statements must end with a semicolon"));}
   }
 
-  return Ok(());
+  return Ok(Statement::Return(Some(value)));
 }
 
-  
-pub fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+// print x;
+// print("sum = ", x, "\n");
+pub fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
     Token::Print => *index += 1,
     _ => return Err(String::from("Parser: Print statements must begin with the 'print' keyword")),
   }
 
-  // Parse the expression inside the parentheses
-  match parse_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
+  if tokens[*index] == Token::LeftParen {
+    let start = *index;
+    match parse_print_arguments(tokens, index) {
+      Ok(arguments) => return Ok(Statement::Print(arguments)),
+
+      // could still be a parenthesized expression such as print (a + b) * 2;
+      Err(e) => {
+        *index = start;
+        let is_expression = parse_expression(tokens, index).is_ok() && tokens[*index] == Token::Semicolon;
+        *index = start;
+        if !is_expression {
+          return Err(e);
+        }
+      }
+    }
   }
 
+  // Parse the expression to print
+  let value = parse_expression(tokens, index)?;
+
   // Expect semicolon
   match tokens[*index] {
     Token::Semicolon => *index += 1,
     _ => return Err(String::from("Parser: Print statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(Statement::Print(vec![PrintArgument::Expr(value)]))
 }
 
-  
-pub fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+fn parse_print_arguments(tokens: &Vec<Token>, index: &mut usize) -> Result<Vec<PrintArgument>, String> {
   match tokens[*index] {
-    Token::Read => *index += 1,
-    _ => return Err(String::from("Parser: Read statements must begin with the 'read' keyword")),
+    Token::LeftParen => *index += 1,
+    _ => return Err(String::from("Parser: Print arguments must begin with '('")),
   }
 
-  match parse_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
+  let mut arguments: Vec<PrintArgument> = vec![];
+  while !matches!(tokens[*index], Token::RightParen) {
+    match &tokens[*index] {
+      Token::Str(text) => {
+        *index += 1;
+        arguments.push(PrintArgument::Str(text.clone()));
+      }
+
+      _ => {
+        match parse_expression(tokens, index) {
+          Ok(value) => arguments.push(PrintArgument::Expr(value)),
+          Err(e) => return Err(e),
+        }
+      }
+    }
+
+    if tokens[*index] == Token::Comma {
+      *index += 1;
+    } else {
+      break;
+    }
+  }
+
+  match tokens[*index] {
+    Token::RightParen => *index += 1,
+    _ => return Err(String::from("Parser: Print arguments must be separated by ',' and end with ')'")),
   }
 
   match tokens[*index] {
     Token::Semicolon => *index += 1,
-    _ => return Err(String::from("Parser: Read statements must end with a semicolon ';'")),
+    _ => return Err(String::from("Parser: Print statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(arguments)
 }
 
-  
-pub fn parse_break_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+pub fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::Break => *index += 1,
-    _ => return Err(String::from("Parser: Expected 'break' keyword")),
+    Token::Read => *index += 1,
+    _ => return Err(String::from("Parser: Read statements must begin with the 'read' keyword")),
   }
 
+  let target = parse_expression(tokens, index)?;
+
   match tokens[*index] {
     Token::Semicolon => *index += 1,
-    _ => return Err(String::from("Parser: Break statements must end with a semicolon ';'")),
+    _ => return Err(String::from("Parser: Read statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(Statement::Read(target))
 }
 
-  
-pub fn parse_continue_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+// printc(c); writes the low byte of c
+pub fn parse_print_char_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::Continue => *index += 1,
-    _ => return Err(String::from("Parser: Expected 'continue' keyword")),
+    Token::PrintChar => *index += 1,
+    _ => return Err(String::from("Parser: Print character statements must begin with the 'printc' keyword")),
   }
 
+  let value = parse_expression(tokens, index)?;
+
   match tokens[*index] {
     Token::Semicolon => *index += 1,
-    _ => return Err(String::from("Parser: Continue statements must end with a semicolon ';'")),
+    _ => return Err(String::from("Parser: Print character statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(Statement::PrintChar(value))
 }
 
-  
-pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+// readc(c); or readc(arr[i]);
+pub fn parse_read_char_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::While => *index += 1,
-    _ => return Err(String::from("Parser: Expected 'while' keyword")),
+    Token::ReadChar => *index += 1,
+    _ => return Err(String::from("Parser: Read character statements must begin with the 'readc' keyword")),
   }
 
-  match parse_bool(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
+  let parenthesized = tokens[*index] == Token::LeftParen;
+  if parenthesized {
+    *index += 1;
   }
 
   match tokens[*index] {
-    Token::LeftCurly => *index += 1,
-    _ => return Err(String::from("Parser: While statement execution code must begin with '{'")),
+    Token::Ident(_) => {}
+    _ => return Err(String::from("Parser: Read character statements expect a variable or array element")),
   }
 
-  while !matches!(tokens[*index], Token::RightCurly) {
-    match parse_statement(tokens, index) {
-      Ok(()) => {},
-      Err(e) => return Err(e),
+  // a variable or an array element, both are terms
+  let target = match parse_term(tokens, index) {
+    Ok(target @ (Expression::Var(_) | Expression::Index(_, _))) => target,
+    Ok(_) => return Err(String::from("Parser: Read character statements expect a variable or array element")),
+    Err(e) => return Err(e),
+  };
+
+  if parenthesized {
+    match tokens[*index] {
+      Token::RightParen => *index += 1,
+      _ => return Err(String::from("Parser: Read character statements expect closing parenthesis ')'")),
     }
   }
 
   match tokens[*index] {
-    Token::RightCurly => *index += 1,
-    _ => return Err(String::from("Parser: While statement expects '}'")),
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Read character statements must end with a semicolon ';'")),
   }
 
-  Ok(())
+  Ok(Statement::ReadChar(target))
 }
 
-  
-  
-pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+pub fn parse_break_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::If => *index += 1,
-    _ => return Err(String::from("Parser: If statement expects 'if' keyword")),
+    Token::Break => *index += 1,
+    _ => return Err(String::from("Parser: Expected 'break' keyword")),
   }
 
-  match parse_bool(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Break statements must end with a semicolon ';'")),
+  }
+
+  Ok(Statement::Break)
+}
+
+
+pub fn parse_continue_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
+  match tokens[*index] {
+    Token::Continue => *index += 1,
+    _ => return Err(String::from("Parser: Expected 'continue' keyword")),
+  }
+
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Continue statements must end with a semicolon ';'")),
   }
 
+  Ok(Statement::Continue)
+}
+
+
+// the statements between '{' and '}', message_start and message_end are
+// the errors for a missing brace.
+fn parse_block(tokens: &Vec<Token>, index: &mut usize, message_start: &str, message_end: &str) -> Result<Vec<Statement>, String> {
   match tokens[*index] {
     Token::LeftCurly => *index += 1,
-    _ => return Err(String::from("Parser: If statement execution code must begin with '{'")),
+    _ => return Err(String::from(message_start)),
   }
 
-  while !matches!(tokens[*index], Token::RightCurly) {
+  let mut block: Vec<Statement> = vec![];
+  while !matches!(tokens[*index], Token::RightCurly | Token::End) {
     match parse_statement(tokens, index) {
-      Ok(()) => {},
+      Ok(statement) => block.push(statement),
       Err(e) => return Err(e),
     }
   }
 
   match tokens[*index] {
     Token::RightCurly => *index += 1,
-    _ => return Err(String::from("Parser: If statement expects '}'")),
+    _ => return Err(String::from(message_end)),
   }
 
-  if *index < tokens.len() && matches!(tokens[*index], Token::Else) {
-    *index += 1;
-
-    match tokens[*index] {
-      Token::LeftCurly => *index += 1,
-      _ => return Err(String::from("Parser: Else statement execution code must begin with '{'")),
-    }
+  Ok(block)
+}
 
-    while !matches!(tokens[*index], Token::RightCurly) {
-      match parse_statement(tokens, index) {
-        Ok(()) => {},
-        Err(e) => return Err(e),
-      }
-    }
 
-    match tokens[*index] {
-      Token::RightCurly => *index += 1,
-      _ => return Err(String::from("Parser: Else statement expects '}' after else block")),
-    }
+pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
+  match tokens[*index] {
+    Token::While => *index += 1,
+    _ => return Err(String::from("Parser: Expected 'while' keyword")),
   }
 
-  Ok(())
+  let condition = parse_bool(tokens, index)?;
+
+  let body = parse_block(tokens, index,
+    "Parser: While statement execution code must begin with '{'",
+    "Parser: While statement expects '}'")?;
+
+  Ok(Statement::While(condition, body))
 }
 
-  
-pub fn parse_bool(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
-  match parse_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
-  }
 
+
+pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Statement, String> {
   match tokens[*index] {
-    Token::Less 
-    | Token::LessEqual 
-    | Token::Greater 
-    | Token::GreaterEqual 
-    | Token::Equality 
-    | Token::NotEqual => {
-      *index += 1;
-    }
-    _ => return Err(String::from("Parser: Boolean expression expects a comparison operator")),
+    Token::If => *index += 1,
+    _ => return Err(String::from("Parser: If statement expects 'if' keyword")),
   }
 
-  match parse_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
+  let condition = parse_bool(tokens, index)?;
+
+  let then = parse_block(tokens, index,
+    "Parser: If statement execution code must begin with '{'",
+    "Parser: If statement expects '}'")?;
+
+  let mut otherwise: Option<Vec<Statement>> = None;
+  if *index < tokens.len() && matches!(tokens[*index], Token::Else) {
+    *index += 1;
+
+    otherwise = Some(parse_block(tokens, index,
+      "Parser: Else statement execution code must begin with '{'",
+      "Parser: Else statement expects '}' after else block")?);
   }
 
-  Ok(())
+  Ok(Statement::If(condition, then, otherwise))
+}
+
+
+pub fn parse_bool(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+  let left = parse_expression(tokens, index)?;
+
+  let op = match tokens[*index] {
+    Token::Less => BinaryOp::LessThan,
+    Token::LessEqual => BinaryOp::LessEqual,
+    Token::Greater => BinaryOp::GreaterThan,
+    Token::GreaterEqual => BinaryOp::GreaterEqual,
+    Token::Equality => BinaryOp::Equal,
+    Token::NotEqual => BinaryOp::NotEqual,
+    _ => return Err(String::from("Parser: Boolean expression expects a comparison operator")),
+  };
+  *index += 1;
+
+  let right = parse_expression(tokens, index)?;
+
+  Ok(Expression::Binary(op, Box::new(left), Box::new(right)))
 }
 
-  
+
 // Parsing complex expressions such as: "a + b - (c * d) / (f + g - 8);
-pub fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+pub fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
   // First, parse a multiply/divide expression
-  match parse_multiply_expression(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
-  }
+  let mut expr = parse_multiply_expression(tokens, index)?;
 
   // Then, handle addition and subtraction
   loop {
-    match tokens[*index] {
-      Token::Plus => {
-        *index += 1;
-        match parse_multiply_expression(tokens, index) {
-          Ok(()) => {},
-          Err(e) => return Err(e),
-        }
-      }
-
-      Token::Subtract => {
-        *index += 1;
-        match parse_multiply_expression(tokens, index) {
-          Ok(()) => {},
-          Err(e) => return Err(e),
-        }
-      }
-
+    let op = match tokens[*index] {
+      Token::Plus => BinaryOp::Add,
+      Token::Subtract => BinaryOp::Sub,
       _ => break, // Exit loop when no addition or subtraction operator is found
+    };
+    *index += 1;
+
+    match parse_multiply_expression(tokens, index) {
+      Ok(right) => expr = Expression::Binary(op, Box::new(expr), Box::new(right)),
+      Err(e) => return Err(e),
     }
   }
 
-  Ok(())
+  Ok(expr)
 }
 
-  
-pub fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
+
+pub fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
   // First, parse a term (e.g., a number, identifier, or parenthesized expression)
-  match parse_term(tokens, index) {
-    Ok(()) => {},
-    Err(e) => return Err(e),
-  }
+  let mut expr = parse_term(tokens, index)?;
 
   // Then, handle multiplication, division, and modulus operations
   loop {
-    match tokens[*index] {
-      Token::Multiply | Token::Divide | Token::Modulus => {
-        // Consume the operator (multiply, divide, or modulus)
-        let operator = tokens[*index].clone(); // We may want to use it later (debugging, etc.)
-        *index += 1;
-
-        // Parse the next term
-        match parse_term(tokens, index) {
-          Ok(()) => {},
-          Err(e) => return Err(e),
-        }
-      }
-
+    let op = match tokens[*index] {
+      Token::Multiply => BinaryOp::Mult,
+      Token::Divide => BinaryOp::Div,
+      Token::Modulus => BinaryOp::Mod,
       _ => break, // Exit loop when no operator is found
+    };
+    *index += 1;
+
+    // Parse the next term
+    match parse_term(tokens, index) {
+      Ok(right) => expr = Expression::Binary(op, Box::new(expr), Box::new(right)),
+      Err(e) => return Err(e),
     }
   }
 
-  Ok(())
+  Ok(expr)
 }
 
-  
-  
-pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String> {
-  match tokens[*index] {
+
+
+pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+  match &tokens[*index] {
     // Handle identifier (e.g., variable, function call, or array access)
-    Token::Ident(_) => {
+    Token::Ident(name) => {
       *index += 1;
 
       // Handle function call: ident(...)
@@ -507,9 +636,10 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String>
         *index += 1;
 
         // Parse the function's arguments
+        let mut arguments: Vec<Expression> = vec![];
         while !matches!(tokens[*index], Token::RightParen) {
           match parse_expression(tokens, index) {
-            Ok(()) => {},
+            Ok(argument) => arguments.push(argument),
             Err(e) => return Err(e),
           }
 
@@ -526,53 +656,78 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<(), String>
           Token::RightParen => *index += 1,
           _ => return Err(String::from("Parser: Function call expects closing parenthesis")),
         }
+
+        Ok(Expression::Call(name.clone(), arguments))
       }
 
       // Handle array access: ident[expression]
       else if tokens[*index] == Token::LeftBracket {
         *index += 1;
 
-        match parse_expression(tokens, index) {
-          Ok(()) => {},
-          Err(e) => return Err(e),
-        }
+        let element = parse_expression(tokens, index)?;
 
         match tokens[*index] {
           Token::RightBracket => *index += 1,
           _ => return Err(String::from("Parser: Array access expects closing bracket ']'")),
         }
+
+        Ok(Expression::Index(name.clone(), Box::new(element)))
       }
 
-      Ok(())
+      else {
+        Ok(Expression::Var(name.clone()))
+      }
     }
 
     // Handle numbers directly
-    Token::Num(_) => {
+    Token::Num(num) => {
+      *index += 1;
+      Ok(Expression::Num(*num))
+    }
+
+    Token::LongNum(num) => {
+      *index += 1;
+      Ok(Expression::LongNum(*num))
+    }
+
+    // Strings have no integer value
+    Token::Str(_) => Err(String::from("Parser: String literals can only be used as print arguments")),
+
+    // Handle casts: int(expression) or long(expression)
+    Token::Int | Token::Long => {
+      let typ = if tokens[*index] == Token::Long { Type::Long } else { Type::Int };
       *index += 1;
-      Ok(())
+
+      match tokens[*index] {
+        Token::LeftParen => *index += 1,
+        _ => return Err(format!("Parser: Cast to {} expects opening parenthesis '('", typ.name())),
+      }
+
+      let value = parse_expression(tokens, index)?;
+
+      match tokens[*index] {
+        Token::RightParen => *index += 1,
+        _ => return Err(format!("Parser: Cast to {} expects closing parenthesis ')'", typ.name())),
+      }
+
+      Ok(Expression::Cast(typ, Box::new(value)))
     }
 
     // Handle parenthesized expressions: (expression)
     Token::LeftParen => {
       *index += 1;
 
-      match parse_expression(tokens, index) {
-        Ok(()) => {},
-        Err(e) => return Err(e),
-      }
+      let expr = parse_expression(tokens, index)?;
 
       match tokens[*index] {
         Token::RightParen => *index += 1,
         _ => return Err(String::from("Parser: Expression expects closing parenthesis ')'")),
       }
 
-      Ok(())
+      Ok(expr)
     }
 
     // Handle invalid token
     _ => Err(String::from("Parser: Unexpected token, expected a term")),
   }
 }
-
-  
-  
\ No newline at end of file
@@ -2,6 +2,11 @@
 // src/parser.rs
 // Import lexer functions or structs
 use super::lexer::*;  // Adjust based on your actual lexer implementation
+use super::ir;
+use super::ir::{BinaryOp, Block, Label, Module, Operand};
+
+// the language's int and long are exactly the IR's %int and %long
+use super::ir::Type as ValueType;
 
 
 fn peek_error(tokens: &[Token], index: &mut usize) -> Result<Token, String> {
@@ -11,52 +16,80 @@ fn peek_error(tokens: &[Token], index: &mut usize) -> Result<Token, String> {
   Ok(tokens[*index].clone()) // Assuming Token is cloneable
 }
 
-fn create_temp() -> String {
-  static mut TEMP_COUNT: usize = 0; // Unsafe but works for quick testing
-  unsafe {
-      let temp_name = format!("t{}", TEMP_COUNT);
-      TEMP_COUNT += 1;
-      temp_name
-  }
+fn create_temp(ctx: &mut ParseContext) -> String {
+  let temp_name = format!("t{}", ctx.temp_count);
+  ctx.temp_count += 1;
+  temp_name
 }
 
-fn create_label() -> String {
-  static mut LABEL_COUNT: usize = 0; // Unsafe, consider using thread-safe alternatives
-  unsafe {
-      let label_name = format!(":{}", LABEL_COUNT);
-      LABEL_COUNT += 1;
-      label_name
+// string literals are pooled, $n is the index into string_constants
+fn create_string_constant(ctx: &mut ParseContext, text: &String) -> String {
+  for (i, existing) in ctx.string_constants.iter().enumerate() {
+      if existing.eq(text) {
+          return format!("${i}");
+      }
   }
+  ctx.string_constants.push(text.clone());
+  format!("${}", ctx.string_constants.len() - 1)
 }
 
+fn create_label(ctx: &mut ParseContext) -> Label {
+  let label_name = format!("{}", ctx.label_count);
+  ctx.label_count += 1;
+  Label(label_name)
+}
 
-//static mut scope_stack: Vec<Vec<(String, SymbolType)>> = vec![vec![]]; // Initialize with global scope
 
-static mut scope_stack: Vec<Vec<(String, SymbolType)>> = Vec::new();
+// everything the parser keeps track of while it works through one program.
+// each parse_program* call starts a fresh one, so programs can be parsed on
+// several threads at once.
+pub struct ParseContext {
+  temp_count: usize,
+  label_count: usize,
+  scope_stack: Vec<Vec<(String, SymbolType)>>,
+  in_loop: bool, //for confirming break statements
+  current_end_label: Option<Label>, //for jumping to end on break
+  current_start_label: Option<Label>, //for jumping back to the condition on continue
+  current_return_type: ReturnType, //for checking return statements
+  always_returns: bool, //true once every path through the current block has returned
+  string_constants: Vec<String>, //string literals, emitted as the %str section
+  token_spans: Vec<Span>, //where each token starts, empty when not known
+}
 
-static mut in_loop: bool = false; //for confirming break statements 
+impl ParseContext {
+  pub fn new(spans: &[Span]) -> ParseContext {
+    ParseContext {
+      temp_count: 0,
+      label_count: 0,
+      scope_stack: vec![],
+      in_loop: false,
+      current_end_label: None,
+      current_start_label: None,
+      current_return_type: ReturnType::Unspecified,
+      always_returns: false,
+      string_constants: vec![],
+      token_spans: spans.to_vec(),
+    }
+  }
+}
 
-static mut CURRENT_END_LABEL: String =  String::new(); //for jumping to end on break 
+fn token_span(ctx: &ParseContext, index: usize) -> Option<Span> {
+  ctx.token_spans.get(index).copied()
+}
 
-fn enter_scope() {
-    unsafe {
-        scope_stack.push(vec![]); // Create a new scope
-    }
+fn enter_scope(ctx: &mut ParseContext) {
+    ctx.scope_stack.push(vec![]); // Create a new scope
 }
 
-fn exit_scope() {
-    unsafe {
-        scope_stack.pop(); // Remove the current scope
-    }
+fn exit_scope(ctx: &mut ParseContext) {
+    ctx.scope_stack.pop(); // Remove the current scope
 }
 
-fn find_symbol(symbol: &String) -> bool {
-  unsafe {
-      if let Some(current_scope) = scope_stack.last() {
-          for (symbol_in_table, _) in current_scope {
-              if symbol_in_table.eq(symbol) {
-                  return true;
-              }
+fn find_symbol(ctx: &ParseContext, symbol: &String) -> bool {
+  if let Some(current_scope) = ctx.scope_stack.last() {
+      for (symbol_in_table, _) in current_scope {
+          if symbol_in_table.eq(symbol) {
+              return true;
           }
       }
   }
@@ -65,28 +98,85 @@ fn find_symbol(symbol: &String) -> bool {
 
 
 // Function to add a symbol to the current scope
-fn add_symbol(ident: String, symbol_type: SymbolType) {
-  unsafe {
-      // Ensure we have at least one scope to push to
-      if let Some(current_scope) = scope_stack.last_mut() {
-          current_scope.push((ident, symbol_type)); // Push the symbol to the current scope
-      }
+fn add_symbol(ctx: &mut ParseContext, ident: String, symbol_type: SymbolType) {
+  // Ensure we have at least one scope to push to
+  if let Some(current_scope) = ctx.scope_stack.last_mut() {
+      current_scope.push((ident, symbol_type)); // Push the symbol to the current scope
   }
 }
 
 
 
-fn find_symbol_type(symbol: &String) -> SymbolType {
-  unsafe {
-      if let Some(current_scope) = scope_stack.last() {
-          for (symbol_in_table, typ) in current_scope {
-              if symbol_in_table.eq(symbol) {
-                  return typ.clone(); // Return the type if found
+// Functions live in the global scope, which is always the bottom of the stack
+fn find_function_return_type(ctx: &ParseContext, symbol: &String) -> Option<ReturnType> {
+  if let Some(global_scope) = ctx.scope_stack.first() {
+      for (symbol_in_table, typ) in global_scope {
+          if symbol_in_table.eq(symbol) {
+              if let SymbolType::Function(return_type, _) = typ {
+                  return Some(return_type.clone());
+              }
+          }
+      }
+  }
+  None
+}
+
+
+fn find_function_parameters(ctx: &ParseContext, symbol: &String) -> Vec<ValueType> {
+  if let Some(global_scope) = ctx.scope_stack.first() {
+      for (symbol_in_table, typ) in global_scope {
+          if symbol_in_table.eq(symbol) {
+              if let SymbolType::Function(_, parameters) = typ {
+                  return parameters.clone();
               }
           }
       }
   }
-  SymbolType::Variable // Default type if not found in the current scope
+  vec![]
+}
+
+
+fn set_function_signature(ctx: &mut ParseContext, symbol: &String, return_type: ReturnType, parameters: Vec<ValueType>) {
+  if let Some(global_scope) = ctx.scope_stack.first_mut() {
+      for (symbol_in_table, typ) in global_scope.iter_mut() {
+          if (*symbol_in_table).eq(symbol) {
+              *typ = SymbolType::Function(return_type.clone(), parameters.clone());
+          }
+      }
+  }
+}
+
+
+// type of the most recently declared symbol, used to collect parameter types
+fn last_symbol_value_type(ctx: &ParseContext) -> ValueType {
+  if let Some(current_scope) = ctx.scope_stack.last() {
+      if let Some((_, typ)) = current_scope.last() {
+          return symbol_value_type(typ);
+      }
+  }
+  ValueType::Int
+}
+
+
+fn symbol_value_type(symbol_type: &SymbolType) -> ValueType {
+  match symbol_type {
+    SymbolType::Variable(value_type) => *value_type,
+    SymbolType::Array(value_type) => *value_type,
+    SymbolType::Function(ReturnType::Long, _) => ValueType::Long,
+    SymbolType::Function(_, _) => ValueType::Int,
+  }
+}
+
+
+fn find_symbol_type(ctx: &ParseContext, symbol: &String) -> SymbolType {
+  if let Some(current_scope) = ctx.scope_stack.last() {
+      for (symbol_in_table, typ) in current_scope {
+          if symbol_in_table.eq(symbol) {
+              return typ.clone(); // Return the type if found
+          }
+      }
+  }
+  SymbolType::Variable(ValueType::Int) // Default type if not found in the current scope
 }
 
 
@@ -95,9 +185,53 @@ fn find_symbol_type(symbol: &String) -> SymbolType {
 #[derive(Clone)]
 #[derive(PartialEq)]
 enum SymbolType {
-  Variable,   // Regular scalar variable
-  Function,   // Function declaration
-  Array,      // Array variable
+  Variable(ValueType),                    // Regular scalar variable
+  Function(ReturnType, Vec<ValueType>),   // Function declaration with its parameter types
+  Array(ValueType),                       // Array variable
+}
+
+
+fn type_name(value_type: &ValueType) -> &'static str {
+  value_type.name()
+}
+
+
+// unsuffixed int literals fit either type, so they take on the type of the other side
+fn is_int_literal(expr: &Expression) -> bool {
+  expr.typ == ValueType::Int && expr.code.is_empty() && matches!(expr.name, Operand::Num(_))
+}
+
+
+fn unify_types(left: &Expression, right: &Expression, context: &str) -> Result<ValueType, String> {
+  if left.typ == right.typ {
+    return Ok(left.typ);
+  }
+  if is_int_literal(left) {
+    return Ok(right.typ);
+  }
+  if is_int_literal(right) {
+    return Ok(left.typ);
+  }
+  Err(format!("Semantic Analysis: Mismatched types {} and {} in {context}, use an explicit int(...) or long(...) cast", type_name(&left.typ), type_name(&right.typ)))
+}
+
+
+// checks that an expression can be stored somewhere of type `expected`
+fn check_type(expected: &ValueType, expr: &Expression, context: &str) -> Result<(), String> {
+  if expected.eq(&expr.typ) || is_int_literal(expr) {
+    return Ok(());
+  }
+  Err(format!("Semantic Analysis: Expected {} but found {} in {context}, use an explicit int(...) or long(...) cast", type_name(expected), type_name(&expr.typ)))
+}
+
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+enum ReturnType {
+  Unspecified,  // func f() { ... } behaves like int but is not checked
+  Int,          // func f() -> int { ... } must return a value on every path
+  Long,         // func f() -> long { ... } same as int but 64-bit
+  Void,         // func f() -> void { ... } cannot return a value or be used in expressions
 }
 
 
@@ -110,25 +244,42 @@ enum SymbolType {
 // parse programs with multiple functions
 // loop over everything, outputting generated code.
 pub fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+  let module = parse_program_ir(tokens, index)?;
+  return Ok(module.to_string());
+}
+
+
+// same as parse_program, but hands back the IR before it is printed
+pub fn parse_program_ir(tokens: &Vec<Token>, index: &mut usize) -> Result<Module, String> {
+  return parse_program_with_spans(tokens, &vec![], index);
+}
 
 
-  let mut ir_code: String = String::new();
+// same as parse_program_ir, but each instruction also records which statement
+// it came from, using the spans from lex_with_spans
+pub fn parse_program_with_spans(tokens: &Vec<Token>, spans: &Vec<Span>, index: &mut usize) -> Result<Module, String> {
 
 
+  let mut module = Module::new();
+
+
+  // each program gets its own context, so the same program always gives the same IR
+  let ctx = &mut ParseContext::new(spans);
+
   // Enter global scope to track function declarations
-  enter_scope();
+  enter_scope(ctx);
   
 
 
 
   assert!(tokens.len() >= 1 && matches!(tokens[tokens.len() - 1], Token::End));
   while !at_end(tokens, *index) {
-    match parse_function(tokens, index) {
+    match parse_function(ctx, tokens, index) {
 
         //append fucntion ir code 
         Ok(function_ir_code) => {
 
-            ir_code += &function_ir_code.unwrap();
+            module.add_function(function_ir_code.unwrap());
             
         }
 
@@ -137,18 +288,22 @@ pub fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<String, S
     }
   }
 
-  unsafe{
 
-    if !find_symbol( &"main".to_string() ){
-      return Err(format!("Semantic Analysis: Did not find main function"));
-    }
+  if !find_symbol(ctx, &"main".to_string() ){
+    return Err(format!("Semantic Analysis: Did not find main function"));
   }
 
   // Exit global scope after parsing is complete
-  exit_scope();
+  exit_scope(ctx);
+
+  // string constant section goes ahead of the functions
+  for (i, text) in ctx.string_constants.iter().enumerate() {
+    module.add_string(&format!("${i}"), text);
+  }
 
+  module.number_lines();
 
-  return Ok(ir_code);
+  return Ok(module);
 }
   
 pub fn at_end(tokens: &Vec<Token>, index: usize) -> bool {
@@ -165,7 +320,7 @@ pub fn at_end(tokens: &Vec<Token>, index: usize) -> bool {
   // }
   // a loop is done to handle statements.
   
-pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<String>, String> {
+pub fn parse_function(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Option<ir::Function>, String> {
 
     //%func main()
     //%endfunc
@@ -176,27 +331,26 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
     }
 
 
-    let mut code: String = String::new();
+    let mut code = ir::Function::new("");
+    let function_name: String;
 
      
 
     match &tokens[*index] {
         Token::Ident(identifier_name) => {
           *index += 1;
+          function_name = identifier_name.clone();
             //duplicate function 
-          unsafe { 
-            if find_symbol( identifier_name ){
-              return Err(format!("Semantic Analysis: Found duplicate function {identifier_name}"));
-            }
-            
-            //symbol_table.push((identifier_name.clone(), SymbolType::Function));
-            add_symbol(identifier_name.clone(), SymbolType::Function);
+          if find_symbol(ctx, identifier_name ){
+            return Err(format!("Semantic Analysis: Found duplicate function {identifier_name}"));
+          }
+          
+          //symbol_table.push((identifier_name.clone(), SymbolType::Function));
+          add_symbol(ctx, identifier_name.clone(), SymbolType::Function(ReturnType::Unspecified, vec![]));
 
 
-          }
 
-            //this needs to be edited to handle declarations 
-            code += &format!("%func {identifier_name}(");
+            code.name = identifier_name.clone();
         },
         _ => return Err(String::from("Parser: Functions must have a function identifier")),
     }
@@ -210,19 +364,21 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
 
 
     // **Enter a new scope for parameters & function body**
-    enter_scope();
+    enter_scope(ctx);
+
+    let mut parameters: Vec<ValueType> = vec![];
 
-    // Handling parameter declarations (e.g., func(int example, int a, int b))
+    // Handling parameter declarations (e.g., func(int example, int a, long b))
     while !matches!(tokens[*index], Token::RightParen) {
-        match parse_declaration(tokens, index) {
-            Ok(declaration_code) => { code += &declaration_code; }
+        match parse_declaration(ctx, tokens, index) {
+            Ok((value_type, ident)) => { code.parameter(value_type, &ident); }
             Err(e) => return Err(e),
         }
+        parameters.push(last_symbol_value_type(ctx));
 
         // If there's a comma, advance and parse the next parameter
         if matches!(tokens[*index], Token::Comma) {
             *index += 1;
-            code += ", ";
         } else {
             break;
         }
@@ -233,7 +389,24 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
     _ => return Err(String::from("Parser: Function expects ')'")),
     }
 
-    code += ")\n";
+    // Optional return type annotation: func f() -> int, func f() -> long, func f() -> void
+    let mut return_type = ReturnType::Unspecified;
+    if matches!(tokens[*index], Token::Arrow) {
+      *index += 1;
+
+      match tokens[*index] {
+        Token::Int => return_type = ReturnType::Int,
+        Token::Long => return_type = ReturnType::Long,
+        Token::Void => return_type = ReturnType::Void,
+        _ => return Err(String::from("Parser: Function return type must be 'int', 'long' or 'void'")),
+      }
+      *index += 1;
+    }
+
+    set_function_signature(ctx, &function_name, return_type.clone(), parameters);
+
+    ctx.current_return_type = return_type.clone();
+    ctx.always_returns = false;
 
     match tokens[*index] {
       Token::LeftCurly => *index += 1,
@@ -242,8 +415,8 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
 
 
     while !matches!(tokens[*index], Token::RightCurly) {
-      match parse_statement(tokens, index) {
-          Ok(statment_code) => {code += &format!("{}",statment_code);}
+      match parse_statement(ctx, tokens, index) {
+          Ok(statment_code) => {code.append(statment_code);}
           Err(e) => return Err(e),
       }
     }
@@ -253,12 +426,12 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
     _ => return Err(String::from("Parser: Function expects '}'")),
     }
 
-    // Exit local scope after parsing the function body
-    exit_scope();
-
-    
+    if (return_type == ReturnType::Int || return_type == ReturnType::Long) && !ctx.always_returns {
+      return Err(format!("Semantic Analysis: Function {function_name} does not return a value on every path"));
+    }
 
-    code += "%endfunc\n";
+    // Exit local scope after parsing the function body
+    exit_scope(ctx);
 
     return Ok(Some(code));
 }
@@ -266,17 +439,18 @@ pub fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<S
   
   //our declaration for in function declarations
   // Declaration parsing for function parameter declarations
-pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+pub fn parse_declaration(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<(ValueType, String), String> {
 
-    let mut code: String = String::new();
-
-    let mut symbol_type = SymbolType::Variable;
+    let code: (ValueType, String);
 
+    let value_type = match tokens[*index] {
+        Token::Int => ValueType::Int,
+        Token::Long => ValueType::Long,
+        _ => return Err(String::from("Parser: Function declaration statements must begin with 'int' or 'long' keyword")),
+    };
+    *index += 1;
 
-    match tokens[*index] {
-        Token::Int => *index += 1,
-        _ => return Err(String::from("Parser: Function declaration statements must begin with 'int' keyword")),
-    }
+    let mut symbol_type = SymbolType::Variable(value_type);
 
     // Handle `int [num] ident` logic
     // We aren't assessed on this so skip, also is unclear for the actual parser why we would declare a function with sized array 
@@ -298,22 +472,20 @@ pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<Strin
             _ => return Err(String::from("Parser: Function declarations of arrays in Type [Num] Ident form require a closing bracket")),
         }
 
-        symbol_type = SymbolType::Array;
+        symbol_type = SymbolType::Array(value_type);
     }
 
     match &tokens[*index] {
     Token::Ident(ident) =>
     {
       //duplicate symbols 
-      unsafe { 
-        if find_symbol(ident){
-          return Err(format!("Semantic Analysis: Found duplicate variable {ident}"));
-        }
-
-        add_symbol(ident.clone(), symbol_type);
+      if find_symbol(ctx, ident){
+        return Err(format!("Semantic Analysis: Found duplicate variable {ident}"));
       }
+
+      add_symbol(ctx, ident.clone(), symbol_type);
         *index += 1;
-        code += &format!("%int {ident}");
+        code = (value_type, ident.clone());
 
     },
     _ => return Err(String::from("Parser: Function declarations must have an identifier")),
@@ -331,29 +503,48 @@ pub fn parse_declaration(tokens: &Vec<Token>, index: &mut usize) -> Result<Strin
   // read(a)
   // returns epsilon if '}'
 
-pub fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+pub fn parse_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+  let start = *index;
+  let mut code = parse_statement_code(ctx, tokens, index)?;
+  code.at(token_span(ctx, start));
+  return Ok(code);
+}
+
+fn parse_statement_code(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
   match tokens[*index] {
-    Token::Int => parse_declaration_statement(tokens, index),
+    Token::Int => parse_declaration_statement(ctx, tokens, index),
+
+    Token::Long => parse_declaration_statement(ctx, tokens, index),
 
-    Token::Ident(_) => parse_assignment_statement(tokens, index),
+    Token::Ident(_) => {
+      if *index + 1 < tokens.len() && tokens[*index + 1] == Token::LeftParen {
+        parse_call_statement(ctx, tokens, index)
+      } else {
+        parse_assignment_statement(ctx, tokens, index)
+      }
+    }
 
     
-    Token::Return => parse_return_statement(tokens, index),
+    Token::Return => parse_return_statement(ctx, tokens, index),
     
-    Token::Print => parse_print_statement(tokens, index),
+    Token::Print => parse_print_statement(ctx, tokens, index),
     
-    Token::Read => parse_read_statement(tokens, index),
+    Token::Read => parse_read_statement(ctx, tokens, index),
+
+    Token::PrintChar => parse_print_char_statement(ctx, tokens, index),
+
+    Token::ReadChar => parse_read_char_statement(ctx, tokens, index),
 
     
     // Control flow statements
-    Token::If => parse_if_statement(tokens, index),
+    Token::If => parse_if_statement(ctx, tokens, index),
     
-    Token::While => parse_while_statement(tokens, index),
+    Token::While => parse_while_statement(ctx, tokens, index),
     
 
     // Loop control statements
-    Token::Break => parse_break_statement(tokens, index),
-    Token::Continue => parse_continue_statement(tokens, index),
+    Token::Break => parse_break_statement(ctx, tokens, index),
+    Token::Continue => parse_continue_statement(ctx, tokens, index),
     
 
     _ => Err(String::from("Parser: Invalid statement")),
@@ -361,38 +552,35 @@ pub fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String,
 }
   
   
-pub fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-
-    let mut code: String = String::new();
-    let mut symbol_type = SymbolType::Variable;
+pub fn parse_declaration_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
+    let mut code = Block::new();
 
-    match tokens[*index] {
-        Token::Int => *index += 1,
-        _ => return Err(String::from("Parser: Declaration statements must begin with 'int' keyword")),
-    }
+    let value_type = match tokens[*index] {
+        Token::Int => ValueType::Int,
+        Token::Long => ValueType::Long,
+        _ => return Err(String::from("Parser: Declaration statements must begin with 'int' or 'long' keyword")),
+    };
+    *index += 1;
 
-    code += "%int";
+    let mut symbol_type = SymbolType::Variable(value_type);
 
 
     
-    let mut arrayNum: String = String::new();
-    arrayNum = "".to_string();
+    let mut arrayNum: Option<i64> = None;
 
     // Handle `int [num] ident` logic
     if tokens[*index] == Token::LeftBracket {
       
       *index += 1;
 
-      code += "[]";
-
       match tokens[*index] {
           Token::Num(num) => {
               *index += 1;
               if num == 0 {
                 return Err(format!("Semantic Analysis: Cannot have array sized 0"))
               }
-              arrayNum = format!(", {num}")
+              arrayNum = Some(num as i64)
           },
 
           _ => return Err(String::from("Parser: Declarations of arrays must have Type [Num] Ident form")),
@@ -405,54 +593,50 @@ pub fn parse_declaration_statement(tokens: &Vec<Token>, index: &mut usize) -> Re
           _ => return Err(String::from("Parser: Declarations of Type [Num] Ident form require a closing bracket")),
       }
 
-      symbol_type = SymbolType::Array;
+      symbol_type = SymbolType::Array(value_type);
     }
 
-    match &tokens[*index] {
+    let ident = match &tokens[*index] {
         Token::Ident(ident) => {
           //finding duplicates
-          unsafe { 
-            if find_symbol(ident){
-              return Err(format!("Semantic Analysis: Found duplicate variable {ident}"));
-            }
-    
-            add_symbol(ident.clone(), symbol_type);
+          if find_symbol(ctx, ident){
+            return Err(format!("Semantic Analysis: Found duplicate variable {ident}"));
           }
+  
+          add_symbol(ctx, ident.clone(), symbol_type);
 
           *index += 1;
-          code += &format!(" {ident}");
+          ident.clone()
         },
         _ => return Err(String::from("Parser: Declarations must have an identifier")),
-    }
+    };
 
     match tokens[*index] {
         Token::Semicolon => *index += 1,
         _ => return Err(String::from("Parser: Declarations statements must end with a semicolon")),
     }
 
-    code += &arrayNum;
-    code += "\n";
+    match arrayNum {
+        Some(num) => code.declare_array(value_type, &ident, num),
+        None => code.declare(value_type, &ident),
+    }
 
     return Ok(code);
 }
 
 
-pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+pub fn parse_assignment_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
-  let mut code: String = String::new();
-  let mut dest: String = String::new();
-  let mut src: String = String::new();
+  let mut code = Block::new();
+  let mut dest: Option<Operand> = None; //the index when assigning to an array element 
+  let src: Operand;
   let mut varHold: String = String::new();
 
-  let symbol_type = SymbolType::Variable; 
-
   match &tokens[*index] {
     Token::Ident(ident) => {
       //finding non declared variables 
-      unsafe { 
-        if !find_symbol(ident){
-          return Err(format!("Semantic Analysis: Variable {ident} not declared before assignment"));
-        }
+      if !find_symbol(ctx, ident){
+        return Err(format!("Semantic Analysis: Variable {ident} not declared before assignment"));
       }
       *index += 1;
       varHold = ident.to_string();
@@ -468,16 +652,15 @@ pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Res
     *index += 1;
 
 
-    if find_symbol_type(&varHold) != SymbolType::Array {
+    if !matches!(find_symbol_type(ctx, &varHold), SymbolType::Array(_)) {
       return Err(format!("Semantic Analysis: {varHold} is not array"));
     }
-    dest += "[";
-    dest += &format!("{varHold} + ");
 
-    match parse_expression(tokens, index) {
+    match parse_expression(ctx, tokens, index) {
       Ok(expression) => {
-        code += &expression.code;
-        dest += &expression.name
+        check_type(&ValueType::Int, &expression, "array index")?;
+        code.append(expression.code);
+        dest = Some(expression.name);
       },
       Err(e) => return Err(e),
     }
@@ -486,11 +669,7 @@ pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Res
       Token::RightBracket => *index += 1,
       _ => return Err(String::from("Parser: Array assignments must have a closing bracket ']'")),
     }
-    dest += "]";
   }
-
-
-  else {dest += &varHold;}
   
   
 
@@ -502,9 +681,12 @@ pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Res
   }
 
 
-  match parse_expression(tokens, index) {
+  let dest_type = symbol_value_type(&find_symbol_type(ctx, &varHold));
+
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
-      code += &expression.code; 
+      check_type(&dest_type, &expression, &format!("assignment to {varHold}"))?;
+      code.append(expression.code); 
       src = expression.name;
     },
     Err(e) => return Err(e),
@@ -517,20 +699,56 @@ pub fn parse_assignment_statement(tokens: &Vec<Token>, index: &mut usize) -> Res
 
   
 
-  code += &format!("%mov {dest}, {src}\n");
+  match dest {
+    Some(element) => code.store(&varHold, element, src),
+    None => code.mov(&varHold, src),
+  }
+
+  return Ok(code);
+}
+
+
+// calling a function for its side effects, e.g. log(a);
+// the result still lands in a temp since %call always has a destination
+pub fn parse_call_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+
+  let mut code = Block::new();
+
+  let ident = match &tokens[*index] {
+    Token::Ident(ident) => ident.clone(),
+    _ => return Err(String::from("Parser: Call statements must begin with a function identifier")),
+  };
+
+  // any function may be called as a statement, void or not
+  if find_function_return_type(ctx, &ident).is_none() {
+    return Err(format!("Semantic Analysis: Function {ident} not declared before call"));
+  }
+
+  *index += 1;
+
+  match parse_call_arguments(ctx, &ident, tokens, index) {
+    Ok(expression) => { code.append(expression.code); },
+    Err(e) => return Err(e),
+  }
+
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Call statements must end with a semicolon ';'")),
+  }
 
   return Ok(code);
 }
 
 
 //%ret value
-fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+fn parse_return_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
-  let mut code = String::new();
+  let mut code = Block::new();
   
   let mut expr = Expression {
-    code: String::new(),
-    name: String::new(),
+    code: Block::new(),
+    name: Operand::Num(0),
+    typ: ValueType::Int,
   };
   
   match tokens[*index] {
@@ -538,16 +756,44 @@ fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
     _ => {return Err(String::from("Parser: Return statements must begin with a return keyword"));}
   }
 
-  match parse_expression(tokens, index) {
+  let return_type = ctx.current_return_type.clone();
+
+  ctx.always_returns = true;
+
+  // bare return; hands back 0 so the IR keeps a single %ret form
+  if matches!(tokens[*index], Token::Semicolon) {
+    *index += 1;
+
+    if return_type == ReturnType::Int || return_type == ReturnType::Long {
+      return Err(String::from("Semantic Analysis: Function returning a value must return a value"));
+    }
+
+    code.ret(Operand::Num(0));
+    return Ok(code);
+  }
+
+  if return_type == ReturnType::Void {
+    return Err(String::from("Semantic Analysis: Void function cannot return a value"));
+  }
+
+  let expected_type = match return_type {
+    ReturnType::Long => ValueType::Long,
+    _ => ValueType::Int,
+  };
+
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
       expr = Expression {
         code: expression.code,
         name: expression.name,
+        typ: expression.typ,
       };
 
-      code += &expr.code;
+      check_type(&expected_type, &expr, "return statement")?;
+
+      code.append(expr.code);
       let statement = expr.name; 
-      code += &format!("%ret {statement}\n");
+      code.ret(statement);
 
     },
     Err(e) => {return Err(e);}
@@ -563,13 +809,18 @@ fn parse_return_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
 }
 
 
-pub fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+// print(x);
+// print("sum = ", x, "\n");
+// a single integer argument keeps the plain %out form, anything else is
+// written piece by piece and finished with a newline
+pub fn parse_print_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
-  let mut code = String::new();
+  let mut code = Block::new();
   
   let mut expr = Expression {
-    code: String::new(),
-    name: String::new(),
+    code: Block::new(),
+    name: Operand::Num(0),
+    typ: ValueType::Int,
   };
 
 
@@ -578,18 +829,32 @@ pub fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
     _ => return Err(String::from("Parser: Print statements must begin with the 'print' keyword")),
   }
 
-  match parse_expression(tokens, index) {
+  // print(a, "b"); is an argument list, but print (a + b) * 2; is an
+  // expression that starts with parentheses. look past the ')' to tell them
+  // apart before generating any code
+  if tokens[*index] == Token::LeftParen {
+    let is_arguments = match closing_paren(tokens, *index) {
+      Some(close) => tokens[close + 1] == Token::Semicolon,
+      None => true,
+    };
+    if is_arguments {
+      return parse_print_arguments(ctx, tokens, index);
+    }
+  }
+
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
       expr = Expression {
         code: expression.code,
         name: expression.name,
+        typ: expression.typ,
       };
 
-      code += &expr.code;
+      code.append(expr.code);
       let statement = expr.name; 
 
 
-      code += &format!("%out {statement}\n");
+      code.out(statement);
 
     },
     Err(e) => {return Err(e);}
@@ -606,14 +871,107 @@ pub fn parse_print_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
 }
 
 
+// the index of the ')' that closes the '(' at index, if there is one.
+fn closing_paren(tokens: &Vec<Token>, index: usize) -> Option<usize> {
+  let mut depth = 0;
+  for (i, token) in tokens.iter().enumerate().skip(index) {
+    match token {
+      Token::LeftParen => depth += 1,
+      Token::RightParen => {
+        depth -= 1;
+        if depth == 0 {
+          return Some(i);
+        }
+      }
+      Token::Semicolon | Token::End => return None,
+      _ => {}
+    }
+  }
+  return None;
+}
+
+
+fn parse_print_arguments(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+
+  let mut code = Block::new();
+  let mut pieces: Vec<Block> = vec![];
+  let mut single_value: Option<Block> = None;
+  let mut count = 0;
 
-pub fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+  match tokens[*index] {
+    Token::LeftParen => *index += 1,
+    _ => return Err(String::from("Parser: Print arguments must begin with '('")),
+  }
+
+  while !matches!(tokens[*index], Token::RightParen) {
+    match &tokens[*index] {
+      Token::Str(text) => {
+        *index += 1;
+        let constant = create_string_constant(ctx, text);
+        let mut piece = Block::new();
+        piece.out_str(&constant);
+        pieces.push(piece);
+        single_value = None;
+      }
+
+      _ => {
+        match parse_expression(ctx, tokens, index) {
+          Ok(expression) => {
+            let mut piece = expression.code.clone();
+            piece.out_int(expression.name.clone());
+            pieces.push(piece);
+
+            let mut value = expression.code;
+            value.out(expression.name);
+            single_value = Some(value);
+          },
+          Err(e) => return Err(e),
+        }
+      }
+    }
+    count += 1;
+
+    if tokens[*index] == Token::Comma {
+      *index += 1;
+    } else {
+      break;
+    }
+  }
+
+  match tokens[*index] {
+    Token::RightParen => *index += 1,
+    _ => return Err(String::from("Parser: Print arguments must be separated by ',' and end with ')'")),
+  }
 
-  let mut code = String::new();
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Print statements must end with a semicolon ';'")),
+  }
+
+  if count == 1 {
+    if let Some(value_code) = single_value {
+      return Ok(value_code);
+    }
+  }
+
+  for piece in pieces {
+    code.append(piece);
+  }
+  code.out_line();
+
+  return Ok(code);
+}
+
+
+
+pub fn parse_read_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+
+  let mut code = Block::new();
   
   let mut expr = Expression {
-    code: String::new(),
-    name: String::new(),
+    code: Block::new(),
+    name: Operand::Num(0),
+    typ: ValueType::Int,
   };
 
 
@@ -622,16 +980,20 @@ pub fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<St
     _ => return Err(String::from("Parser: Read statements must begin with the 'read' keyword")),
   }
 
-  match parse_expression(tokens, index) {
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
       expr = Expression {
         code: expression.code,
         name: expression.name,
+        typ: expression.typ,
       };
 
-      code += &expr.code;
-      let statement = expr.name; 
-      code += &format!("%input {statement}\n");
+      code.append(expr.code);
+      let statement = match expr.name {
+        Operand::Var(name) => name,
+        Operand::Num(_) => return Err(String::from("Semantic Analysis: read expects a variable")),
+      };
+      code.input(&statement);
 
     },
     Err(e) => {return Err(e);}
@@ -648,13 +1010,110 @@ pub fn parse_read_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<St
 }
 
 
-pub fn parse_break_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+// printc(c); writes the low byte of c
+pub fn parse_print_char_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
-   unsafe {
-        if !in_loop {
-            return Err(String::from("Semantic Analysis: break statement not within a loop"));
-        }
+  let mut code = Block::new();
+
+  match tokens[*index] {
+    Token::PrintChar => *index += 1,
+    _ => return Err(String::from("Parser: Print character statements must begin with the 'printc' keyword")),
+  }
+
+  match parse_expression(ctx, tokens, index) {
+    Ok(expression) => {
+      code.append(expression.code);
+      code.out_char(expression.name);
+    },
+    Err(e) => return Err(e),
+  }
+
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Print character statements must end with a semicolon ';'")),
+  }
+
+  return Ok(code);
+}
+
+
+// readc(c); or readc(arr[i]); stores the next byte of input, -1 at end of input
+pub fn parse_read_char_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+
+  let mut code = Block::new();
+
+  match tokens[*index] {
+    Token::ReadChar => *index += 1,
+    _ => return Err(String::from("Parser: Read character statements must begin with the 'readc' keyword")),
+  }
+
+  let parenthesized = tokens[*index] == Token::LeftParen;
+  if parenthesized {
+    *index += 1;
+  }
+
+  let ident = match &tokens[*index] {
+    Token::Ident(ident) => ident.clone(),
+    _ => return Err(String::from("Parser: Read character statements expect a variable or array element")),
+  };
+  *index += 1;
+
+  if !find_symbol(ctx, &ident) {
+    return Err(format!("Semantic Analysis: Variable {ident} not declared before use in readc"));
+  }
+
+  if tokens[*index] == Token::LeftBracket {
+    *index += 1;
+
+    let symbol_type = find_symbol_type(ctx, &ident);
+    if !matches!(symbol_type, SymbolType::Array(_)) {
+      return Err(format!("Semantic Analysis: {ident} is not array"));
+    }
+
+    let element = parse_expression(ctx, tokens, index)?;
+    check_type(&ValueType::Int, &element, "array index")?;
+
+    match tokens[*index] {
+      Token::RightBracket => *index += 1,
+      _ => return Err(String::from("Parser: Array access expects closing bracket ']'")),
+    }
+
+    // the byte goes through a temp since %inputc only writes plain variables
+    let t = create_temp(ctx);
+    code.append(element.code);
+    code.declare(symbol_value_type(&symbol_type), &t);
+    code.input_char(&t);
+    code.store(&ident, element.name, Operand::Var(t));
+  }
+
+  else {
+    if !matches!(find_symbol_type(ctx, &ident), SymbolType::Variable(_)) {
+      return Err(format!("Semantic Analysis: {ident} is not variable"));
+    }
+    code.input_char(&ident);
+  }
+
+  if parenthesized {
+    match tokens[*index] {
+      Token::RightParen => *index += 1,
+      _ => return Err(String::from("Parser: Read character statements expect closing parenthesis ')'")),
     }
+  }
+
+  match tokens[*index] {
+    Token::Semicolon => *index += 1,
+    _ => return Err(String::from("Parser: Read character statements must end with a semicolon ';'")),
+  }
+
+  return Ok(code);
+}
+
+
+pub fn parse_break_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+
+   if !ctx.in_loop {
+       return Err(String::from("Semantic Analysis: break statement not within a loop"));
+   }
 
   match tokens[*index] {
     Token::Break => *index += 1,
@@ -668,21 +1127,19 @@ pub fn parse_break_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
 
 
 
-  unsafe {
-        // Check if CURRENT_END_LABEL is empty
-        if CURRENT_END_LABEL.is_empty() {
-            return Err(String::from("Error: CURRENT_END_LABEL is empty."));
-        }
-        return Ok(format!("%jmp {CURRENT_END_LABEL}\n"));
+  // Check if current_end_label is empty
+  let mut code = Block::new();
+  match &ctx.current_end_label {
+      Some(label) => code.jump(label),
+      None => return Err(String::from("Error: current_end_label is empty.")),
   }
+  return Ok(code);
 }
 
 
-pub fn parse_continue_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-  unsafe {
-        if !in_loop {
-            return Err(String::from("Error: continue statement not within a loop"));
-        }
+pub fn parse_continue_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
+  if !ctx.in_loop {
+      return Err(String::from("Semantic Analysis: continue statement not within a loop"));
   }
 
   match tokens[*index] {
@@ -695,51 +1152,52 @@ pub fn parse_continue_statement(tokens: &Vec<Token>, index: &mut usize) -> Resul
     _ => return Err(String::from("Parser: Continue statements must end with a semicolon ';'")),
   }
 
-  unsafe {
-    // Check if CURRENT_END_LABEL is empty
-    if CURRENT_END_LABEL.is_empty() {
-        return Err(String::from("Error: CURRENT_END_LABEL is empty."));
-    }
-    return Ok(format!("%jmp {CURRENT_END_LABEL}\n"));
+  // Check if current_start_label is empty
+  let mut code = Block::new();
+  match &ctx.current_start_label {
+      Some(label) => code.jump(label),
+      None => return Err(String::from("Error: current_start_label is empty.")),
   }
+  return Ok(code);
 }
 
 
-pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+pub fn parse_while_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
-  let mut code = String::new(); 
-  let mut condition = String::new();
+  let mut code = Block::new(); 
+  let condition: Operand;
 
-  let end_label = create_label();
-  let start_label = create_label();
+  let end_label = create_label(ctx);
+  let start_label = create_label(ctx);
 
-  let mut hold_end_label = String::new();
+  // the loop body may never run, so it cannot make the function return
+  let returned_before = ctx.always_returns;
 
 
-  unsafe {
-    in_loop = true; // Set to true when entering a loop
-    hold_end_label = CURRENT_END_LABEL.clone();
-    CURRENT_END_LABEL = end_label.clone(); 
-  }
+  ctx.in_loop = true; // Set to true when entering a loop
+  let hold_end_label: Option<Label> = ctx.current_end_label.clone();
+  ctx.current_end_label = Some(end_label.clone()); 
+  let hold_start_label: Option<Label> = ctx.current_start_label.clone();
+  ctx.current_start_label = Some(start_label.clone()); 
 
   match tokens[*index] {
     Token::While => *index += 1,
     _ => return Err(String::from("Parser: Expected 'while' keyword")),
   }
 
-  code += &format!("{start_label}\n");
+  code.label(&start_label);
 
-  match parse_bool(tokens, index) {
+  match parse_bool(ctx, tokens, index) {
     Ok(expression) => {
 
-      code += &expression.code; //creates code to make first temp in the expression
+      code.append(expression.code); //creates code to make first temp in the expression
       condition = expression.name; //holds the temp for the boolean TAC 
 
     },
     Err(e) => return Err(e),
   }
 
-  code += &format!("%branch_ifn {condition}, {end_label}\n");
+  code.branch_ifn(condition, &end_label);
 
   match tokens[*index] {
     Token::LeftCurly => *index += 1,
@@ -747,8 +1205,8 @@ pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
   }
 
   while !matches!(tokens[*index], Token::RightCurly) {
-    match parse_statement(tokens, index) {
-      Ok(statement) => {code += &statement;},
+    match parse_statement(ctx, tokens, index) {
+      Ok(statement) => {code.append(statement);},
       Err(e) => return Err(e),
     }
   }
@@ -759,14 +1217,15 @@ pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
     _ => return Err(String::from("Parser: While statement expects '}'")),
   }
 
-  code += &format!("{end_label}\n");
+  code.jump(&start_label);
+  code.label(&end_label);
 
-  unsafe {
-    if hold_end_label.is_empty(){
-      in_loop = false; // Set to false when exiting the loop
-    }
-    CURRENT_END_LABEL = hold_end_label; 
+  if hold_end_label.is_none(){
+    ctx.in_loop = false; // Set to false when exiting the loop
   }
+  ctx.current_end_label = hold_end_label; 
+  ctx.current_start_label = hold_start_label; 
+  ctx.always_returns = returned_before;
 
   return Ok(code);
 }
@@ -774,14 +1233,14 @@ pub fn parse_while_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<S
 
   
   
-pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
+pub fn parse_if_statement(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Block, String> {
 
 
-  let mut code = String::new(); 
-  let mut condition = String::new();
+  let mut code = Block::new(); 
+  let condition: Operand;
 
-  let end_label = create_label();
-  let false_label = create_label();
+  let end_label = create_label(ctx);
+  let false_label = create_label(ctx);
 
 
   
@@ -792,19 +1251,23 @@ pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
     _ => return Err(String::from("Parser: If statement expects 'if' keyword")),
   }
 
-  match parse_bool(tokens, index) {
+  match parse_bool(ctx, tokens, index) {
     Ok(expression) => {
 
-      code += &expression.code; //creates code to make first temp in the expression
+      code.append(expression.code); //creates code to make first temp in the expression
       condition = expression.name; //holds the temp for the boolean TAC 
 
     },
     Err(e) => {return Err(e);}
   }
 
-  code += &format!("%branch_ifn {condition}, {false_label}\n");
+  code.branch_ifn(condition, &false_label);
 
-  
+  // the if only guarantees a return when both branches do
+  let returned_before = ctx.always_returns;
+  let mut else_returns = false;
+
+  ctx.always_returns = false;
 
   match tokens[*index] {
     Token::LeftCurly => *index += 1,
@@ -812,8 +1275,8 @@ pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
   }
 
   while !matches!(tokens[*index], Token::RightCurly) {
-    match parse_statement(tokens, index) {
-      Ok(statement) => {code += &statement;},
+    match parse_statement(ctx, tokens, index) {
+      Ok(statement) => {code.append(statement);},
       Err(e) => return Err(e),
     }
   }
@@ -823,8 +1286,13 @@ pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
     _ => return Err(String::from("Parser: If statement expects '}'")),
   }
 
+  let then_returns: bool = ctx.always_returns;
+  ctx.always_returns = false;
+
 
-  code += &format!("{false_label}\n");
+  // skip over the else block once the then block is done
+  code.jump(&end_label);
+  code.label(&false_label);
 
   if *index < tokens.len() && matches!(tokens[*index], Token::Else) {
     *index += 1;
@@ -835,8 +1303,8 @@ pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
     }
 
     while !matches!(tokens[*index], Token::RightCurly) {
-      match parse_statement(tokens, index) {
-        Ok(statement) => {code += &statement;},
+      match parse_statement(ctx, tokens, index) {
+        Ok(statement) => {code.append(statement);},
         Err(e) => return Err(e),
       }
     }
@@ -845,93 +1313,78 @@ pub fn parse_if_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Stri
       Token::RightCurly => *index += 1,
       _ => return Err(String::from("Parser: Else statement expects '}' after else block")),
     }
+
+    else_returns = ctx.always_returns;
   }
 
-  code += &format!("{end_label}\n");
+  code.label(&end_label);
+
+  ctx.always_returns = returned_before || (then_returns && else_returns);
 
 
   return Ok(code);
 }
 
   
-pub fn parse_bool(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+pub fn parse_bool(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
 
   
-  let mut varHold = String::new();
-  let mut statementHold = String::new();
+  let varHold: Operand;
+  let left: Expression;
 
   let mut expr = Expression {
-    code: String::from(""),
-    name: String::from(""),
+    code: Block::new(),
+    name: Operand::Num(0),
+    typ: ValueType::Int,
   };
   
   
 
 
-  match parse_expression(tokens, index) {
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
 
-      expr.code += &expression.code; //creates code to make first temp in the expression
-      varHold = expression.name; //holds the temp for the boolean TAC 
+      expr.code.append(expression.code.clone()); //creates code to make first temp in the expression
+      varHold = expression.name.clone(); //holds the temp for the boolean TAC 
+      left = expression;
 
     },
     Err(e) => {return Err(e);}
   }
 
-  let t = create_temp(); //the destination of the generated code for boolean 
+  let t = create_temp(ctx); //the destination of the generated code for boolean 
 
-  expr.code += &format!("%int {t}\n");
+  expr.code.declare(ValueType::Int, &t);
 
-  match tokens[*index] {
-    Token::Less => {
-        *index += 1;
-        expr.code += "%lt ";
-    }
-    Token::LessEqual => {
-        *index += 1;
-        expr.code += "%le ";
-    }
-    Token::Greater => {
-        *index += 1;
-        expr.code += "%gt ";
-    }
-    Token::GreaterEqual => {
-        *index += 1;
-        expr.code += "%ge ";
-    }
-    Token::Equality => {
-        *index += 1;
-        expr.code += "%eq ";
-    }
-    Token::NotEqual => {
-        *index += 1;
-        expr.code += "%neq ";
-    }
+  let opcode = match tokens[*index] {
+    Token::Less => BinaryOp::LessThan,
+    Token::LessEqual => BinaryOp::LessEqual,
+    Token::Greater => BinaryOp::GreaterThan,
+    Token::GreaterEqual => BinaryOp::GreaterEqual,
+    Token::Equality => BinaryOp::Equal,
+    Token::NotEqual => BinaryOp::NotEqual,
     _ => return Err(String::from("Parser: Boolean expression expects a comparison operator")),
-  }
+  };
+  *index += 1;
 
   
 
 
-  statementHold += &format!("{t}, {varHold}, "); //still have to generate the code for the other part before we use this 
-
-
-  match parse_expression(tokens, index) {
+  // the right hand side's code has to come before the comparison itself
+  match parse_expression(ctx, tokens, index) {
     Ok(expression) => {
 
-      expr.code += &expression.code; //creates code to make first temp in the expression
-      varHold = expression.name; //holds the temp for the boolean TAC 
+      // both sides have to be the same width, the result is always an int
+      unify_types(&left, &expression, "comparison")?;
+
+      expr.code.append(expression.code); //creates code to make first temp in the expression
+      expr.code.binary(opcode, &t, varHold, expression.name);
 
     },
     Err(e) => {return Err(e);}
   }
 
-  statementHold += &varHold; 
-
-  expr.code += &statementHold;
-  expr.code += "\n";
-
-  expr.name = t;
+  expr.name = Operand::Var(t);
 
 
 
@@ -943,32 +1396,36 @@ pub fn parse_bool(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
   
 
 
+#[derive(Clone)]
 struct Expression{
-  code: String, //the code that creates temp/call/index to use in the expression 
-  name: String, //the part that is used in TAC instructions 
+  code: Block, //the code that creates temp/call/index to use in the expression 
+  name: Operand, //the part that is used in TAC instructions 
+  typ: ValueType, //int or long, decides how temps holding this value are declared 
 }
 
 // Parsing complex expressions such as: "a + b - (c * d) / (f + g - 8);
-pub fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+pub fn parse_expression(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
 
-  let mut expr = parse_multiply_expression(tokens, index)?;
+  let mut expr = parse_multiply_expression(ctx, tokens, index)?;
 
   loop {
 
     let opcode = match peek_error(tokens, index)?{
-      Token::Plus => "%add",
-      Token::Subtract => "%sub",
+      Token::Plus => BinaryOp::Add,
+      Token::Subtract => BinaryOp::Sub,
       _ => { break;}
     };
 
     *index += 1;
 
-    let m_expr = parse_multiply_expression(tokens, index)?;
-    let t = create_temp();
-    let instr = format!("%int {}\n{opcode} {}, {}, {}\n", t, t, expr.name, m_expr.name);
-    expr.code += &m_expr.code;
-    expr.code += &instr;
-    expr.name = t; 
+    let m_expr = parse_multiply_expression(ctx, tokens, index)?;
+    let typ = unify_types(&expr, &m_expr, "arithmetic")?;
+    let t = create_temp(ctx);
+    expr.code.append(m_expr.code);
+    expr.code.declare(typ, &t);
+    expr.code.binary(opcode, &t, expr.name, m_expr.name);
+    expr.name = Operand::Var(t); 
+    expr.typ = typ;
 
   }
 
@@ -977,26 +1434,28 @@ pub fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expres
 }
 
   
-pub fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+pub fn parse_multiply_expression(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
   
-  let mut expr = parse_term(tokens, index)?;
+  let mut expr = parse_term(ctx, tokens, index)?;
   loop { 
     let opcode = match peek_error(tokens, index)?{ 
-      Token::Multiply => "%mult",
-      Token::Divide => "%div",
-      Token::Modulus => "%mod",
+      Token::Multiply => BinaryOp::Mult,
+      Token::Divide => BinaryOp::Div,
+      Token::Modulus => BinaryOp::Mod,
       _ => {break;}
 
     };
 
 
     *index += 1;
-    let node = parse_term(tokens, index)?;
-    expr.code += &node.code;
-    let t = create_temp();
-    let instr = format!("%int {}\n{opcode} {}, {}, {}\n", t, t, expr.name, node.name);
-    expr.code += &instr;
-    expr.name = t; 
+    let node = parse_term(ctx, tokens, index)?;
+    let typ = unify_types(&expr, &node, "arithmetic")?;
+    expr.code.append(node.code);
+    let t = create_temp(ctx);
+    expr.code.declare(typ, &t);
+    expr.code.binary(opcode, &t, expr.name, node.name);
+    expr.name = Operand::Var(t); 
+    expr.typ = typ;
   }
 
   return Ok(expr);
@@ -1004,7 +1463,77 @@ pub fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Resu
 
   
   
-pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+// parse the argument list of a call whose identifier has already been consumed
+// %int temp
+// %call temp, funcname(variables)
+fn parse_call_arguments(ctx: &mut ParseContext, ident: &String, tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
+
+  let mut expr = Expression {
+    code: Block::new(),
+    name: Operand::Num(0),
+    typ: ValueType::Int,
+  };
+
+  match tokens[*index] {
+    Token::LeftParen => *index += 1,
+    _ => return Err(String::from("Parser: Function call expects opening parenthesis")),
+  }
+
+  let t = create_temp(ctx);
+  let mut arguments: Vec<Operand> = vec![];
+
+  let parameters = find_function_parameters(ctx, ident);
+  let mut argument_count = 0;
+
+
+  // Parse the function's arguments
+  while !matches!(tokens[*index], Token::RightParen) {
+    
+
+    match parse_expression(ctx, tokens, index) {
+      Ok(expression) => {
+        if let Some(parameter) = parameters.get(argument_count) {
+          check_type(parameter, &expression, &format!("argument {} of call to {ident}", argument_count + 1))?;
+        }
+        argument_count += 1;
+        expr.code.append(expression.code);
+        arguments.push(expression.name);
+      },
+
+      Err(e) => return Err(e),
+    }
+
+    // If there's a comma, continue parsing more arguments
+    if tokens[*index] == Token::Comma {
+      *index += 1; // Skip the comma
+    } else {
+      break; // End function argument parsing
+    }
+  }
+
+  // Check for closing parenthesis
+  match tokens[*index] {
+    Token::RightParen => *index += 1,
+    _ => return Err(String::from("Parser: Function call expects closing parenthesis")),
+  }
+
+  if argument_count != parameters.len() {
+    return Err(format!("Semantic Analysis: Function {ident} expects {} arguments but was called with {argument_count}", parameters.len()));
+  }
+
+  if find_function_return_type(ctx, ident) == Some(ReturnType::Long) {
+    expr.typ = ValueType::Long;
+  }
+
+  expr.code.declare(expr.typ, &t);
+  expr.code.call(&t, ident, arguments);
+  expr.name = Operand::Var(t);
+
+  return Ok(expr);
+}
+
+
+pub fn parse_term(ctx: &mut ParseContext, tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
 
   match &tokens[*index] {
     
@@ -1015,16 +1544,24 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
 
       *index += 1;
 
-      unsafe { 
-        if !find_symbol(ident){
+      // function names live in the global scope rather than the current one
+      if tokens[*index] == Token::LeftParen {
+        if find_function_return_type(ctx, ident).is_none() {
+          return Err(format!("Semantic Analysis: Function {ident} not declared before call"));
+        }
+      }
+
+      else { 
+        if !find_symbol(ctx, ident){
           return Err(format!("Semantic Analysis: Variable {ident} not declared before use as term"));
         }
       }
 
 
       let mut expr = Expression {
-        code: String::from(""),
-        name: String::from(""),
+        code: Block::new(),
+        name: Operand::Num(0),
+        typ: ValueType::Int,
       };
 
       // Handle function call: ident(...)
@@ -1035,52 +1572,12 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
           // %mov c, t 
 
       if tokens[*index] == Token::LeftParen {
-        *index += 1;
-        
-        //%call temp, funcname(variables)
-          //generate the code for the variables, append the name to the call string 
-        
-        let t = create_temp();
-        let mut callCode: String = String::new();
-        callCode += &format!("%call {t}, {ident}");
-        callCode += "(";
-
-
-        // Parse the function's arguments
-        while !matches!(tokens[*index], Token::RightParen) {
-          
-
-          match parse_expression(tokens, index) {
-            Ok(expression) => {
-              expr.code += &expression.code;
-              callCode += &expression.name;
-            },
 
-            Err(e) => return Err(e),
-          }
-
-          // If there's a comma, continue parsing more arguments
-          if tokens[*index] == Token::Comma {
-            *index += 1; // Skip the comma
-            callCode += ",";
-          } else {
-            break; // End function argument parsing
-          }
-        }
-
-        // Check for closing parenthesis
-        match tokens[*index] {
-          Token::RightParen => *index += 1,
-          _ => return Err(String::from("Parser: Function call expects closing parenthesis")),
+        if find_function_return_type(ctx, ident) == Some(ReturnType::Void) {
+          return Err(format!("Semantic Analysis: Void function {ident} cannot be used in an expression"));
         }
 
-        callCode += ")\n";
-
-        expr.code += &format!("%int {}\n", t);
-        expr.code += &callCode;
-        expr.name += &t;
-
-        return Ok(expr);
+        return parse_call_arguments(ctx, ident, tokens, index);
         
       }
 
@@ -1089,13 +1586,13 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
       // Handle array access: ident[expression]
       else if tokens[*index] == Token::LeftBracket {
         *index += 1;
-        expr.name += &format!("[{ident} + ");
         
 
-        match parse_expression(tokens, index) {
+        match parse_expression(ctx, tokens, index) {
           Ok(expression) => {
-            expr.code += &expression.code;
-            expr.name += &expression.name;
+            check_type(&ValueType::Int, &expression, "array index")?;
+            expr.code.append(expression.code);
+            expr.name = expression.name;
           },
           Err(e) => return Err(e),
         }
@@ -1105,15 +1602,14 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
           _ => return Err(String::from("Parser: Array access expects closing bracket ']'")),
         }
 
-        expr.name += "]";
-
-        let statement = expr.name;
+        let element = expr.name;
+        expr.typ = symbol_value_type(&find_symbol_type(ctx, ident));
 
         //fix print/return/etc array[0] error 
-        let t = create_temp();
-        expr.code += &format!("%int {t}\n");
-        expr.code += &format!("%mov {t}, {statement}\n");
-        expr.name = t; 
+        let t = create_temp(ctx);
+        expr.code.declare(expr.typ, &t);
+        expr.code.load(&t, ident, element);
+        expr.name = Operand::Var(t); 
 
 
         return Ok(expr);
@@ -1124,13 +1620,15 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
       //if just a variable 
       else{
 
-        if find_symbol_type(ident) != SymbolType::Variable {
+        let symbol_type = find_symbol_type(ctx, ident);
+        if !matches!(symbol_type, SymbolType::Variable(_)) {
           return Err(format!("Semantic Analysis: {ident} is not variable"));
         }
 
         let expr = Expression {
-          code: String::from(""),
-          name: ident.clone(),
+          code: Block::new(),
+          name: Operand::Var(ident.clone()),
+          typ: symbol_value_type(&symbol_type),
         };
         return Ok(expr);
       }
@@ -1148,32 +1646,79 @@ pub fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression,
       let expr = Expression {
         code: String::from(""),
         name: ident.clone(),
+        typ: ValueType::Int,
       };
       return Ok(expr);
     }
     */
 
+    // Strings have no integer value
+    Token::Str(_) => Err(String::from("Semantic Analysis: String literals can only be used as print arguments")),
+
     // Handle numbers directly
     Token::Num(num) => {
       *index += 1;
       let expr = Expression{
-        code : String::from(""),
-        name: format!("{num}"),
+        code : Block::new(),
+        name: Operand::Num(*num as i64),
+        typ: ValueType::Int,
       };
       return Ok(expr);
     }
 
+    Token::LongNum(num) => {
+      *index += 1;
+      let expr = Expression{
+        code : Block::new(),
+        name: Operand::Num(*num),
+        typ: ValueType::Long,
+      };
+      return Ok(expr);
+    }
+
+
+    // Handle casts: int(expression) or long(expression)
+    //   %long t
+    //   %mov t, expression
+    Token::Int | Token::Long => {
+      let typ = if tokens[*index] == Token::Long { ValueType::Long } else { ValueType::Int };
+      *index += 1;
+
+      match tokens[*index] {
+        Token::LeftParen => *index += 1,
+        _ => return Err(format!("Parser: Cast to {} expects opening parenthesis '('", type_name(&typ))),
+      }
+
+      let inner = parse_expression(ctx, tokens, index)?;
+
+      match tokens[*index] {
+        Token::RightParen => *index += 1,
+        _ => return Err(format!("Parser: Cast to {} expects closing parenthesis ')'", type_name(&typ))),
+      }
+
+      let t = create_temp(ctx);
+      let mut expr = Expression {
+        code: inner.code,
+        name: Operand::Var(t.clone()),
+        typ,
+      };
+      expr.code.declare(expr.typ, &t);
+      expr.code.mov(&t, inner.name);
+      return Ok(expr);
+    }
+
 
     // Handle parenthesized expressions: (expression)
     Token::LeftParen => {
       *index += 1;
 
       let mut expr = Expression {
-        code: String::from(""),
-        name: String::from(""),
+        code: Block::new(),
+        name: Operand::Num(0),
+        typ: ValueType::Int,
       };
 
-      match parse_expression(tokens, index) {
+      match parse_expression(ctx, tokens, index) {
         Ok(expression) => {expr = expression;},
         Err(e) => return Err(e),
       }
@@ -0,0 +1,241 @@
+// src/profiler.rs
+// Counts the instructions a program runs: by kind of bytecode, by function,
+// by IR line and by source line. A function's exclusive count is the
+// instructions run in it, and its inclusive count adds the functions it
+// called. The counts per call stack can be written in the folded format
+// flamegraph tools read:
+//
+// main;fib;fib 1200
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::interpreter::{Control, Hook, State};
+
+// the busiest lines shown in the report, the rest are summed up.
+const TOP_LINES: usize = 20;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionProfile {
+    pub calls: u64,
+    pub exclusive: u64,
+    pub inclusive: u64,
+}
+
+// a call that has not returned yet.
+struct Call {
+    function: String,
+    // the instruction count when it started, and its call stack.
+    start: u64,
+    stack: usize,
+}
+
+pub struct Profiler {
+    total: u64,
+    kinds: HashMap<&'static str, u64>,
+    functions: HashMap<String, FunctionProfile>,
+    // IR line -> function and count.
+    ir_lines: HashMap<usize, (String, u64)>,
+    source_lines: HashMap<usize, u64>,
+    calls: Vec<Call>,
+    // every call stack seen, as (parent, function), and the instructions run with it on top.
+    stacks: Vec<(Option<usize>, String)>,
+    stack_ids: HashMap<(Option<usize>, String), usize>,
+    stack_counts: Vec<u64>,
+}
+
+// a table row sorted by count, biggest first, then by name.
+fn sorted<K: Clone + Ord>(counts: impl Iterator<Item = (K, u64)>) -> Vec<(K, u64)> {
+    let mut rows: Vec<(K, u64)> = counts.collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    rows
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { 100.0 * count as f64 / total as f64 }
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            total: 0,
+            kinds: HashMap::new(),
+            functions: HashMap::new(),
+            ir_lines: HashMap::new(),
+            source_lines: HashMap::new(),
+            calls: vec![],
+            stacks: vec![],
+            stack_ids: HashMap::new(),
+            stack_counts: vec![],
+        }
+    }
+
+    // how many instructions ran in total.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn kind(&self, kind: &str) -> u64 {
+        self.kinds.get(kind).copied().unwrap_or(0)
+    }
+
+    pub fn ir_line(&self, line: usize) -> u64 {
+        self.ir_lines.get(&line).map_or(0, |(_, count)| *count)
+    }
+
+    pub fn source_line(&self, line: usize) -> u64 {
+        self.source_lines.get(&line).copied().unwrap_or(0)
+    }
+
+    pub fn function(&self, name: &str) -> Option<FunctionProfile> {
+        let mut profile = self.functions.get(name)?.clone();
+        // calls still running when the program stopped have not been added yet.
+        if let Some(call) = self.calls.iter().find(|call| call.function == name) {
+            profile.inclusive += self.total - call.start;
+        }
+        Some(profile)
+    }
+
+    fn enter(&mut self, function: &str) {
+        let parent = self.calls.last().map(|call| call.stack);
+        let key = (parent, String::from(function));
+        let stack = match self.stack_ids.get(&key) {
+        Some(id) => *id,
+        None => {
+            let id = self.stacks.len();
+            self.stacks.push(key.clone());
+            self.stack_ids.insert(key, id);
+            self.stack_counts.push(0);
+            id
+        }
+        };
+
+        self.functions.entry(String::from(function)).or_default().calls += 1;
+        self.calls.push(Call { function: String::from(function), start: self.total, stack });
+    }
+
+    fn leave(&mut self) {
+        let call = self.calls.pop().unwrap();
+        // with recursion only the outermost call counts, so nothing is counted twice.
+        if !self.calls.iter().any(|c| c.function == call.function) {
+            self.functions.get_mut(&call.function).unwrap().inclusive += self.total - call.start;
+        }
+    }
+
+    // a call stack as main;f;g.
+    fn stack_name(&self, mut id: usize) -> String {
+        let mut names: Vec<&str> = vec![];
+        loop {
+            let (parent, function) = &self.stacks[id];
+            names.push(function);
+            match parent {
+            Some(parent) => id = *parent,
+            None => break,
+            }
+        }
+        names.reverse();
+        names.join(";")
+    }
+
+    // writes the instruction count of every call stack, for flamegraph.pl and similar tools.
+    pub fn write_folded(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        let mut lines: Vec<(String, u64)> = self.stack_counts.iter().enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(id, count)| (self.stack_name(id), *count))
+            .collect();
+        lines.sort();
+        for (stack, count) in lines {
+            writeln!(output, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+
+    // writes the tables of counts, busiest first.
+    pub fn write_report(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(output, "Instructions run: {}", self.total)?;
+
+        writeln!(output)?;
+        writeln!(output, "{:<20} {:>10} {:>12} {:>8} {:>12} {:>8}", "Function", "Calls", "Exclusive", "%", "Inclusive", "%")?;
+        let mut functions: Vec<(String, FunctionProfile)> = self.functions.keys().map(|name| (name.clone(), self.function(name).unwrap())).collect();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then(a.0.cmp(&b.0)));
+        for (name, profile) in &functions {
+            writeln!(output, "{:<20} {:>10} {:>12} {:>7.1}% {:>12} {:>7.1}%", name, profile.calls,
+                profile.exclusive, percent(profile.exclusive, self.total),
+                profile.inclusive, percent(profile.inclusive, self.total))?;
+        }
+
+        writeln!(output)?;
+        writeln!(output, "{:<20} {:>12} {:>8}", "Instruction", "Count", "%")?;
+        for (kind, count) in sorted(self.kinds.iter().map(|(kind, count)| (*kind, *count))) {
+            writeln!(output, "{:<20} {:>12} {:>7.1}%", kind, count, percent(count, self.total))?;
+        }
+
+        if !self.source_lines.is_empty() {
+            writeln!(output)?;
+            writeln!(output, "{:<20} {:>12} {:>8}", "Source line", "Count", "%")?;
+            let lines = sorted(self.source_lines.iter().map(|(line, count)| (*line, *count)));
+            self.write_lines(output, lines.iter().map(|(line, count)| (line.to_string(), *count)).collect())?;
+        }
+
+        writeln!(output)?;
+        writeln!(output, "{:<20} {:>12} {:>8}", "IR line", "Count", "%")?;
+        let lines = sorted(self.ir_lines.iter().map(|(line, (_, count))| (*line, *count)));
+        self.write_lines(output, lines.iter().map(|(line, count)| (format!("{} ({})", line, self.ir_lines[line].0), *count)).collect())?;
+        Ok(())
+    }
+
+    fn write_lines(&self, output: &mut dyn Write, lines: Vec<(String, u64)>) -> std::io::Result<()> {
+        for (line, count) in lines.iter().take(TOP_LINES) {
+            writeln!(output, "{:<20} {:>12} {:>7.1}%", line, count, percent(*count, self.total))?;
+        }
+        if lines.len() > TOP_LINES {
+            let rest: u64 = lines[TOP_LINES..].iter().map(|(_, count)| count).sum();
+            writeln!(output, "{:<20} {:>12} {:>7.1}%", format!("{} more lines", lines.len() - TOP_LINES), rest, percent(rest, self.total))?;
+        }
+        Ok(())
+    }
+}
+
+impl Hook for Profiler {
+    fn before(&mut self, state: &State, _input: &mut dyn BufRead, _output: &mut dyn Write) -> Control {
+        let frame = state.frame(0).unwrap();
+
+        // calls and returns change the depth by one at a time.
+        while self.calls.len() > state.depth() {
+            self.leave();
+        }
+        if self.calls.len() < state.depth() {
+            self.enter(frame.function());
+        }
+
+        self.total += 1;
+        *self.kinds.entry(frame.kind()).or_insert(0) += 1;
+        self.functions.get_mut(frame.function()).unwrap().exclusive += 1;
+        self.stack_counts[self.calls.last().unwrap().stack] += 1;
+
+        let entry = frame.entry();
+        if let Some(line) = entry.line {
+            self.ir_lines.entry(line).or_insert_with(|| (entry.function.clone(), 0)).1 += 1;
+        }
+        if let Some(span) = entry.span {
+            *self.source_lines.entry(span.line).or_insert(0) += 1;
+        }
+        Control::Continue
+    }
+
+    fn after(&mut self, state: &State) {
+        // main has returned.
+        if state.depth() == 0 {
+            while !self.calls.is_empty() {
+                self.leave();
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+// src/repl.rs
+// An interactive prompt for trying out snippets. Function definitions are
+// kept, declarations become variables of main that keep their values between
+// inputs, statements run straight away, and a bare expression is printed:
+//
+// >>> int x;
+// >>> x = 6 * 7;
+// >>> x + 1
+// 43
+//
+// Every input compiles the whole program again: the functions, then a main
+// that declares the variables, puts back their values and runs the new input.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::interpreter::{Control, Hook, Program, State, Vm};
+use crate::phases::ir::Instruction;
+use crate::phases::lexer::{lex, lex_with_spans, Token};
+use crate::phases::semantic_parser::parse_program_with_spans;
+
+const HELP: &str = "\
+Type a function definition, a declaration, a statement or an expression.
+Input with an unclosed '{' carries on over the next lines.
+    :vars     show the variables and their values
+    :reset    forget all functions and variables
+    :quit     leave (or end the input)
+";
+
+// a variable declared at the prompt.
+#[derive(Debug, Clone, PartialEq)]
+struct Variable {
+    name: String,
+    long: bool,
+    // the length for arrays.
+    length: Option<i64>,
+}
+
+pub struct Repl {
+    // (name, source) of each function, in the order they were first defined.
+    functions: Vec<(String, String)>,
+    variables: Vec<Variable>,
+    values: HashMap<String, Vec<i64>>,
+}
+
+// picks up the values of main's variables as it finishes.
+struct Snapshot<'v> {
+    variables: &'v [Variable],
+    values: HashMap<String, Vec<i64>>,
+}
+
+impl<'v> Hook for Snapshot<'v> {
+    fn before(&mut self, state: &State, _input: &mut dyn BufRead, _output: &mut dyn Write) -> Control {
+        if state.depth() != 1 {
+            return Control::Continue;
+        }
+        let frame = state.frame(0).unwrap();
+        if !matches!(frame.instruction(), None | Some(Instruction::Return(_))) {
+            return Control::Continue;
+        }
+        for variable in self.variables {
+            let values = match variable.length {
+            Some(_) => frame.array(&variable.name).map(|values| values.to_vec()),
+            None => frame.variable(&variable.name).map(|value| vec![value]),
+            };
+            if let Some(values) = values {
+                self.values.insert(variable.name.clone(), values);
+            }
+        }
+        Control::Continue
+    }
+}
+
+// a literal for the value, written so the lexer reads it back with the right type.
+fn literal(value: i64, long: bool) -> String {
+    let suffix = if long { "L" } else { "" };
+    if value >= 0 {
+        format!("{}{}", value, suffix)
+    } else if value == i64::MIN || (!long && value == i32::MIN as i64) {
+        // the positive half of MIN does not fit.
+        format!("(0{s} - {}{s} - 1{s})", -(value + 1), s = suffix)
+    } else {
+        format!("(0{s} - {}{s})", -value, s = suffix)
+    }
+}
+
+// true once every '{' has its '}', so the input can be run.
+pub fn is_complete(text: &str) -> bool {
+    match lex(text) {
+    Ok(tokens) => {
+        let open = tokens.iter().filter(|t| **t == Token::LeftCurly).count();
+        let close = tokens.iter().filter(|t| **t == Token::RightCurly).count();
+        open <= close
+    }
+    // e.g. an unterminated string, which is an error however much more is typed.
+    Err(_) => true,
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Repl {
+        Repl::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        Repl { functions: vec![], variables: vec![], values: HashMap::new() }
+    }
+
+    // the program for running one input.
+    fn source(&self, variables: &[Variable], functions: &[(String, String)], statement: &str) -> String {
+        let mut code = String::new();
+        for (_, function) in functions {
+            code += function;
+            code += "\n";
+        }
+        code += "func main() {\n";
+        for variable in variables {
+            let typ = if variable.long { "long" } else { "int" };
+            match variable.length {
+            Some(length) => code += &format!("    {}[{}] {};\n", typ, length, variable.name),
+            None => code += &format!("    {} {};\n", typ, variable.name),
+            }
+        }
+        for variable in variables {
+            let values = match self.values.get(&variable.name) {
+            Some(values) => values,
+            None => continue,
+            };
+            for (i, value) in values.iter().enumerate() {
+                // variables start out as 0 anyway.
+                if *value == 0 {
+                    continue;
+                }
+                match variable.length {
+                Some(_) => code += &format!("    {}[{}] = {};\n", variable.name, i, literal(*value, variable.long)),
+                None => code += &format!("    {} = {};\n", variable.name, literal(*value, variable.long)),
+                }
+            }
+        }
+        code += statement;
+        code += "\n}\n";
+        code
+    }
+
+    // compiles and runs a program, keeping the values its variables ended with.
+    fn run(&mut self, variables: &[Variable], functions: &[(String, String)], statement: &str, input: &mut dyn BufRead, output: &mut dyn Write) -> Result<(), String> {
+        let code = self.source(variables, functions, statement);
+        let (tokens, spans) = lex_with_spans(&code)?;
+        let module = parse_program_with_spans(&tokens, &spans, &mut 0)?;
+        let program = match Program::load(&module) {
+        Ok(program) => program,
+        Err(e) => return Err(e.to_string()),
+        };
+
+        let mut snapshot = Snapshot { variables, values: HashMap::new() };
+        let result = program.run_with_hook(&mut Vm::with_io(&mut *input, &mut *output), &mut snapshot);
+        if let Err(e) = result {
+            // the variables keep the values they had before.
+            return Err(String::from(e.message()));
+        }
+        self.values.extend(snapshot.values);
+        Ok(())
+    }
+
+    // runs one complete input.
+    pub fn eval(&mut self, entry: &str, input: &mut dyn BufRead, output: &mut dyn Write) -> Result<(), String> {
+        let tokens = lex(entry)?;
+        let variables = self.variables.clone();
+        let functions = self.functions.clone();
+        match tokens.as_slice() {
+        [Token::End] => Ok(()),
+
+        [Token::Func, Token::Ident(name), ..] => {
+            if name == "main" {
+                return Err(String::from("main is run by the prompt itself, give the function another name."));
+            }
+            let mut functions = functions;
+            let function = (name.clone(), String::from(entry));
+            match functions.iter().position(|(f, _)| f == name) {
+            Some(i) => functions[i] = function,
+            None => functions.push(function),
+            }
+            // only check that it compiles.
+            let code = self.source(&variables, &functions, "");
+            let (tokens, spans) = lex_with_spans(&code)?;
+            parse_program_with_spans(&tokens, &spans, &mut 0)?;
+            self.functions = functions;
+            Ok(())
+        }
+
+        [typ @ (Token::Int | Token::Long), Token::Ident(name), Token::Semicolon, Token::End] => {
+            self.declare(Variable { name: name.clone(), long: *typ == Token::Long, length: None }, input, output)
+        }
+
+        [typ @ (Token::Int | Token::Long), Token::LeftBracket, Token::Num(length), Token::RightBracket, Token::Ident(name), Token::Semicolon, Token::End] => {
+            self.declare(Variable { name: name.clone(), long: *typ == Token::Long, length: Some(*length as i64) }, input, output)
+        }
+
+        [.., Token::Semicolon, Token::End] | [.., Token::RightCurly, Token::End] => {
+            self.run(&variables, &functions, entry, input, output)
+        }
+
+        // anything else is an expression to show.
+        _ => self.run(&variables, &functions, &format!("print({});", entry), input, output),
+        }
+    }
+
+    fn declare(&mut self, variable: Variable, input: &mut dyn BufRead, output: &mut dyn Write) -> Result<(), String> {
+        let mut variables = self.variables.clone();
+        variables.push(variable);
+        let functions = self.functions.clone();
+        self.run(&variables, &functions, "", input, output)?;
+        self.variables = variables;
+        Ok(())
+    }
+
+    fn show_variables(&self, output: &mut dyn Write) {
+        for variable in &self.variables {
+            let values = self.values.get(&variable.name).cloned().unwrap_or_default();
+            match variable.length {
+            Some(_) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                let _ = writeln!(output, "{} = [{}]", variable.name, values.join(", "));
+            }
+            None => { let _ = writeln!(output, "{} = {}", variable.name, values.first().copied().unwrap_or(0)); }
+            }
+        }
+    }
+
+    // reads inputs until the end of the input or :quit. the program's read
+    // and readc take their input from the same place.
+    pub fn run_prompt(&mut self, input: &mut dyn BufRead, output: &mut dyn Write) {
+        let mut entry = String::new();
+        loop {
+            let prompt = if entry.is_empty() { ">>> " } else { "... " };
+            let _ = write!(output, "{}", prompt);
+            let _ = output.flush();
+
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                let _ = writeln!(output);
+                return;
+            }
+            Ok(_) => {}
+            }
+
+            if entry.is_empty() {
+                match line.trim() {
+                ":quit" | ":q" => return,
+                ":help" => {
+                    let _ = write!(output, "{}", HELP);
+                    continue;
+                }
+                ":vars" => {
+                    self.show_variables(output);
+                    continue;
+                }
+                ":reset" => {
+                    *self = Repl::new();
+                    continue;
+                }
+                _ => {}
+                }
+            }
+
+            entry += &line;
+            if !is_complete(&entry) {
+                continue;
+            }
+            let text = std::mem::take(&mut entry);
+            if let Err(message) = self.eval(text.trim(), input, output) {
+                let _ = writeln!(output, "{}", message);
+            }
+        }
+    }
+}
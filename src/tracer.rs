@@ -0,0 +1,276 @@
+// src/tracer.rs
+// Logs every instruction a program runs, with the values it read and the
+// variable it wrote before and after:
+//
+// fib (IR line 20) at line 7, column 5: %add t5, a, t3 | a = 2, t3 = 1 | t5: 0 -> 3
+//
+// or, as JSON Lines, one object per instruction:
+//
+// {"depth":2,"function":"fib","line":20,"span":{"line":7,"column":5},"instruction":"%add t5, a, t3","reads":{"a":2,"t3":1},"dest":"t5","before":0,"after":3}
+
+use std::fmt;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::interpreter::{Control, FrameView, Hook, State, StackEntry};
+use crate::phases::ir::{Instruction, Operand};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Human,
+    Json,
+}
+
+// an instruction that has started running, written out once it is done.
+struct Event {
+    depth: usize,
+    entry: StackEntry,
+    instruction: String,
+    reads: Vec<(String, i64)>,
+    // what was written to, and its value beforehand.
+    dest: Option<Dest>,
+    before: Option<i64>,
+    after: Option<i64>,
+    // set on returns, where the value lands in the caller.
+    returned: bool,
+}
+
+pub struct Tracer<'a> {
+    pub format: TraceFormat,
+    // only trace the instructions of this function, if set.
+    pub function: Option<String>,
+    output: Box<dyn Write + 'a>,
+    event: Option<Event>,
+    // where each running call's return value goes, the destination of its %call.
+    returns: Vec<String>,
+}
+
+// what an instruction writes: a variable, or an element of an array.
+enum Dest {
+    Variable(String),
+    Element(String, i64),
+}
+
+impl fmt::Display for Dest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+        Dest::Variable(name) => write!(f, "{}", name),
+        Dest::Element(array, index) => write!(f, "{}[{}]", array, index),
+        }
+    }
+}
+
+fn operand_value(frame: &FrameView, operand: &Operand) -> Option<i64> {
+    match operand {
+    Operand::Num(num) => Some(*num),
+    Operand::Var(name) => frame.variable(name),
+    }
+}
+
+fn dest_of(frame: &FrameView, instruction: &Instruction) -> Option<Dest> {
+    match instruction {
+    // the call itself only passes the arguments, the destination is written on return.
+    Instruction::Call(_, _, _) => None,
+    Instruction::Store(array, index, _) => {
+        let index = operand_value(frame, index)?;
+        Some(Dest::Element(array.clone(), index))
+    }
+    _ => instruction.dest().map(|name| Dest::Variable(String::from(name))),
+    }
+}
+
+fn read_dest(frame: &FrameView, dest: &Dest) -> Option<i64> {
+    match dest {
+    Dest::Variable(name) => frame.variable(name),
+    Dest::Element(array, index) => {
+        let array = frame.array(array)?;
+        if *index < 0 { None } else { array.get(*index as usize).copied() }
+    }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+        '"' => escaped += "\\\"",
+        '\\' => escaped += "\\\\",
+        '\n' => escaped += "\\n",
+        c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+        _ => escaped.push(c),
+        }
+    }
+    escaped += "\"";
+    escaped
+}
+
+fn json_number(value: Option<i64>) -> String {
+    match value {
+    Some(num) => num.to_string(),
+    None => String::from("null"),
+    }
+}
+
+impl<'a> Tracer<'a> {
+    pub fn new(format: TraceFormat, output: impl Write + 'a) -> Tracer<'a> {
+        Tracer {
+            format,
+            function: None,
+            output: Box::new(output),
+            event: None,
+            returns: vec![],
+        }
+    }
+
+    fn write_human(&mut self, event: &Event) {
+        let mut line = format!("{:indent$}{}", "", event.entry.function, indent = 2 * (event.depth - 1));
+        if let Some(ir_line) = event.entry.line {
+            line += &format!(" (IR line {})", ir_line);
+        }
+        if let Some(span) = event.entry.span {
+            line += &format!(" at line {}, column {}", span.line, span.column);
+        }
+        line += &format!(": {}", event.instruction);
+        if !event.reads.is_empty() {
+            let reads: Vec<String> = event.reads.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+            line += &format!(" | {}", reads.join(", "));
+        }
+        if let Some(dest) = &event.dest {
+            let before = event.before.map_or(String::from("?"), |v| v.to_string());
+            let after = event.after.map_or(String::from("?"), |v| v.to_string());
+            if event.returned {
+                line += &format!(" | returns to {}: {} -> {}", dest, before, after);
+            } else {
+                line += &format!(" | {}: {} -> {}", dest, before, after);
+            }
+        }
+        let _ = writeln!(self.output, "{}", line);
+    }
+
+    fn write_json(&mut self, event: &Event) {
+        let mut line = format!("{{\"depth\":{},\"function\":{}", event.depth, json_string(&event.entry.function));
+        line += &format!(",\"line\":{}", json_number(event.entry.line.map(|l| l as i64)));
+        match event.entry.span {
+        Some(span) => line += &format!(",\"span\":{{\"line\":{},\"column\":{}}}", span.line, span.column),
+        None => line += ",\"span\":null",
+        }
+        line += &format!(",\"instruction\":{}", json_string(&event.instruction));
+        let reads: Vec<String> = event.reads.iter().map(|(name, value)| format!("{}:{}", json_string(name), value)).collect();
+        line += &format!(",\"reads\":{{{}}}", reads.join(","));
+        match &event.dest {
+        Some(dest) => {
+            line += &format!(",\"dest\":{},\"before\":{},\"after\":{}", json_string(&dest.to_string()), json_number(event.before), json_number(event.after));
+            if event.returned {
+                line += ",\"returned\":true";
+            }
+        }
+        None => line += ",\"dest\":null",
+        }
+        line += "}";
+        let _ = writeln!(self.output, "{}", line);
+    }
+
+    fn write(&mut self, event: Event) {
+        match self.format {
+        TraceFormat::Human => self.write_human(&event),
+        TraceFormat::Json => self.write_json(&event),
+        }
+    }
+}
+
+impl<'a> Hook for Tracer<'a> {
+    fn before(&mut self, state: &State, _input: &mut dyn BufRead, _output: &mut dyn Write) -> Control {
+        let frame = state.frame(0).unwrap();
+        let instruction = frame.instruction();
+
+        // keep track of where returns go, even in functions not being traced.
+        let mut returned_to: Option<String> = None;
+        match instruction {
+        Some(Instruction::Call(dest, _, _)) => self.returns.push(dest.clone()),
+        Some(Instruction::Return(_)) | None => returned_to = self.returns.pop(),
+        _ => {}
+        }
+
+        if let Some(function) = &self.function {
+            if frame.function() != function {
+                return Control::Continue;
+            }
+        }
+
+        let text = match instruction {
+        Some(instruction) => instruction.to_string(),
+        None => String::from("%endfunc"),
+        };
+        let mut event = Event {
+            depth: state.depth(),
+            entry: frame.entry(),
+            instruction: text,
+            reads: vec![],
+            dest: None,
+            before: None,
+            after: None,
+            returned: false,
+        };
+
+        if let Some(instruction) = instruction {
+            for name in instruction.sources() {
+                if event.reads.iter().any(|(read, _)| read == name) {
+                    continue;
+                }
+                if let Some(value) = frame.variable(name) {
+                    event.reads.push((String::from(name), value));
+                }
+            }
+            if let Instruction::Load(_, array, index) = instruction {
+                if let Some(index) = operand_value(&frame, index) {
+                    let element = Dest::Element(array.clone(), index);
+                    if let Some(value) = read_dest(&frame, &element) {
+                        event.reads.push((element.to_string(), value));
+                    }
+                }
+            }
+            if let Some(dest) = dest_of(&frame, instruction) {
+                event.before = read_dest(&frame, &dest);
+                event.dest = Some(dest);
+            }
+        }
+
+        // the value of a return is written to the caller's variable.
+        if let Some(dest) = returned_to {
+            if let Some(caller) = state.frame(1) {
+                event.before = caller.variable(&dest);
+                event.dest = Some(Dest::Variable(dest));
+                event.returned = true;
+            }
+        }
+
+        self.event = Some(event);
+        Control::Continue
+    }
+
+    fn after(&mut self, state: &State) {
+        let mut event = match self.event.take() {
+        Some(event) => event,
+        None => return,
+        };
+
+        if let Some(dest) = &event.dest {
+            // returns land in the caller, which is now the innermost call.
+            let frame = if event.returned { state.frame(0) } else { state.frame(state.depth().wrapping_sub(event.depth)) };
+            if let Some(frame) = frame {
+                event.after = read_dest(&frame, dest);
+            }
+        }
+        self.write(event);
+    }
+}
+
+impl<'a> Drop for Tracer<'a> {
+    fn drop(&mut self) {
+        // an instruction that stopped the program with an error never finished.
+        if let Some(event) = self.event.take() {
+            self.write(event);
+        }
+        let _ = self.output.flush();
+    }
+}
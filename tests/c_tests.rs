@@ -0,0 +1,42 @@
+mod common;
+
+use common::*;
+use rustcompiler::backend::c;
+use rustcompiler::phases::ir::Module;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// compiles the C for the module into an executable.
+fn build(module: &Module, name: &str) -> PathBuf {
+    let executable = executable("c", name);
+    let mut cc = Command::new("cc").args(["-std=c99", "-x", "c", "-", "-o"]).arg(&executable).stdin(Stdio::piped()).spawn().unwrap();
+    cc.stdin.take().unwrap().write_all(c::generate(module).as_bytes()).unwrap();
+    assert!(cc.wait().unwrap().success(), "cc could not compile {name}");
+    executable
+}
+
+#[test]
+fn test_examples() {
+    if !have("cc") {
+        return;
+    }
+    check_examples(build);
+}
+
+#[test]
+fn test_arithmetic_and_calls() {
+    if !have("cc") {
+        return;
+    }
+    check_arithmetic_and_calls(build);
+}
+
+#[test]
+fn test_runtime_errors() {
+    if !have("cc") {
+        return;
+    }
+    check_runtime_errors(build);
+}
@@ -0,0 +1,250 @@
+// Runs the compiler binary itself, for behaviour only visible from the outside.
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+// writes code to a file of its own under the target directory.
+fn source_file(name: &str, code: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let path = dir.join(name);
+    fs::write(&path, code).unwrap();
+    path
+}
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rustcompiler"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn exit_code(args: &[&str], input: &str) -> i32 {
+    run(args, input).status.code().unwrap()
+}
+
+#[test]
+fn test_exit_codes() {
+    // a program that runs exits with main's return value
+    let file = source_file("exit_ok.tt", "func main() { return 0; }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], ""), 0);
+    let file = source_file("exit_value.tt", "func main() { int a; read(a); return a; }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], "42\n"), 42);
+    // one that would be cut down to 8 bits, or taken for an error, exits with 8
+    for value in ["256", "300", "-1", "3", "8"] {
+        let output = run(&[file.to_str().unwrap()], &format!("{value}\n"));
+        assert_eq!(output.status.code(), Some(8), "{value}");
+        let message = String::from_utf8(output.stderr).unwrap();
+        assert!(message.starts_with(&format!("main returned {value}, ")), "{message}");
+    }
+    assert_eq!(exit_code(&[file.to_str().unwrap()], "1\n"), 1);
+    assert_eq!(exit_code(&[file.to_str().unwrap()], "9\n"), 9);
+
+    // errors at each stage have their own code
+    assert_eq!(exit_code(&[], ""), 2);
+    assert_eq!(exit_code(&["--no-such-flag", "x.tt"], ""), 2);
+    assert_eq!(exit_code(&["does_not_exist.tt"], ""), 2);
+
+    let file = source_file("exit_lex.tt", "func main() { int a; a = 1 $ 2; }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], ""), 3);
+    let file = source_file("exit_parse.tt", "func main() { int a }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], ""), 4);
+    let file = source_file("exit_semantic.tt", "func main() { a = 1; }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], ""), 5);
+    let file = source_file("exit_runtime.tt", "func main() { int a; a = 1 / a; }");
+    assert_eq!(exit_code(&[file.to_str().unwrap()], ""), 7);
+}
+
+#[test]
+fn test_debug_input() {
+    // the debugger reads its commands from standard input and the program reads --input
+    let file = source_file("debug_input.tt", "func main() { int a; read(a); print(a * 2); return a; }");
+    let input = source_file("debug_input.txt", "21\n");
+    let output = run(&["--debug", &format!("--input={}", input.to_str().unwrap()), file.to_str().unwrap()], "continue\n");
+    assert_eq!(output.status.code(), Some(21));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(debug) 42\n"));
+
+    // without --input the program has nothing to read, rather than the commands
+    let output = run(&["--debug", file.to_str().unwrap()], "continue\n");
+    assert_eq!(output.status.code(), Some(7));
+
+    // quitting the debugger, or running out of commands, is not an error
+    for commands in ["quit\n", ""] {
+        let output = run(&["--debug", file.to_str().unwrap()], commands);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(output.stderr.is_empty());
+    }
+
+    let output = run(&["--input=does_not_exist.txt", file.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Input file \"does_not_exist.txt\""));
+}
+
+#[test]
+fn test_subcommands() {
+    let file = source_file("stages.tt", "func main() {\n    int a;\n    read(a);\n    print(a * 2);\n    return a;\n}\n");
+    let path = file.to_str().unwrap();
+
+    let output = run(&["lex", path], "");
+    let tokens = String::from_utf8(output.stdout).unwrap();
+    assert!(tokens.starts_with("1:1 Func\n1:6 Ident(\"main\")\n"));
+
+    let output = run(&["parse", path], "");
+    let tree = String::from_utf8(output.stdout).unwrap();
+    assert!(tree.starts_with("(func main ()\n  (declare int a)\n  (read a)\n"));
+
+    // check prints nothing when the program is fine
+    let output = run(&["check", path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let ir_file = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("stages.ir");
+    let output = run(&["build", "--emit=ir", "-o", ir_file.to_str().unwrap(), path], "");
+    assert_eq!(output.status.code(), Some(0));
+    let ir = fs::read_to_string(&ir_file).unwrap();
+    assert!(ir.starts_with("%func main()\n%int a\n%input a\n"));
+
+    let output = run(&["build", "--emit", "bytecode", path], "");
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("func main: 0 parameters"));
+
+    // run shows only what the program prints
+    let output = run(&["run", path], "21\n");
+    assert_eq!(output.status.code(), Some(21));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+
+    // errors go to standard error, with the stage's exit code
+    let file = source_file("stages_semantic.tt", "func main() { a = 1; }");
+    let output = run(&["check", file.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(5));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Semantic Analysis"));
+    // the syntax alone is fine
+    assert_eq!(exit_code(&["parse", file.to_str().unwrap()], ""), 0);
+
+    let file = source_file("stages_runtime.tt", "func main() { int a; a = 1 / a; }");
+    let output = run(&["run", file.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(7));
+    // one prefix, like the native builds print
+    let expected = "Runtime Error. Attempt to divide by zero.\nCall stack:\n    in main at line 1, column 22 (IR line 4)\n------------------\n001:  func main() { int a; a = 1 / a; }\n------------------\n";
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), expected);
+
+    // flags that do not fit the subcommand
+    assert_eq!(exit_code(&["lex", "--trace", path], ""), 2);
+    assert_eq!(exit_code(&["run", "--emit=ir", path], ""), 2);
+    assert_eq!(exit_code(&["check", "-o", "out.txt", path], ""), 2);
+    assert_eq!(exit_code(&["build", "--emit=html", path], ""), 2);
+
+    // usage errors go to standard error, so they never end up in the output
+    for args in [&["build", "--emit=html", path][..], &["run", "--max-instructions=lots", path], &["run", "--bogus", path], &["run", path, path], &["run", "missing.tt"]] {
+        let output = run(args, "");
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(output.stdout.is_empty(), "{args:?}");
+        assert!(!output.stderr.is_empty(), "{args:?}");
+    }
+}
+
+#[test]
+fn test_ir_files() {
+    let file = source_file("double.ir", "; doubles what it reads\n%func main()\n%int a\n%input a\n%mult a, a, 2\n%out a\n%ret a\n%endfunc\n");
+    let path = file.to_str().unwrap();
+    let output = run(&["run", path], "8\n");
+    assert_eq!(output.status.code(), Some(16));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "16\n");
+
+    // without a subcommand it runs the same way
+    assert_eq!(exit_code(&[path], "4\n"), 8);
+    assert_eq!(exit_code(&["check", path], ""), 0);
+
+    let output = run(&["build", "--emit=ir", path], "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "%func main()\n%int a\n%input a\n%mult a, a, 2\n%out a\n%ret a\n%endfunc\n");
+    assert_eq!(exit_code(&["parse", path], ""), 2);
+
+    // errors point at the line of the file
+    let file = source_file("undeclared.ir", "; uses b\n%func main()\n%int a\n%mov a, b\n%endfunc\n");
+    let output = run(&["check", file.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(6));
+    let message = String::from_utf8(output.stderr).unwrap();
+    assert!(message.contains("004:  %mov a, b"));
+    assert!(message.contains("Error at line 4."));
+
+    let file = source_file("no_main.ir", "%func f()\n%ret 0\n%endfunc\n");
+    assert_eq!(exit_code(&["run", file.to_str().unwrap()], ""), 6);
+}
+
+#[test]
+fn test_binary_files() {
+    let file = source_file("triple.tt", "func main() {\n    int a;\n    read(a);\n    print(a * 3);\n    return a / (a - 2);\n}\n");
+    let binary = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("triple.ttb");
+    let (path, binary) = (file.to_str().unwrap(), binary.to_str().unwrap());
+    assert_eq!(exit_code(&["build", "--emit=binary", "-o", binary, path], ""), 0);
+
+    let output = run(&["run", binary], "4\n");
+    assert_eq!(output.status.code(), Some(8));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12\n");
+    assert_eq!(exit_code(&[binary], "6\n"), 1);
+    assert_eq!(exit_code(&["check", binary], ""), 0);
+
+    // runtime errors keep their source position
+    let output = run(&["run", binary], "2\n");
+    assert_eq!(output.status.code(), Some(7));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("at line 5, column 5"));
+
+    let output = run(&["build", "--emit=bytecode", binary], "");
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("func main: 0 parameters"));
+    assert_eq!(exit_code(&["build", "--emit=ir", binary], ""), 2);
+    // the debug section has the instructions for the trace
+    let output = run(&["run", "--trace", binary], "4\n");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("%input a"));
+
+    // binary output has to go to a file, and only it can be stripped
+    assert_eq!(exit_code(&["build", "--emit=binary", path], ""), 2);
+    assert_eq!(exit_code(&["build", "--strip", path], ""), 2);
+    let stripped = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("triple_stripped.ttb");
+    let stripped = stripped.to_str().unwrap();
+    assert_eq!(exit_code(&["build", "--emit=binary", "--strip", "-o", stripped, path], ""), 0);
+    assert!(fs::metadata(stripped).unwrap().len() < fs::metadata(binary).unwrap().len());
+    assert_eq!(exit_code(&["run", stripped], "5\n"), 1);
+    assert_eq!(exit_code(&["run", "--trace", stripped], "5\n"), 2);
+
+    let file = source_file("broken.ttb", "TTBC not really");
+    assert_eq!(exit_code(&["run", file.to_str().unwrap()], ""), 6);
+}
+
+#[test]
+fn test_native_build() {
+    let file = source_file("native.tt", "func main() {\n    int a;\n    read(a);\n    print(a * 3);\n    return a;\n}\n");
+    let path = file.to_str().unwrap();
+    let output = run(&["build", "--emit=asm", path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\ntt_main:\n"));
+    let output = run(&["build", "--emit=c", path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\nstatic int64_t f_main(void) {\n"));
+    let output = run(&["build", "--emit=wat", path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("(module\n"));
+    let output = run(&["build", "--emit=llvm", path], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\ndefine internal i64 @\"f.main\"() {\n"));
+
+    // linking needs a C compiler
+    if !common::have("cc") {
+        return;
+    }
+    let executable = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("native");
+    let executable = executable.to_str().unwrap();
+    assert_eq!(exit_code(&["build", "--emit=exe", "-o", executable, path], ""), 0);
+    let mut child = Command::new(executable).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"4\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12\n");
+}
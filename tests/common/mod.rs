@@ -0,0 +1,171 @@
+// What the backend tests share: compiling programs, running them in the
+// interpreter, and checking a native build against it. Each backend's test
+// file only says how to build an executable from a module.
+
+// not every test file uses all of it
+#![allow(dead_code)]
+
+use rustcompiler::interpreter::*;
+use rustcompiler::phases::ir::Module;
+use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::semantic_parser::*;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub const INPUT: &str = "5\n3\n2\n1\nhello\n";
+
+// the examples that show off the error messages, so never load: break.tt
+// uses undeclared variables and functions, error1.tt breaks outside a loop,
+// and wrong.tt does that too and has no main.
+const FAILING_EXAMPLES: [&str; 3] = ["break", "error1", "wrong"];
+
+// set to skip the tests that build native executables when the tools for
+// them are not installed, rather than failing.
+const SKIP_NATIVE: &str = "TT_SKIP_NATIVE";
+
+pub fn compile(code: &str) -> Result<Module, String> {
+    let (tokens, spans) = lex_with_spans(code)?;
+    parse_program_with_spans(&tokens, &spans, &mut 0)
+}
+
+// the name and module of every example that loads. the ones that do not
+// have to be the ones in FAILING_EXAMPLES.
+pub fn examples() -> Vec<(String, Module)> {
+    let mut examples = vec![];
+    let mut failing = vec![];
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let code = fs::read_to_string(&path).unwrap();
+        let module = match path.extension().unwrap().to_str().unwrap() {
+        "tt" => compile(&code),
+        "ir" => parse_module(&code).map_err(|e| e.to_string()),
+        _ => continue,
+        };
+        match module {
+        Ok(module) if Program::load(&module).is_ok() => examples.push((name, module)),
+        _ => failing.push(name),
+        }
+    }
+    failing.sort();
+    assert_eq!(failing, FAILING_EXAMPLES, "the examples that do not load");
+    examples.sort_by(|a, b| a.0.cmp(&b.0));
+    examples
+}
+
+// what the program printed and the exit code a native executable gives: main's
+// value cut down to 8 bits, or 7 for a runtime error.
+pub fn interpret(module: &Module, input: &str) -> (String, i32) {
+    let program = Program::load(module).unwrap();
+    let mut output: Vec<u8> = vec![];
+    let mut vm = Vm::with_io(input.as_bytes(), &mut output);
+    vm.options.max_instructions = Some(10_000_000);
+    let code = match program.run(&mut vm) {
+    Ok(n) => n as u8 as i32,
+    Err(e) if e.limit().is_some() => panic!("the program did not finish: {}", e.message()),
+    Err(_) => 7,
+    };
+    drop(vm);
+    (String::from_utf8(output).unwrap(), code)
+}
+
+// whether the tool runs, for the tests that build native executables. a
+// missing tool fails the test unless TT_SKIP_NATIVE is set, so a passing run
+// has really checked the native builds.
+pub fn have(tool: &str) -> bool {
+    if Command::new(tool).arg("--version").stdout(Stdio::null()).output().is_ok() {
+        return true;
+    }
+    if env::var_os(SKIP_NATIVE).is_none() {
+        panic!("{tool} is needed to check the native builds. Install it, or set {SKIP_NATIVE}=1 to skip these tests.");
+    }
+    eprintln!("{tool} is not available and {SKIP_NATIVE} is set, skipping the native code tests");
+    false
+}
+
+// a path under the target directory for a build of the program.
+pub fn executable(backend: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{backend}_{name}"))
+}
+
+// runs an executable with the input, giving its output, errors and exit code.
+pub fn run(executable: &Path, input: &str) -> (String, String, i32) {
+    let mut child = Command::new(executable).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    // the program may finish without reading all of it
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap(), output.status.code().unwrap())
+}
+
+// every example prints the same and exits the same when built with the backend.
+pub fn check_examples(build: impl Fn(&Module, &str) -> PathBuf) {
+    let mut checked = 0;
+    for (name, module) in examples() {
+        let (expected, code) = interpret(&module, INPUT);
+        let (output, _, status) = run(&build(&module, &name), INPUT);
+        assert_eq!(output, expected, "output of {name}");
+        assert_eq!(status, code, "exit code of {name}");
+        checked += 1;
+    }
+    assert!(checked >= 5);
+}
+
+// wrapping arithmetic, long values, calls with many arguments, and reading
+// numbers that do not fit.
+pub fn check_arithmetic_and_calls(build: impl Fn(&Module, &str) -> PathBuf) {
+    let module = compile("func many(int a, int b, int c, int d, int e, int f, int g, long h) -> long {
+    return long(a + b * 2 + c * 3 + d * 4 + e * 5 + f * 6 + g * 7) + h * 8L;
+}
+func main() {
+    int x;
+    long big;
+    x = 2147483647;
+    x = x + 1;
+    big = 0L - 9223372036854775807L - 1L;
+    print(x, \" \", x / (0 - 1), \" \", big / (0L - 1L), \" \", big % (0L - 1L));
+    x = 0 - 7;
+    print(x / 2, \" \", x % 2, \" \", many(1, 2, 3, 4, 5, 6, 7, 9000000000L));
+    read(x);
+    read(big);
+    print(x, \" \", big, \" \", int(big));
+    read(big);
+    print(big);
+    if x < 0 {
+        print(\"negative\");
+    }
+    return x;
+}").unwrap();
+
+    // numbers that do not fit are read again, like in the interpreter
+    let input = "99999999999\n 3\n+\n-12\n123456789012\n9223372036854775808\n-9223372036854775808\n";
+    let (expected, code) = interpret(&module, input);
+    let (output, _, status) = run(&build(&module, "arithmetic"), input);
+    assert_eq!(output, expected);
+    assert_eq!(status, code);
+    assert!(output.contains("User Input Error. ' 3' is not a valid number."));
+    assert!(output.contains("-9223372036854775808\nnegative\n"));
+}
+
+// runtime errors stop the program with exit code 7 and the interpreter's message.
+pub fn check_runtime_errors(build: impl Fn(&Module, &str) -> PathBuf) {
+    let module = compile("func main() { int[3] a; int i; read(i); a[i] = 4; print(a[i]); return 10 / (i - 1); }").unwrap();
+    let executable = build(&module, "errors");
+    for (input, message) in [("1\n", "divide by zero"), ("3\n", "Array out of bounds. Value 3. Array Length 3"), ("-1\n", "Value -1. Array Length 3"), ("", "end of the input")] {
+        let (expected, code) = interpret(&module, input);
+        let (output, error, status) = run(&executable, input);
+        assert_eq!(output, expected);
+        assert_eq!((status, code), (7, 7));
+        assert!(error.contains(message), "{error}");
+    }
+
+    // names from hand-written IR that assemblers and compilers would not take,
+    // and one that would clash with another if mangled carelessly
+    let module = parse_module("%func a-b(%int x)\n%int t\n%int a-b\n%int a_2db\n%mov a-b, x\n%mov a_2db, 5\n%mov t, a-b\n:x.y\n%branch_ifn t, :x.y\n%ret t\n%endfunc\n%func main()\n%int r\n%call r, a-b(2)\n%ret r\n%endfunc\n").unwrap();
+    let (_, error, status) = run(&build(&module, "names"), "");
+    assert_eq!(status, 7);
+    assert!(error.contains("neither 0 or 1. The value is: 2"));
+}
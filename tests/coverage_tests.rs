@@ -0,0 +1,78 @@
+mod common;
+
+use common::compile;
+use rustcompiler::coverage::*;
+use rustcompiler::interpreter::*;
+use rustcompiler::phases::ir::Module;
+
+const SIGN: &str = "func sign(int n) -> int {\n    if n < 0 {\n        return 0 - 1;\n    }\n    return 1;\n}\n\nfunc unused() {\n    print(1);\n}\n\nfunc main() {\n    int s;\n    s = sign(5);\n    s = sign(7);\n}\n";
+
+fn cover(module: &Module) -> Coverage {
+    let mut coverage = Coverage::new(module);
+    let program = Program::load(module).unwrap();
+    program.run_with_hook(&mut Vm::with_io(std::io::empty(), std::io::sink()), &mut coverage).unwrap();
+    coverage
+}
+
+#[test]
+fn test_lines_and_branches() {
+    let coverage = cover(&compile(SIGN).unwrap());
+    assert_eq!(coverage.calls("sign"), Some(2));
+    assert_eq!(coverage.calls("unused"), Some(0));
+
+    let lines = coverage.lines("sign");
+    assert_eq!(lines, vec![
+        LineCount { line: 2, count: 2 },
+        LineCount { line: 3, count: 0 },
+        LineCount { line: 5, count: 2 },
+    ]);
+
+    // n < 0 was never true
+    assert_eq!(coverage.branches("sign"), vec![BranchCount { line: 2, when_true: 0, when_false: 2 }]);
+    assert_eq!(coverage.lines("unused"), vec![LineCount { line: 9, count: 0 }]);
+}
+
+#[test]
+fn test_lcov() {
+    let coverage = cover(&compile(SIGN).unwrap());
+    let mut lcov: Vec<u8> = vec![];
+    coverage.write_lcov(&mut lcov, "sign.tt").unwrap();
+    let lcov = String::from_utf8(lcov).unwrap();
+    let lines: Vec<&str> = lcov.lines().collect();
+    assert_eq!(lines[..2], ["TN:", "SF:sign.tt"]);
+    assert!(lines.contains(&"FN:2,sign"));
+    assert!(lines.contains(&"FNDA:2,sign"));
+    assert!(lines.contains(&"FNDA:0,unused"));
+    assert!(lines.contains(&"FNH:2"));
+    assert!(lines.contains(&"BRDA:2,0,0,0"));
+    assert!(lines.contains(&"BRDA:2,0,1,2"));
+    assert!(lines.contains(&"BRF:2"));
+    assert!(lines.contains(&"BRH:1"));
+    assert!(lines.contains(&"DA:3,0"));
+    assert!(lines.contains(&"DA:14,1"));
+    assert_eq!(lines.last(), Some(&"end_of_record"));
+}
+
+#[test]
+fn test_summary() {
+    let coverage = cover(&compile(SIGN).unwrap());
+    let mut summary: Vec<u8> = vec![];
+    coverage.write_summary(&mut summary).unwrap();
+    let summary = String::from_utf8(summary).unwrap();
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines[1], "sign                      2/3    66.7%        1/2    50.0%  3");
+    assert_eq!(lines[2], "unused                    0/1     0.0%        0/0        -  9");
+    assert!(lines[4].starts_with("Total                     5/7    71.4%        1/2    50.0%"));
+}
+
+#[test]
+fn test_different_module() {
+    // the coverage is made for a shorter main than the one that runs.
+    let coverage_for = compile("func main() {\n}\n").unwrap();
+    let module = compile(SIGN).unwrap();
+    let mut coverage = Coverage::new(&coverage_for);
+    let program = Program::load(&module).unwrap();
+    program.run_with_hook(&mut Vm::with_io(std::io::empty(), std::io::sink()), &mut coverage).unwrap();
+    assert!(coverage.mismatch().unwrap().starts_with("main has no instruction"));
+    assert_eq!(cover(&module).mismatch(), None);
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use rustcompiler::debugger::*;
+use rustcompiler::interpreter::*;
+
+const FIB: &str = "func fib(int n) -> int {\n    int a;\n    if n < 2 {\n        return n;\n    }\n    a = fib(n - 1);\n    return a + fib(n - 2);\n}\n\nfunc main() {\n    int[3] arr;\n    int x;\n    x = fib(4);\n    arr[1] = x;\n    print(x);\n}\n";
+
+fn load(code: &str) -> Program {
+    Program::load(&common::compile(code).unwrap()).unwrap()
+}
+
+// runs the program under a debugger, with the commands as its input.
+fn debug(program: &Program, debugger: &mut Debugger, commands: &str) -> (Result<i64, RuntimeError>, String) {
+    let mut output: Vec<u8> = vec![];
+    let result = program.run_with_hook(&mut Vm::with_io(commands.as_bytes(), &mut output), debugger);
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_breakpoints() {
+    let program = load(FIB);
+    let mut debugger = Debugger::with_source(FIB);
+    let (result, output) = debug(&program, &mut debugger, "break fib\nbreak 14\ncontinue\nprint n\nbacktrace\ndelete 1\ncontinue\nprint arr\nprint x\ncontinue\n");
+    assert_eq!(result.unwrap(), 0);
+
+    // starts before the first instruction of main
+    assert!(output.starts_with("Stopped in main at line 11, column 5"));
+    assert!(output.contains("Breakpoint 1, function fib.\nStopped in fib at line 2, column 5"));
+    assert!(output.contains("n = 4\n"));
+    assert!(output.contains("#0 in fib at line 2, column 5"));
+    assert!(output.contains("#1 in main at line 13, column 5"));
+
+    // the line breakpoint stops once, before the array is written
+    assert!(output.contains("Breakpoint 2, line 14.\nStopped in main at line 14, column 5"));
+    assert!(output.contains("   14 |     arr[1] = x;\n"));
+    assert!(output.contains("arr = [0, 0, 0]\n(debug) x = 3\n"));
+    assert!(output.ends_with("3\n"));
+}
+
+#[test]
+fn test_labels() {
+    let code = "%func main()\n%int i\n:loop\n%add i, i, 1\n%int c\n%lt c, i, 3\n%branch_if c, :loop\n%ret i\n%endfunc\n";
+    let program = parse_ir(code).unwrap();
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(Breakpoint::Label(Some(String::from("main")), String::from("loop")));
+    let (result, output) = debug(&program, &mut debugger, "c\np i\nc\np i\nc\np i\nc\n");
+    assert_eq!(result.unwrap(), 3);
+    assert_eq!(output.matches("Breakpoint 1, label :loop in main.").count(), 3);
+    assert!(output.contains("i = 0\n"));
+    assert!(output.contains("i = 2\n"));
+
+    // IR text has no source, so stops show the IR line
+    assert!(output.contains("Stopped in main (IR line 3)\n      => :loop\n"));
+}
+
+#[test]
+fn test_stepping() {
+    let program = load(FIB);
+    let mut debugger = Debugger::new();
+
+    // next runs to the next source line, running calls through
+    let (_, output) = debug(&program, &mut debugger, "n\nn\nn\nn\np x\nq\n");
+    assert!(output.contains("(debug) Stopped in main at line 13, column 5 (IR line 26)\n      => %int t6\n(debug) Stopped in main at line 14, column 5"));
+    assert!(output.contains("=> %mov [arr + 1], x\n(debug) Stopped in main at line 15, column 5"));
+    assert!(output.contains("=> %out x\n(debug) x = 3\n"));
+
+    // step goes into calls, and finish comes back out
+    let mut debugger = Debugger::new();
+    let (_, output) = debug(&program, &mut debugger, "n\nn\ns\ns\nbt\nfinish\nn\np x\nq\n");
+    assert!(output.contains("=> %int t6\n(debug) Stopped in fib at line 2, column 5 (IR line 2)\n      => %int a\n(debug) Stopped in fib at line 3, column 5"));
+    assert!(output.contains("#0 in fib at line 3, column 5"));
+    assert!(output.contains("#1 in main at line 13, column 5"));
+    assert!(output.contains("(debug) Stopped in main at line 13, column 5 (IR line 28)\n      => %mov x, "));
+    assert!(output.contains("=> %mov x, t6\n(debug) Stopped in main at line 14, column 5"));
+    assert!(output.contains("x = 3\n"));
+
+    // quitting stops the program, which is not the program failing
+    let mut debugger = Debugger::new();
+    let (result, _) = debug(&program, &mut debugger, "quit\n");
+    let e = result.unwrap_err();
+    assert!(e.stopped());
+    assert!(e.message().contains("stopped"));
+
+    // so does running out of commands
+    let mut debugger = Debugger::new();
+    assert!(debug(&program, &mut debugger, "").0.unwrap_err().stopped());
+
+    // a runtime error is not a stop
+    let program = parse_ir("%func main()\n%int a\n%div a, 1, 0\n%ret a\n%endfunc\n").unwrap();
+    let mut debugger = Debugger::new();
+    assert!(!debug(&program, &mut debugger, "c\n").0.unwrap_err().stopped());
+}
+
+#[test]
+fn test_program_input() {
+    // the program reads from the same input as the debugger
+    let program = parse_ir("%func main()\n%int a\n%input a\n%ret a\n%endfunc\n").unwrap();
+    let mut debugger = Debugger::new();
+    let (result, output) = debug(&program, &mut debugger, "c\n42\n");
+    assert_eq!(result.unwrap(), 42);
+    assert!(output.contains("=> %int a\n(debug) "));
+
+    let mut debugger = Debugger::new();
+    let (_, output) = debug(&program, &mut debugger, "bogus\nprint b\nbreak\nc\n1\n");
+    assert!(output.contains("Unknown command 'bogus'."));
+    assert!(output.contains("No variable or array 'b' in main."));
+    assert!(output.contains("Expected a line number, function or label to break at."));
+}
+
+#[test]
+fn test_command_reader() {
+    // with a reader of its own, the debugger leaves the program's input alone
+    let program = parse_ir("%func main()\n%int a\n%input a\n%ret a\n%endfunc\n").unwrap();
+    let mut debugger = Debugger::new().with_commands(Box::new("step\nprint a\nc\n".as_bytes()));
+    let (result, output) = debug(&program, &mut debugger, "7\n");
+    assert_eq!(result.unwrap(), 7);
+    assert!(output.contains("a = 0\n"));
+}
@@ -0,0 +1,107 @@
+; generated by the Teh Terik compiler
+
+; %func main
+define internal i64 @"f.main"() {
+entry:
+  %result = alloca i64
+  %"v.a" = call ptr @tt_array(i64 3)
+  %"v.b" = call ptr @tt_array(i64 2)
+  %"v.i" = alloca i64
+  store i64 0, ptr %"v.i"
+  %"v.t0" = alloca i64
+  store i64 0, ptr %"v.t0"
+  %"v.t1" = alloca i64
+  store i64 0, ptr %"v.t1"
+  %"v.t2" = alloca i64
+  store i64 0, ptr %"v.t2"
+  %"v.t3" = alloca i64
+  store i64 0, ptr %"v.t3"
+  %"v.t4" = alloca i64
+  store i64 0, ptr %"v.t4"
+  %"v.t5" = alloca i64
+  store i64 0, ptr %"v.t5"
+  ; %int[] a, 3
+  %t0 = call ptr @memset(ptr %"v.a", i32 0, i64 24)
+  ; %long[] b, 2
+  %t1 = call ptr @memset(ptr %"v.b", i32 0, i64 16)
+  ; %int i
+  store i64 0, ptr %"v.i"
+  ; %input i
+  %t2 = call i64 @tt_input(i1 false)
+  store i64 %t2, ptr %"v.i"
+  ; %mov [a + i], 4
+  %t3 = load i64, ptr %"v.i"
+  call void @tt_check_write(i64 %t3, i64 3)
+  %t4 = getelementptr inbounds i64, ptr %"v.a", i64 %t3
+  store i64 4, ptr %t4
+  ; %int t0
+  store i64 0, ptr %"v.t0"
+  ; %mov t0, [a + i]
+  %t5 = load i64, ptr %"v.i"
+  call void @tt_check_read(i64 %t5, i64 3)
+  %t6 = getelementptr inbounds i64, ptr %"v.a", i64 %t5
+  %t7 = load i64, ptr %t6
+  %t8 = trunc i64 %t7 to i32
+  %t9 = sext i32 %t8 to i64
+  store i64 %t9, ptr %"v.t0"
+  ; %long t1
+  store i64 0, ptr %"v.t1"
+  ; %mov t1, t0
+  %t10 = load i64, ptr %"v.t0"
+  store i64 %t10, ptr %"v.t1"
+  ; %long t2
+  store i64 0, ptr %"v.t2"
+  ; %mult t2, t1, 3
+  %t11 = load i64, ptr %"v.t1"
+  %t12 = mul i64 %t11, 3
+  store i64 %t12, ptr %"v.t2"
+  ; %mov [b + 1], t2
+  call void @tt_check_write(i64 1, i64 2)
+  %t13 = getelementptr inbounds i64, ptr %"v.b", i64 1
+  %t14 = load i64, ptr %"v.t2"
+  store i64 %t14, ptr %t13
+  ; %long t3
+  store i64 0, ptr %"v.t3"
+  ; %mov t3, [b + 1]
+  call void @tt_check_read(i64 1, i64 2)
+  %t15 = getelementptr inbounds i64, ptr %"v.b", i64 1
+  %t16 = load i64, ptr %t15
+  store i64 %t16, ptr %"v.t3"
+  ; %out t3
+  %t17 = load i64, ptr %"v.t3"
+  call void @tt_out(i64 %t17)
+  ; %int t4
+  store i64 0, ptr %"v.t4"
+  ; %sub t4, i, 1
+  %t18 = load i64, ptr %"v.i"
+  %t19 = sub i64 %t18, 1
+  %t20 = trunc i64 %t19 to i32
+  %t21 = sext i32 %t20 to i64
+  store i64 %t21, ptr %"v.t4"
+  ; %int t5
+  store i64 0, ptr %"v.t5"
+  ; %div t5, 10, t4
+  %t22 = load i64, ptr %"v.t4"
+  %t23 = trunc i64 %t22 to i32
+  %t24 = sext i32 %t23 to i64
+  %t25 = call i64 @tt_div(i64 10, i64 %t24)
+  %t26 = trunc i64 %t25 to i32
+  %t27 = sext i32 %t26 to i64
+  store i64 %t27, ptr %"v.t5"
+  ; %ret t5
+  %t28 = load i64, ptr %"v.t5"
+  store i64 %t28, ptr %result
+  br label %return
+return:
+  call void @free(ptr %"v.a")
+  call void @free(ptr %"v.b")
+  %t29 = load i64, ptr %result
+  ret i64 %t29
+}
+
+define i32 @main() {
+  %result = call i64 @"f.main"()
+  %byte = trunc i64 %result to i8
+  %code = zext i8 %byte to i32
+  ret i32 %code
+}
@@ -0,0 +1,173 @@
+; generated by the Teh Terik compiler
+
+@str.0 = private unnamed_addr constant [4 x i8] c"! = "
+@str.1 = private unnamed_addr constant [11 x i8] c"int(20!) = "
+
+; %func fact
+define internal i64 @"f.fact"(i64 %"p.n") {
+entry:
+  %result = alloca i64
+  %"v.n" = alloca i64
+  store i64 %"p.n", ptr %"v.n"
+  %"v.t0" = alloca i64
+  store i64 0, ptr %"v.t0"
+  %"v.t2" = alloca i64
+  store i64 0, ptr %"v.t2"
+  %"v.t1" = alloca i64
+  store i64 0, ptr %"v.t1"
+  %"v.t3" = alloca i64
+  store i64 0, ptr %"v.t3"
+  ; %int t0
+  store i64 0, ptr %"v.t0"
+  ; %le t0, n, 1
+  %t0 = load i64, ptr %"v.n"
+  %t1 = icmp sle i64 %t0, 1
+  %t2 = zext i1 %t1 to i64
+  store i64 %t2, ptr %"v.t0"
+  ; %branch_ifn t0, :1
+  %t3 = load i64, ptr %"v.t0"
+  %t4 = call i1 @tt_branch(i64 %t3)
+  br i1 %t4, label %t5, label %"l.1"
+t5:
+  ; %ret 1
+  store i64 1, ptr %result
+  br label %return
+t6:
+  ; %jmp :0
+  br label %"l.0"
+"l.1":
+  br label %"l.0"
+"l.0":
+  ; %long t2
+  store i64 0, ptr %"v.t2"
+  ; %sub t2, n, 1
+  %t7 = load i64, ptr %"v.n"
+  %t8 = sub i64 %t7, 1
+  store i64 %t8, ptr %"v.t2"
+  ; %long t1
+  store i64 0, ptr %"v.t1"
+  ; %call t1, fact(t2)
+  %t9 = load i64, ptr %"v.t2"
+  %t10 = call i64 @"f.fact"(i64 %t9)
+  store i64 %t10, ptr %"v.t1"
+  ; %long t3
+  store i64 0, ptr %"v.t3"
+  ; %mult t3, n, t1
+  %t11 = load i64, ptr %"v.n"
+  %t12 = load i64, ptr %"v.t1"
+  %t13 = mul i64 %t11, %t12
+  store i64 %t13, ptr %"v.t3"
+  ; %ret t3
+  %t14 = load i64, ptr %"v.t3"
+  store i64 %t14, ptr %result
+  br label %return
+return:
+  %t15 = load i64, ptr %result
+  ret i64 %t15
+}
+
+; %func main
+define internal i64 @"f.main"() {
+entry:
+  %result = alloca i64
+  %"v.i" = alloca i64
+  store i64 0, ptr %"v.i"
+  %"v.t4" = alloca i64
+  store i64 0, ptr %"v.t4"
+  %"v.t6" = alloca i64
+  store i64 0, ptr %"v.t6"
+  %"v.t5" = alloca i64
+  store i64 0, ptr %"v.t5"
+  %"v.t7" = alloca i64
+  store i64 0, ptr %"v.t7"
+  %"v.t8" = alloca i64
+  store i64 0, ptr %"v.t8"
+  %"v.t9" = alloca i64
+  store i64 0, ptr %"v.t9"
+  ; %int i
+  store i64 0, ptr %"v.i"
+  ; %mov i, 1
+  store i64 1, ptr %"v.i"
+  br label %"l.3"
+"l.3":
+  ; %int t4
+  store i64 0, ptr %"v.t4"
+  ; %le t4, i, 20
+  %t0 = load i64, ptr %"v.i"
+  %t1 = icmp sle i64 %t0, 20
+  %t2 = zext i1 %t1 to i64
+  store i64 %t2, ptr %"v.t4"
+  ; %branch_ifn t4, :2
+  %t3 = load i64, ptr %"v.t4"
+  %t4 = call i1 @tt_branch(i64 %t3)
+  br i1 %t4, label %t5, label %"l.2"
+t5:
+  ; %out_int i
+  %t6 = load i64, ptr %"v.i"
+  call void @tt_out_int(i64 %t6)
+  ; %out_str $0
+  call void @tt_out_str(ptr @str.0, i64 4)
+  ; %long t6
+  store i64 0, ptr %"v.t6"
+  ; %mov t6, i
+  %t7 = load i64, ptr %"v.i"
+  store i64 %t7, ptr %"v.t6"
+  ; %long t5
+  store i64 0, ptr %"v.t5"
+  ; %call t5, fact(t6)
+  %t8 = load i64, ptr %"v.t6"
+  %t9 = call i64 @"f.fact"(i64 %t8)
+  store i64 %t9, ptr %"v.t5"
+  ; %out_int t5
+  %t10 = load i64, ptr %"v.t5"
+  call void @tt_out_int(i64 %t10)
+  ; %out_nl
+  %t11 = call i32 @putchar(i32 10)
+  ; %int t7
+  store i64 0, ptr %"v.t7"
+  ; %add t7, i, 1
+  %t12 = load i64, ptr %"v.i"
+  %t13 = add i64 %t12, 1
+  %t14 = trunc i64 %t13 to i32
+  %t15 = sext i32 %t14 to i64
+  store i64 %t15, ptr %"v.t7"
+  ; %mov i, t7
+  %t16 = load i64, ptr %"v.t7"
+  %t17 = trunc i64 %t16 to i32
+  %t18 = sext i32 %t17 to i64
+  store i64 %t18, ptr %"v.i"
+  ; %jmp :3
+  br label %"l.3"
+"l.2":
+  ; %out_str $1
+  call void @tt_out_str(ptr @str.1, i64 11)
+  ; %long t8
+  store i64 0, ptr %"v.t8"
+  ; %call t8, fact(20)
+  %t19 = call i64 @"f.fact"(i64 20)
+  store i64 %t19, ptr %"v.t8"
+  ; %int t9
+  store i64 0, ptr %"v.t9"
+  ; %mov t9, t8
+  %t20 = load i64, ptr %"v.t8"
+  %t21 = trunc i64 %t20 to i32
+  %t22 = sext i32 %t21 to i64
+  store i64 %t22, ptr %"v.t9"
+  ; %out_int t9
+  %t23 = load i64, ptr %"v.t9"
+  call void @tt_out_int(i64 %t23)
+  ; %out_nl
+  %t24 = call i32 @putchar(i32 10)
+  store i64 0, ptr %result
+  br label %return
+return:
+  %t25 = load i64, ptr %result
+  ret i64 %t25
+}
+
+define i32 @main() {
+  %result = call i64 @"f.main"()
+  %byte = trunc i64 %result to i8
+  %code = zext i8 %byte to i32
+  ret i32 %code
+}
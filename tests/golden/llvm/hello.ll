@@ -0,0 +1,92 @@
+; generated by the Teh Terik compiler
+
+@str.0 = private unnamed_addr constant [12 x i8] c"hello, world"
+@str.1 = private unnamed_addr constant [6 x i8] c"sum = "
+@str.2 = private unnamed_addr constant [3 x i8] c" + "
+@str.3 = private unnamed_addr constant [3 x i8] c" = "
+@str.4 = private unnamed_addr constant [12 x i8] c"tab\09\22quoted\22"
+
+; %func main
+define internal i64 @"f.main"() {
+entry:
+  %result = alloca i64
+  %"v.a" = alloca i64
+  store i64 0, ptr %"v.a"
+  %"v.b" = alloca i64
+  store i64 0, ptr %"v.b"
+  %"v.t0" = alloca i64
+  store i64 0, ptr %"v.t0"
+  %"v.t1" = alloca i64
+  store i64 0, ptr %"v.t1"
+  ; %int a
+  store i64 0, ptr %"v.a"
+  ; %int b
+  store i64 0, ptr %"v.b"
+  ; %mov a, 100
+  store i64 100, ptr %"v.a"
+  ; %mov b, 50
+  store i64 50, ptr %"v.b"
+  ; %out_str $0
+  call void @tt_out_str(ptr @str.0, i64 12)
+  ; %out_nl
+  %t0 = call i32 @putchar(i32 10)
+  ; %out_str $1
+  call void @tt_out_str(ptr @str.1, i64 6)
+  ; %int t0
+  store i64 0, ptr %"v.t0"
+  ; %add t0, a, b
+  %t1 = load i64, ptr %"v.a"
+  %t2 = load i64, ptr %"v.b"
+  %t3 = add i64 %t1, %t2
+  %t4 = trunc i64 %t3 to i32
+  %t5 = sext i32 %t4 to i64
+  store i64 %t5, ptr %"v.t0"
+  ; %out_int t0
+  %t6 = load i64, ptr %"v.t0"
+  call void @tt_out_int(i64 %t6)
+  ; %out_nl
+  %t7 = call i32 @putchar(i32 10)
+  ; %out_int a
+  %t8 = load i64, ptr %"v.a"
+  call void @tt_out_int(i64 %t8)
+  ; %out_str $2
+  call void @tt_out_str(ptr @str.2, i64 3)
+  ; %out_int b
+  %t9 = load i64, ptr %"v.b"
+  call void @tt_out_int(i64 %t9)
+  ; %out_str $3
+  call void @tt_out_str(ptr @str.3, i64 3)
+  ; %int t1
+  store i64 0, ptr %"v.t1"
+  ; %add t1, a, b
+  %t10 = load i64, ptr %"v.a"
+  %t11 = load i64, ptr %"v.b"
+  %t12 = add i64 %t10, %t11
+  %t13 = trunc i64 %t12 to i32
+  %t14 = sext i32 %t13 to i64
+  store i64 %t14, ptr %"v.t1"
+  ; %out_int t1
+  %t15 = load i64, ptr %"v.t1"
+  call void @tt_out_int(i64 %t15)
+  ; %out_nl
+  %t16 = call i32 @putchar(i32 10)
+  ; %out_str $4
+  call void @tt_out_str(ptr @str.4, i64 12)
+  ; %out_nl
+  %t17 = call i32 @putchar(i32 10)
+  ; %out b
+  %t18 = load i64, ptr %"v.b"
+  call void @tt_out(i64 %t18)
+  store i64 0, ptr %result
+  br label %return
+return:
+  %t19 = load i64, ptr %result
+  ret i64 %t19
+}
+
+define i32 @main() {
+  %result = call i64 @"f.main"()
+  %byte = trunc i64 %result to i8
+  %code = zext i8 %byte to i32
+  ret i32 %code
+}
@@ -0,0 +1,68 @@
+; generated by the Teh Terik compiler
+
+; %func a-b
+define internal i64 @"f.a-b"(i64 %"p.x") {
+entry:
+  %result = alloca i64
+  %"v.x" = alloca i64
+  %t0 = trunc i64 %"p.x" to i32
+  %t1 = sext i32 %t0 to i64
+  store i64 %t1, ptr %"v.x"
+  %"v.t" = alloca i64
+  store i64 0, ptr %"v.t"
+  ; %int t
+  store i64 0, ptr %"v.t"
+  ; %mov t, x
+  %t2 = load i64, ptr %"v.x"
+  %t3 = trunc i64 %t2 to i32
+  %t4 = sext i32 %t3 to i64
+  store i64 %t4, ptr %"v.t"
+  br label %"l.x.y"
+"l.x.y":
+  ; %branch_ifn t, :x.y
+  %t5 = load i64, ptr %"v.t"
+  %t6 = call i1 @tt_branch(i64 %t5)
+  br i1 %t6, label %t7, label %"l.x.y"
+t7:
+  ; %ret t
+  %t8 = load i64, ptr %"v.t"
+  store i64 %t8, ptr %result
+  br label %return
+return:
+  %t9 = load i64, ptr %result
+  ret i64 %t9
+}
+
+; %func main
+define internal i64 @"f.main"(i64 %"p.n") {
+entry:
+  %result = alloca i64
+  %"v.n" = alloca i64
+  %t0 = trunc i64 %"p.n" to i32
+  %t1 = sext i32 %t0 to i64
+  store i64 %t1, ptr %"v.n"
+  %"v.r" = alloca i64
+  store i64 0, ptr %"v.r"
+  ; %int r
+  store i64 0, ptr %"v.r"
+  ; %call r, a-b(n)
+  %t2 = load i64, ptr %"v.n"
+  %t3 = call i64 @"f.a-b"(i64 %t2)
+  %t4 = trunc i64 %t3 to i32
+  %t5 = sext i32 %t4 to i64
+  store i64 %t5, ptr %"v.r"
+  ; %ret r
+  %t6 = load i64, ptr %"v.r"
+  store i64 %t6, ptr %result
+  br label %return
+return:
+  %t7 = load i64, ptr %result
+  ret i64 %t7
+}
+
+@main.error = private unnamed_addr constant [100 x i8] c"Runtime Error. Incorrect number of parameters passed to the function. Expected 1, got 0 parameters\0A\00"
+
+define i32 @main() {
+  call void @tt_fail(ptr @main.error)
+  unreachable
+}
@@ -0,0 +1,93 @@
+; generated by the Teh Terik compiler
+
+; %func sum
+define internal i64 @"f.sum"(i64 %"p.n") {
+entry:
+  %result = alloca i64
+  %"v.n" = alloca i64
+  %t0 = trunc i64 %"p.n" to i32
+  %t1 = sext i32 %t0 to i64
+  store i64 %t1, ptr %"v.n"
+  %"v.total" = alloca i64
+  store i64 0, ptr %"v.total"
+  %"v.done" = alloca i64
+  store i64 0, ptr %"v.done"
+  ; %int total
+  store i64 0, ptr %"v.total"
+  ; %int done
+  store i64 0, ptr %"v.done"
+  br label %"l.loop"
+"l.loop":
+  ; %lt done, n, 1
+  %t2 = load i64, ptr %"v.n"
+  %t3 = icmp slt i64 %t2, 1
+  %t4 = zext i1 %t3 to i64
+  store i64 %t4, ptr %"v.done"
+  ; %branch_if done, :end
+  %t5 = load i64, ptr %"v.done"
+  %t6 = call i1 @tt_branch(i64 %t5)
+  br i1 %t6, label %"l.end", label %t7
+t7:
+  ; %add total, total, n
+  %t8 = load i64, ptr %"v.total"
+  %t9 = load i64, ptr %"v.n"
+  %t10 = add i64 %t8, %t9
+  %t11 = trunc i64 %t10 to i32
+  %t12 = sext i32 %t11 to i64
+  store i64 %t12, ptr %"v.total"
+  ; %sub n, n, 1
+  %t13 = load i64, ptr %"v.n"
+  %t14 = sub i64 %t13, 1
+  %t15 = trunc i64 %t14 to i32
+  %t16 = sext i32 %t15 to i64
+  store i64 %t16, ptr %"v.n"
+  ; %jmp :loop
+  br label %"l.loop"
+"l.end":
+  ; %ret total
+  %t17 = load i64, ptr %"v.total"
+  store i64 %t17, ptr %result
+  br label %return
+return:
+  %t18 = load i64, ptr %result
+  ret i64 %t18
+}
+
+; %func main
+define internal i64 @"f.main"() {
+entry:
+  %result = alloca i64
+  %"v.n" = alloca i64
+  store i64 0, ptr %"v.n"
+  %"v.s" = alloca i64
+  store i64 0, ptr %"v.s"
+  ; %int n
+  store i64 0, ptr %"v.n"
+  ; %int s
+  store i64 0, ptr %"v.s"
+  ; %input n
+  %t0 = call i64 @tt_input(i1 false)
+  store i64 %t0, ptr %"v.n"
+  ; %call s, sum(n)
+  %t1 = load i64, ptr %"v.n"
+  %t2 = call i64 @"f.sum"(i64 %t1)
+  %t3 = trunc i64 %t2 to i32
+  %t4 = sext i32 %t3 to i64
+  store i64 %t4, ptr %"v.s"
+  ; %out s
+  %t5 = load i64, ptr %"v.s"
+  call void @tt_out(i64 %t5)
+  ; %ret 0
+  store i64 0, ptr %result
+  br label %return
+return:
+  %t6 = load i64, ptr %result
+  ret i64 %t6
+}
+
+define i32 @main() {
+  %result = call i64 @"f.main"()
+  %byte = trunc i64 %result to i8
+  %code = zext i8 %byte to i32
+  ret i32 %code
+}
@@ -0,0 +1,128 @@
+; generated by the Teh Terik compiler
+
+; %func main
+define internal i64 @"f.main"() {
+entry:
+  %result = alloca i64
+  %"v.c" = alloca i64
+  store i64 0, ptr %"v.c"
+  %"v.t0" = alloca i64
+  store i64 0, ptr %"v.t0"
+  %"v.t1" = alloca i64
+  store i64 0, ptr %"v.t1"
+  %"v.t2" = alloca i64
+  store i64 0, ptr %"v.t2"
+  %"v.t3" = alloca i64
+  store i64 0, ptr %"v.t3"
+  %"v.t4" = alloca i64
+  store i64 0, ptr %"v.t4"
+  %"v.t5" = alloca i64
+  store i64 0, ptr %"v.t5"
+  ; %int c
+  store i64 0, ptr %"v.c"
+  ; %inputc c
+  %t0 = call i64 @tt_inputc()
+  %t1 = trunc i64 %t0 to i32
+  %t2 = sext i32 %t1 to i64
+  store i64 %t2, ptr %"v.c"
+  br label %"l.1"
+"l.1":
+  ; %int t0
+  store i64 0, ptr %"v.t0"
+  ; %int t1
+  store i64 0, ptr %"v.t1"
+  ; %sub t1, 0, 1
+  %t3 = sub i64 0, 1
+  %t4 = trunc i64 %t3 to i32
+  %t5 = sext i32 %t4 to i64
+  store i64 %t5, ptr %"v.t1"
+  ; %neq t0, c, t1
+  %t6 = load i64, ptr %"v.c"
+  %t7 = load i64, ptr %"v.t1"
+  %t8 = icmp ne i64 %t6, %t7
+  %t9 = zext i1 %t8 to i64
+  store i64 %t9, ptr %"v.t0"
+  ; %branch_ifn t0, :0
+  %t10 = load i64, ptr %"v.t0"
+  %t11 = call i1 @tt_branch(i64 %t10)
+  br i1 %t11, label %t12, label %"l.0"
+t12:
+  ; %int t2
+  store i64 0, ptr %"v.t2"
+  ; %ge t2, c, 97
+  %t13 = load i64, ptr %"v.c"
+  %t14 = icmp sge i64 %t13, 97
+  %t15 = zext i1 %t14 to i64
+  store i64 %t15, ptr %"v.t2"
+  ; %branch_ifn t2, :3
+  %t16 = load i64, ptr %"v.t2"
+  %t17 = call i1 @tt_branch(i64 %t16)
+  br i1 %t17, label %t18, label %"l.3"
+t18:
+  ; %int t3
+  store i64 0, ptr %"v.t3"
+  ; %le t3, c, 122
+  %t19 = load i64, ptr %"v.c"
+  %t20 = icmp sle i64 %t19, 122
+  %t21 = zext i1 %t20 to i64
+  store i64 %t21, ptr %"v.t3"
+  ; %branch_ifn t3, :5
+  %t22 = load i64, ptr %"v.t3"
+  %t23 = call i1 @tt_branch(i64 %t22)
+  br i1 %t23, label %t24, label %"l.5"
+t24:
+  ; %int t4
+  store i64 0, ptr %"v.t4"
+  ; %sub t4, c, 97
+  %t25 = load i64, ptr %"v.c"
+  %t26 = sub i64 %t25, 97
+  %t27 = trunc i64 %t26 to i32
+  %t28 = sext i32 %t27 to i64
+  store i64 %t28, ptr %"v.t4"
+  ; %int t5
+  store i64 0, ptr %"v.t5"
+  ; %add t5, t4, 65
+  %t29 = load i64, ptr %"v.t4"
+  %t30 = add i64 %t29, 65
+  %t31 = trunc i64 %t30 to i32
+  %t32 = sext i32 %t31 to i64
+  store i64 %t32, ptr %"v.t5"
+  ; %mov c, t5
+  %t33 = load i64, ptr %"v.t5"
+  %t34 = trunc i64 %t33 to i32
+  %t35 = sext i32 %t34 to i64
+  store i64 %t35, ptr %"v.c"
+  ; %jmp :4
+  br label %"l.4"
+"l.5":
+  br label %"l.4"
+"l.4":
+  ; %jmp :2
+  br label %"l.2"
+"l.3":
+  br label %"l.2"
+"l.2":
+  ; %outc c
+  %t36 = load i64, ptr %"v.c"
+  call void @tt_outc(i64 %t36)
+  ; %inputc c
+  %t37 = call i64 @tt_inputc()
+  %t38 = trunc i64 %t37 to i32
+  %t39 = sext i32 %t38 to i64
+  store i64 %t39, ptr %"v.c"
+  ; %jmp :1
+  br label %"l.1"
+"l.0":
+  store i64 0, ptr %result
+  br label %return
+return:
+  %t40 = load i64, ptr %result
+  ret i64 %t40
+}
+
+define i32 @main() {
+  %result = call i64 @"f.main"()
+  %byte = trunc i64 %result to i8
+  %code = zext i8 %byte to i32
+  ret i32 %code
+}
@@ -0,0 +1,308 @@
+mod common;
+
+use common::compile;
+use rustcompiler::interpreter::*;
+use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::semantic_parser::*;
+
+#[test]
+fn test_run_ir_text() {
+    let code = "%func square(%int n)\n%int t\n%mult t, n, n\n%ret t\n%endfunc\n%func main()\n%int r\n%call r, square(7)\n%ret r\n%endfunc\n";
+    let program = parse_ir(code).unwrap();
+    assert_eq!(program.run(&mut Vm::new()).unwrap(), 49);
+
+    // the same program can be run more than once
+    assert_eq!(program.run(&mut Vm::new()).unwrap(), 49);
+}
+
+#[test]
+fn test_ir_errors() {
+    // errors about one instruction know its line
+    let e = parse_ir("%func main()\n%int a\n%mov a, b\n%endfunc\n").unwrap_err();
+    assert_eq!(e.line(), Some(3));
+    assert!(e.message().contains("'b'"));
+
+    // errors about the whole program do not
+    let e = parse_ir("%func f()\n%endfunc\n").unwrap_err();
+    assert_eq!(e.line(), None);
+
+    assert!(parse_module("%func main()\n%bogus a\n%endfunc\n").is_err());
+}
+
+#[test]
+fn test_runtime_errors() {
+    let program = parse_ir("%func main()\n%int a\n%div a, 1, 0\n%ret a\n%endfunc\n").unwrap();
+    let e = program.run(&mut Vm::new()).unwrap_err();
+    assert!(e.message().contains("divide by zero"));
+
+    let program = parse_ir("%func main()\n%int a\n%add a, 2147483647, 1\n%ret a\n%endfunc\n").unwrap();
+    assert_eq!(program.run(&mut Vm::new()).unwrap(), i32::MIN as i64);
+    let options = RunOptions { checked_arithmetic: true, ..RunOptions::default() };
+    assert!(program.run(&mut Vm::with_options(options)).is_err());
+}
+
+#[test]
+fn test_run_frontend_module() {
+    let program = Program::load(&compile("func fib(int n) -> int { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); } func main() { return fib(10); }").unwrap()).unwrap();
+    assert_eq!(program.run(&mut Vm::new()).unwrap(), 55);
+}
+
+// runs a program on the given input, giving back its result and everything it printed.
+fn run_with_input(program: &Program, input: &str) -> (Result<i64, RuntimeError>, String) {
+    let mut output: Vec<u8> = vec![];
+    let result = program.run(&mut Vm::with_io(input.as_bytes(), &mut output));
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_scripted_io() {
+    let program = Program::load(&compile("func main() { int a; int b; print(\"a? \"); read(a); read(b); print(a + b); printc('!'); }").unwrap()).unwrap();
+
+    let (result, output) = run_with_input(&program, "3\n4\n");
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(output, "a? \n7\n!");
+
+    // bad numbers are reported and read again
+    let (result, output) = run_with_input(&program, "x\n3\n99999999999\n4\n");
+    assert!(result.is_ok());
+    assert_eq!(output, "a? \nUser Input Error. 'x' is not a valid number.\nUser Input Error. '99999999999' is not a valid number.\n7\n!");
+
+    // running out of input is an error rather than asking forever
+    let (result, output) = run_with_input(&program, "3\n");
+    assert!(result.unwrap_err().message().contains("end of the input"));
+    assert_eq!(output, "a? \n");
+}
+
+#[test]
+fn test_scripted_char_io() {
+    // %inputc gives -1 at the end of the input
+    let program = parse_ir("%func main()\n%int c\n%inputc c\n%outc c\n%inputc c\n%out c\n%ret c\n%endfunc\n").unwrap();
+    let (result, output) = run_with_input(&program, "z");
+    assert_eq!(result.unwrap(), -1);
+    assert_eq!(output, "z-1\n");
+}
+
+#[test]
+fn test_frames() {
+    // every call gets its own variables and arrays, all starting at zero
+    let program = Program::load(&compile("func f(int n) -> int { int[3] a; int x; if n == 0 { return a[1] + x; } a[1] = n; x = n; return f(n - 1) + a[1] + x; }
+                      func main() { return f(4); }").unwrap()).unwrap();
+    assert_eq!(program.run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap(), 20);
+
+    // int and long slots keep their own widths in the same frame
+    let program = parse_ir("%func main()\n%long a\n%int b\n%long[] c, 2\n%mov a, 3000000000\n%mov b, a\n%mov [c + 1], a\n%mov a, [c + 1]\n%sub a, a, b\n%ret a\n%endfunc\n").unwrap();
+    assert_eq!(program.run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap(), 3000000000 - (3000000000i64 as i32 as i64));
+}
+
+#[test]
+fn test_call_depth() {
+    // compiled without spans, so the call stack only has IR lines
+    let tokens = lex("func down(int n) -> int { if n == 0 { return 0; } return down(n - 1) + 1; }
+                      func start(int n) -> int { return down(n); }
+                      func main() { int a; read(a); return start(a); }").unwrap();
+    let module = parse_program_ir(&tokens, &mut 0).unwrap();
+    let program = Program::load(&module).unwrap();
+
+    // deep recursion is fine, it does not use the native stack
+    let (result, _) = run_with_input(&program, "90000\n");
+    assert_eq!(result.unwrap(), 90000);
+
+    // going past the limit is a stack overflow with the calls that led to it
+    let mut output: Vec<u8> = vec![];
+    let mut vm = Vm::with_io("50\n".as_bytes(), &mut output);
+    vm.options.max_call_depth = 20;
+    let e = program.run(&mut vm).unwrap_err();
+    assert!(e.message().contains("Stack overflow in function 'down'"));
+    assert_eq!(e.backtrace().len(), 20);
+    assert_eq!(e.backtrace()[0].function, "down");
+    assert_eq!(e.backtrace()[18].function, "start");
+    assert_eq!(e.backtrace()[19].function, "main");
+
+    // long backtraces are cut short when printed
+    let text = e.to_string();
+    assert!(text.contains("Call stack:\n    in down (IR line "));
+    assert!(text.contains("\n    in start (IR line "));
+
+    let (result, _) = run_with_input(&program, "1000000\n");
+    let e = result.unwrap_err();
+    assert_eq!(e.backtrace().len(), 100000);
+    assert!(e.to_string().contains("... 99980 more calls ..."));
+}
+
+#[test]
+fn test_limits() {
+    let program = Program::load(&compile("func fill(int n) { int[100] a; if n > 0 { fill(n - 1); } }
+                      func main() { int n; read(n); fill(n); fill(n); while n == 0 { n = 0; } }").unwrap()).unwrap();
+    let run = |input: &str, options: RunOptions| {
+        let mut vm = Vm::with_io(input.as_bytes(), std::io::sink());
+        vm.options = options;
+        program.run(&mut vm)
+    };
+
+    // an infinite loop runs out of fuel
+    let options = RunOptions { max_instructions: Some(10000), ..RunOptions::default() };
+    let e = run("0\n", options.clone()).unwrap_err();
+    assert_eq!(e.limit(), Some(Limit::Instructions));
+    assert!(e.message().contains("Out of fuel"));
+    assert!(run("1\n", options).is_ok());
+
+    // and runs out of time
+    let options = RunOptions { timeout: Some(std::time::Duration::from_millis(50)), ..RunOptions::default() };
+    let e = run("0\n", options.clone()).unwrap_err();
+    assert_eq!(e.limit(), Some(Limit::Time));
+    assert!(e.message().contains("Out of time. The program ran for more than 50 milliseconds."));
+    assert!(run("1\n", options).is_ok());
+
+    // each call to fill takes 800 bytes of arrays while it runs
+    let options = RunOptions { max_array_memory: Some(8000), ..RunOptions::default() };
+    let e = run("10\n", options.clone()).unwrap_err();
+    assert_eq!(e.limit(), Some(Limit::ArrayMemory));
+    assert!(e.message().contains("'fill'"));
+
+    // memory is given back when a call returns
+    assert!(run("9\n", options).is_ok());
+
+    let options = RunOptions { max_call_depth: 5, ..RunOptions::default() };
+    assert_eq!(run("10\n", options).unwrap_err().limit(), Some(Limit::CallDepth));
+
+    // mistakes in the program itself are not limits
+    let program = parse_ir("%func main()\n%int a\n%div a, 1, 0\n%ret a\n%endfunc\n").unwrap();
+    assert_eq!(program.run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap_err().limit(), None);
+}
+
+#[test]
+fn test_runtime_error_locations() {
+    let code = "func get(int i) -> int {\n    int[3] a;\n    return a[i];\n}\nfunc main() {\n    int x;\n    x = get(1);\n    x = get(5);\n}\n";
+    let module = compile(code).unwrap();
+    let program = Program::load(&module).unwrap();
+    let e = program.run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap_err();
+
+    // the failing statement, and the call that led to it
+    assert_eq!(e.span(), Some(Span { line: 3, column: 5 }));
+    assert_eq!(e.backtrace()[1].function, "main");
+    assert_eq!(e.backtrace()[1].span, Some(Span { line: 8, column: 5 }));
+
+    // the IR lines point at the instructions that were running
+    let ir = module.to_string();
+    let ir: Vec<&str> = ir.lines().collect();
+    assert!(ir[e.line().unwrap() - 1].starts_with("%mov"));
+    assert!(ir[e.backtrace()[1].line.unwrap() - 1].starts_with("%call"));
+    assert!(e.to_string().contains("in get at line 3, column 5 (IR line "));
+
+    // IR text has no source to point at
+    let program = parse_ir("%func main()\n%int a\n%div a, 1, 0\n%ret a\n%endfunc\n").unwrap();
+    let e = program.run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap_err();
+    assert_eq!(e.line(), Some(3));
+    assert_eq!(e.span(), None);
+}
+
+#[test]
+fn test_bytecode_listing() {
+    let program = parse_ir("%func add(%int a, %long b)\n%long t\n%add t, a, b\n%ret t\n%endfunc\n%func main()\n%int[] arr, 2\n%int r\n:loop\n%call r, add(1, 2)\n%mov [arr + 1], r\n%branch_ifn r, :loop\n%ret r\n%endfunc\n").unwrap();
+    let listing = program.to_string();
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines[0], "func add: 2 parameters, 3 slots, 0 array bytes");
+    assert_eq!(lines[1], "  slots: s0 a, s1 b (long), s2 t (long)");
+    assert_eq!(lines[3], "     1  Add s2, s0, s1");
+    assert_eq!(lines[6], "func main: 0 parameters, 2 slots, 16 array bytes");
+    assert_eq!(lines[7], "  slots: s0 arr[], s1 r");
+    assert!(lines.contains(&"     3  Call s1, add(1, 2)"));
+    assert!(lines.contains(&"     4  Mov s0[1], s1"));
+    assert!(lines.contains(&"     5  BranchIfn s1, @2"));
+}
+
+#[test]
+fn test_hand_written_ir() {
+    // comments, blank lines and non-ASCII text, with no newline at the end
+    let code = "; sets a to 1 — then returns it\n\n%func main()\n%int a\n%mov a, 1 ; é\n%jmp :end\n:end\n%ret a\n%endfunc";
+    assert_eq!(parse_ir(code).unwrap().run(&mut Vm::with_io(std::io::empty(), std::io::sink())).unwrap(), 1);
+    for ending in ["   ", " ; done", "\n; done"] {
+        assert!(parse_module(&format!("%func main()\n%ret 0\n%endfunc{ending}")).is_ok());
+    }
+    // stray text after the last function is an error, not a crash
+    for ending in ["\n:end", "\n%ret 7", "\n12"] {
+        assert!(parse_module(&format!("%func main()\n%ret 0\n%endfunc{ending}")).is_err());
+    }
+
+    // the lines of errors are the lines of the file
+    let e = parse_ir("; first\n%func main()\n%ret 99999999999999999999\n%endfunc\n").unwrap_err();
+    assert_eq!(e.line(), Some(3));
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let code = "func twice(long n) -> long { return n * 2L; }\nfunc main() {\n    int[3] a;\n    long big;\n    int i;\n    big = 0L - 9223372036854775807L - 1L;\n    while i < 3 {\n        a[i] = 0 - i;\n        i = i + 1;\n    }\n    print(\"big: \", big, \" \", twice(4000000000L));\n    printc('!');\n    return a[2];\n}\n";
+    let program = Program::load(&compile(code).unwrap()).unwrap();
+    let (result, output) = run_with_input(&program, "");
+
+    let bytes = program.to_binary(true);
+    assert_eq!(&bytes[..4], b"TTBC");
+    let loaded = Program::from_binary(&bytes).unwrap();
+    assert!(loaded.has_debug_info());
+    assert_eq!(loaded.to_string(), program.to_string());
+    assert_eq!(loaded.to_binary(true), bytes);
+    let (loaded_result, loaded_output) = run_with_input(&loaded, "");
+    assert_eq!(loaded_result.unwrap(), result.unwrap());
+    assert_eq!(loaded_output, output);
+
+    // without the debug section it is smaller, but runs the same
+    let stripped = program.to_binary(false);
+    assert!(stripped.len() < bytes.len());
+    let loaded = Program::from_binary(&stripped).unwrap();
+    assert!(!loaded.has_debug_info());
+    assert_eq!(loaded.to_binary(false), stripped);
+    assert_eq!(run_with_input(&loaded, "").1, output);
+}
+
+#[test]
+fn test_binary_debug_info() {
+    let program = Program::load(&compile("func main() {\n    int x;\n    x = 5 / x;\n}\n").unwrap()).unwrap();
+    let e = program.run(&mut Vm::new()).unwrap_err();
+
+    // errors still know where they happened
+    let loaded = Program::from_binary(&program.to_binary(true)).unwrap();
+    let loaded_e = loaded.run(&mut Vm::new()).unwrap_err();
+    assert_eq!(loaded_e.span(), e.span());
+    assert_eq!(loaded_e.line(), e.line());
+
+    let loaded = Program::from_binary(&program.to_binary(false)).unwrap();
+    let loaded_e = loaded.run(&mut Vm::new()).unwrap_err();
+    assert_eq!(loaded_e.message(), e.message());
+    assert_eq!(loaded_e.span(), None);
+    assert_eq!(loaded_e.line(), None);
+}
+
+#[test]
+fn test_binary_errors() {
+    let program = parse_ir("%func main()\n%int[] arr, 2\n%int r\n:loop\n%mov [arr + 1], r\n%add r, r, 1\n%branch_ifn r, :loop\n%out_nl\n%ret r\n%endfunc\n").unwrap();
+    let bytes = program.to_binary(true);
+
+    let e = Program::from_binary(b"\x7fELF").unwrap_err();
+    assert!(e.message().contains("not a bytecode file"));
+    assert_eq!(e.line(), None);
+    let mut newer = bytes.clone();
+    newer[4] = 2;
+    assert!(Program::from_binary(&newer).unwrap_err().message().contains("version 2"));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(Program::from_binary(&longer).is_err());
+
+    // cut short anywhere, it is an error rather than a crash
+    for length in 0..bytes.len() {
+        assert!(Program::from_binary(&bytes[..length]).is_err());
+    }
+
+    // and any byte changed either fails to load or runs like any other program
+    let options = RunOptions { max_instructions: Some(1000), max_array_memory: Some(1 << 20), ..RunOptions::default() };
+    for at in 0..bytes.len() {
+        for value in [0, 1, 0x7f, 0x80, 0xff, bytes[at].wrapping_add(1)] {
+            let mut broken = bytes.clone();
+            broken[at] = value;
+            if let Ok(program) = Program::from_binary(&broken) {
+                let mut vm = Vm::with_io(std::io::empty(), std::io::sink());
+                vm.options = options.clone();
+                let _ = program.run(&mut vm);
+            }
+        }
+    }
+}
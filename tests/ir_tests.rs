@@ -0,0 +1,49 @@
+use rustcompiler::phases::ir::*;
+
+#[test]
+fn test_block_printing() {
+    let mut block = Block::new();
+    block.declare(Type::Int, "t0");
+    block.declare_array(Type::Long, "a", 4);
+    block.binary(BinaryOp::Add, "t0", Operand::Var(String::from("x")), Operand::Num(1));
+    block.load("t0", "a", Operand::Num(2));
+    block.store("a", Operand::Var(String::from("t0")), Operand::Num(7));
+    block.call("t0", "f", vec![Operand::Num(1), Operand::Var(String::from("x"))]);
+    block.label(&Label(String::from("3")));
+    block.branch_ifn(Operand::Var(String::from("t0")), &Label(String::from("3")));
+    block.out_str("$0");
+
+    let text: Vec<String> = block.instructions.iter().map(|i| i.to_string()).collect();
+    assert_eq!(text, vec![
+        "%int t0",
+        "%long[] a, 4",
+        "%add t0, x, 1",
+        "%mov t0, [a + 2]",
+        "%mov [a + t0], 7",
+        "%call t0, f(1,x)",
+        ":3",
+        "%branch_ifn t0, :3",
+        "%out_str $0",
+    ]);
+}
+
+#[test]
+fn test_module_printing() {
+    let mut module = Module::new();
+    module.add_string("$0", "a \"quoted\"\n");
+
+    let mut function = Function::new("add");
+    function.parameter(Type::Int, "a");
+    function.parameter(Type::Long, "b");
+    let mut block = Block::new();
+    block.ret(Operand::Var(String::from("a")));
+    function.append(block);
+    module.add_function(function);
+    module.add_function(Function::new("main"));
+    module.number_lines();
+
+    assert_eq!(module.to_string(), "%str $0, \"a \\\"quoted\\\"\\n\"\n%func add(%int a, %long b)\n%ret a\n%endfunc\n%func main()\n%endfunc\n");
+    assert_eq!(module.functions[0].line, 2);
+    assert_eq!(module.functions[0].lines, vec![3]);
+    assert_eq!(module.functions[1].line, 5);
+}
@@ -303,3 +303,126 @@ fn test_valid_variable_with_underscore() {
     let result = lex(input);
     assert!(result.is_ok());  // Changed from assert_eq to assert that result is Ok
 }
+
+#[test]
+fn test_return_type_tokens() {
+    let input = "func f() -> void";
+    let expected_tokens = vec![
+        Token::Func,
+        Token::Ident("f".to_string()),
+        Token::LeftParen,
+        Token::RightParen,
+        Token::Arrow,
+        Token::Void,
+        Token::End,
+    ];
+
+    assert_eq!(lex(input).unwrap(), expected_tokens);
+
+    // a minus sign on its own is still subtraction
+    assert_eq!(lex("a - b").unwrap()[1], Token::Subtract);
+}
+
+#[test]
+fn test_string_literals() {
+    let input = r#"print("sum = ", x);"#;
+    let expected_tokens = vec![
+        Token::Print,
+        Token::LeftParen,
+        Token::Str("sum = ".to_string()),
+        Token::Comma,
+        Token::Ident("x".to_string()),
+        Token::RightParen,
+        Token::Semicolon,
+        Token::End,
+    ];
+
+    assert_eq!(lex(input).unwrap(), expected_tokens);
+
+    // Escape sequences
+    let tokens = lex(r#""a\tb\n\"c\"\\""#).unwrap();
+    assert_eq!(tokens[0], Token::Str("a\tb\n\"c\"\\".to_string()));
+
+    // Comments and non-ascii text inside strings are kept
+    let tokens = lex(r##""# not a comment é""##).unwrap();
+    assert_eq!(tokens[0], Token::Str("# not a comment é".to_string()));
+
+    // Unterminated strings and unknown escapes
+    assert!(lex(r#""abc"#).is_err());
+    assert!(lex("\"abc\ndef\"").is_err());
+    assert!(lex(r#""\q""#).is_err());
+}
+
+#[test]
+fn test_character_literals() {
+    let input = "printc('a'); readc(c);";
+    let expected_tokens = vec![
+        Token::PrintChar,
+        Token::LeftParen,
+        Token::Num(97),
+        Token::RightParen,
+        Token::Semicolon,
+        Token::ReadChar,
+        Token::LeftParen,
+        Token::Ident("c".to_string()),
+        Token::RightParen,
+        Token::Semicolon,
+        Token::End,
+    ];
+
+    assert_eq!(lex(input).unwrap(), expected_tokens);
+
+    // Escape sequences
+    assert_eq!(lex(r"'\n'").unwrap()[0], Token::Num(10));
+    assert_eq!(lex(r"'\''").unwrap()[0], Token::Num(39));
+    assert_eq!(lex(r"'\0'").unwrap()[0], Token::Num(0));
+    assert_eq!(lex("' '").unwrap()[0], Token::Num(32));
+
+    // Empty, unterminated, too long or non-ascii literals
+    assert!(lex("''").is_err());
+    assert!(lex("'a").is_err());
+    assert!(lex("'ab'").is_err());
+    assert!(lex("'é'").is_err());
+    assert!(lex(r"'\q'").is_err());
+}
+
+#[test]
+fn test_long_literals() {
+    assert_eq!(lex("long a;").unwrap(), vec![
+        Token::Long,
+        Token::Ident("a".to_string()),
+        Token::Semicolon,
+        Token::End,
+    ]);
+
+    // Explicit suffix
+    assert_eq!(lex("10L").unwrap()[0], Token::LongNum(10));
+
+    // Literals too big for an int are inferred to be long
+    assert_eq!(lex("2147483647").unwrap()[0], Token::Num(2147483647));
+    assert_eq!(lex("2147483648").unwrap()[0], Token::LongNum(2147483648));
+    assert_eq!(lex("9223372036854775807").unwrap()[0], Token::LongNum(i64::MAX));
+
+    // Too big for a long, or a suffix that is really the start of a name
+    assert!(lex("9223372036854775808").is_err());
+    assert!(lex("10Lx").is_err());
+}
+
+#[test]
+fn test_token_spans() {
+    let (tokens, spans) = lex_with_spans("func main() {\n  int a; # comment\n\n  a = 'x' + \"s\";\n}").unwrap();
+    assert_eq!(tokens.len(), spans.len());
+    assert_eq!(spans[0], Span { line: 1, column: 1 });
+    assert_eq!(spans[1], Span { line: 1, column: 6 });
+    assert_eq!(tokens[5], Token::Int);
+    assert_eq!(spans[5], Span { line: 2, column: 3 });
+    assert_eq!(tokens[8], Token::Ident(String::from("a")));
+    assert_eq!(spans[8], Span { line: 4, column: 3 });
+    assert_eq!(spans[10], Span { line: 4, column: 7 });
+    assert_eq!(spans[12], Span { line: 4, column: 13 });
+    assert_eq!(spans[14], Span { line: 5, column: 1 });
+
+    // End sits just past the last character
+    assert_eq!(tokens[15], Token::End);
+    assert_eq!(spans[15], Span { line: 5, column: 2 });
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use rustcompiler::backend::llvm;
+use rustcompiler::interpreter::parse_module;
+use rustcompiler::phases::ir::Module;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn load(path: &str) -> Module {
+    let code = fs::read_to_string(path).unwrap();
+    if path.ends_with(".ir") {
+        return parse_module(&code).unwrap();
+    }
+    compile(&code).unwrap()
+}
+
+// which is the same for every program. UPDATE_GOLDEN=1 writes the files instead.
+fn golden(module: &Module, name: &str) {
+    let text = llvm::generate(module);
+    let program = &text[..text.find("\n; runtime\n").expect("no runtime in the output")];
+    let path = format!("tests/golden/llvm/{name}.ll");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, program).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| panic!("no golden file {path}, run with UPDATE_GOLDEN=1 to write it"));
+    assert_eq!(program, expected, "{name} does not match {path}");
+}
+
+// the flags llc needs for the text, or None without llc, or without cc when
+// TT_SKIP_NATIVE is set. llc is only used if it is installed, so a missing
+// one is written straight to stderr, where the test harness does not hide it.
+fn llc_flags() -> Option<Vec<&'static str>> {
+    if Command::new("llc").arg("--version").output().is_err() {
+        let _ = writeln!(io::stderr(), "llc is not available, skipping the LLVM executable tests");
+        return None;
+    }
+    if !have("cc") {
+        return None;
+    }
+    let version = Command::new("llc").arg("--version").output().unwrap();
+    let version = String::from_utf8_lossy(&version.stdout).to_string();
+    let major: u32 = version.split("version ").nth(1).and_then(|rest| rest.split('.').next()).and_then(|n| n.parse().ok()).expect("no version in llc --version");
+    let mut flags = vec!["-O2", "-relocation-model=pic"];
+    // LLVM 15 made opaque pointers the default
+    if major < 15 {
+        flags.push("-opaque-pointers");
+    }
+    Some(flags)
+}
+
+// builds the module with llc and cc into an executable.
+fn build(module: &Module, flags: &[&str], name: &str) -> PathBuf {
+    let executable = executable("llvm", name);
+    let (ll, assembly) = (executable.with_extension("ll"), executable.with_extension("s"));
+    fs::write(&ll, llvm::generate(module)).unwrap();
+    let llc = Command::new("llc").args(flags).arg(&ll).arg("-o").arg(&assembly).output().unwrap();
+    assert!(llc.status.success(), "llc could not compile {name}: {}", String::from_utf8_lossy(&llc.stderr));
+    assert!(Command::new("cc").arg(&assembly).arg("-o").arg(&executable).status().unwrap().success(), "cc could not link {name}");
+    executable
+}
+
+#[test]
+fn test_golden() {
+    for path in ["examples/hello.tt", "examples/factorial_long.tt", "examples/upper.tt", "examples/sum.ir"] {
+        let name = path.rsplit('/').next().unwrap().split('.').next().unwrap();
+        golden(&load(path), name);
+    }
+
+    let module = compile("func main() { int[3] a; long[2] b; int i; read(i); a[i] = 4; b[1] = long(a[i]) * 3L; print(b[1]); return 10 / (i - 1); }").unwrap();
+    golden(&module, "arrays");
+
+    // quoted names take anything hand-written IR can, and main has to be callable
+    let module = parse_module("%func a-b(%int x)\n%int t\n%mov t, x\n:x.y\n%branch_ifn t, :x.y\n%ret t\n%endfunc\n%func main(%int n)\n%int r\n%call r, a-b(n)\n%ret r\n%endfunc\n").unwrap();
+    golden(&module, "names");
+}
+
+#[test]
+fn test_examples() {
+    let flags = match llc_flags() {
+    Some(flags) => flags,
+    None => return,
+    };
+    check_examples(|module, name| build(module, &flags, name));
+}
+
+#[test]
+fn test_arithmetic_and_calls() {
+    let flags = match llc_flags() {
+    Some(flags) => flags,
+    None => return,
+    };
+    check_arithmetic_and_calls(|module, name| build(module, &flags, name));
+}
+
+#[test]
+fn test_runtime_errors() {
+    let flags = match llc_flags() {
+    Some(flags) => flags,
+    None => return,
+    };
+    check_runtime_errors(|module, name| build(module, &flags, name));
+}
@@ -266,3 +266,35 @@ fn test_invalid_if_while_statements() {
     let tokens = lex("while (y > 0) { y = y - 1; }; ").unwrap();
     assert!(parse_while_statement(&tokens, &mut 0).is_err());
 }
+
+#[test]
+fn test_syntax_tree() {
+    use rustcompiler::phases::ast::*;
+
+    let tokens = lex("x = a + b * 2;").unwrap();
+    let statement = parse_assignment_statement(&tokens, &mut 0).unwrap();
+    let product = Expression::Binary(BinaryOp::Mult, Box::new(Expression::Var(String::from("b"))), Box::new(Expression::Num(2)));
+    assert_eq!(statement, Statement::Assign(
+        Expression::Var(String::from("x")),
+        Expression::Binary(BinaryOp::Add, Box::new(Expression::Var(String::from("a"))), Box::new(product)),
+    ));
+
+    // parentheses only show in the shape of the tree
+    let tokens = lex("(a - 1) * long(c[i])").unwrap();
+    assert_eq!(parse_expression(&tokens, &mut 0).unwrap().to_string(), "(* (- a 1) (long (index c i)))");
+
+    let code = "func f(int[4] arr, long n) -> void {\n    int i;\n    while i < 4 {\n        if arr[i] == 0 { break; } else { }\n        print(\"at \", i);\n    }\n    return;\n}\n";
+    let program = parse_program(&lex(code).unwrap(), &mut 0).unwrap();
+    assert_eq!(program.functions[0].return_type, ReturnType::Void);
+    assert_eq!(program.to_string(), "\
+(func f ((int[4] arr) (long n)) void
+  (declare int i)
+  (while (< i 4)
+    (if (== (index arr i) 0)
+      (then
+        (break))
+      (else))
+    (print \"at \" i))
+  (return))
+");
+}
@@ -0,0 +1,71 @@
+use rustcompiler::interpreter::*;
+use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::semantic_parser::*;
+use rustcompiler::profiler::*;
+
+// main calls count(3), which counts down to 0 by calling itself.
+const COUNT: &str = "%func count(%int n)\n%int c\n%eq c, n, 0\n%branch_if c, :done\n%int m\n%sub m, n, 1\n%call m, count(m)\n:done\n%ret 0\n%endfunc\n%func main()\n%int r\n%call r, count(3)\n%ret r\n%endfunc\n";
+
+fn profile(program: &Program) -> (Result<i64, RuntimeError>, Profiler) {
+    let mut profiler = Profiler::new();
+    let result = program.run_with_hook(&mut Vm::with_io(std::io::empty(), std::io::sink()), &mut profiler);
+    (result, profiler)
+}
+
+#[test]
+fn test_counts() {
+    let program = parse_ir(COUNT).unwrap();
+    let (result, profiler) = profile(&program);
+    assert_eq!(result.unwrap(), 0);
+
+    // count(0) runs 5 instructions and the other three calls 8 each, main runs 3.
+    assert_eq!(profiler.total(), 32);
+    assert_eq!(profiler.function("count"), Some(FunctionProfile { calls: 4, exclusive: 29, inclusive: 29 }));
+    assert_eq!(profiler.function("main"), Some(FunctionProfile { calls: 1, exclusive: 3, inclusive: 32 }));
+    assert_eq!(profiler.function("missing"), None);
+
+    assert_eq!(profiler.kind("Call"), 4);
+    assert_eq!(profiler.kind("Label"), 4);
+    assert_eq!(profiler.kind("Sub"), 3);
+    assert_eq!(profiler.ir_line(6), 3);
+    assert_eq!(profiler.ir_line(9), 4);
+}
+
+#[test]
+fn test_folded_stacks() {
+    let program = parse_ir(COUNT).unwrap();
+    let (_, profiler) = profile(&program);
+    let mut folded: Vec<u8> = vec![];
+    profiler.write_folded(&mut folded).unwrap();
+    assert_eq!(String::from_utf8(folded).unwrap(), "main 3\nmain;count 8\nmain;count;count 8\nmain;count;count;count 8\nmain;count;count;count;count 5\n");
+}
+
+#[test]
+fn test_report() {
+    let (tokens, spans) = lex_with_spans("func main() {\n    int i;\n    while i < 3 {\n        i = i + 1;\n    }\n}\n").unwrap();
+    let program = Program::load(&parse_program_with_spans(&tokens, &spans, &mut 0).unwrap()).unwrap();
+    let (_, profiler) = profile(&program);
+
+    // the loop body runs three times
+    assert_eq!(profiler.source_line(4) % 3, 0);
+    assert!(profiler.source_line(4) > 0);
+
+    let mut report: Vec<u8> = vec![];
+    profiler.write_report(&mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.starts_with(&format!("Instructions run: {}\n", profiler.total())));
+    assert!(report.contains("\nmain                          1"));
+    assert!(report.contains("\nSource line "));
+    assert!(report.contains("\nIR line "));
+}
+
+#[test]
+fn test_unfinished_calls() {
+    // calls still running when the program fails count up to where it stopped
+    let program = parse_ir("%func f()\n%int a\n%div a, 1, 0\n%endfunc\n%func main()\n%int r\n%call r, f()\n%endfunc\n").unwrap();
+    let (result, profiler) = profile(&program);
+    assert!(result.is_err());
+    assert_eq!(profiler.total(), 4);
+    assert_eq!(profiler.function("main").unwrap().inclusive, 4);
+    assert_eq!(profiler.function("f").unwrap().exclusive, 2);
+}
@@ -0,0 +1,54 @@
+use rustcompiler::repl::*;
+
+// runs a whole session, giving back everything the prompt printed.
+fn session(input: &str) -> String {
+    let mut output: Vec<u8> = vec![];
+    Repl::new().run_prompt(&mut input.as_bytes(), &mut output);
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_state() {
+    let output = session("int x;\nx = 6 * 7;\nx + 1\nx = x - 50;\nx\nlong big;\nbig = 9223372036854775807L;\nbig\n");
+    assert_eq!(output, ">>> >>> >>> 43\n>>> >>> -8\n>>> >>> >>> 9223372036854775807\n>>> \n");
+}
+
+#[test]
+fn test_functions() {
+    let output = session("func sq(int n) -> int {\n    return n * n;\n}\nsq(9)\nfunc sq(int n) -> int { return n + n; }\nsq(9)\n:quit\nsq(1)\n");
+    // the definition goes on over lines until its braces match
+    assert_eq!(output, ">>> ... ... >>> 81\n>>> >>> 18\n>>> ");
+}
+
+#[test]
+fn test_arrays_and_blocks() {
+    let output = session("int[3] a;\nint i;\nwhile i < 3 {\n    a[i] = 0 - i;\n    i = i + 1;\n}\n:vars\n");
+    assert!(output.ends_with("a = [0, -1, -2]\ni = 3\n>>> \n"));
+}
+
+#[test]
+fn test_errors() {
+    let output = session("int x;\nx = 5;\ny\nx = x / 0;\nx\nint x;\nfunc main() {}\n");
+    assert!(output.contains("Variable y not declared"));
+    assert!(output.contains("divide by zero"));
+
+    // a statement that fails leaves the variables as they were
+    assert!(output.contains(">>> 5\n"));
+    assert!(output.contains("duplicate variable x"));
+    assert!(output.contains("give the function another name"));
+}
+
+#[test]
+fn test_input() {
+    // read takes the line after it, the same as the prompt
+    let output = session("int x;\nread(x);\n41\nx + 1\n");
+    assert!(output.contains("42\n"));
+}
+
+#[test]
+fn test_is_complete() {
+    assert!(is_complete("x = 1;"));
+    assert!(!is_complete("while x < 3 {"));
+    assert!(!is_complete("func f() {\n if x { "));
+    assert!(is_complete("func f() {\n if x { }\n}"));
+}
@@ -0,0 +1,223 @@
+mod common;
+
+// Include both lexer and semantic parser modules
+use common::compile;
+use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::semantic_parser::*;
+
+
+#[test]
+fn test_return_type_annotations() {
+    // Unannotated functions keep working as before
+    assert!(compile("func main() { int a; a = 1; }").is_ok());
+
+    // Explicit int and void return types
+    assert!(compile("func f() -> int { return 1; } func main() { int a; a = f(); }").is_ok());
+    assert!(compile("func f() -> void { return; } func main() { f(); }").is_ok());
+    assert!(compile("func f(int a) -> void { print(a); } func main() { f(1); }").is_ok());
+
+    // Missing or invalid return type after '->'
+    assert!(compile("func main() -> { }").is_err());
+    assert!(compile("func main() -> main { }").is_err());
+}
+
+#[test]
+fn test_void_functions() {
+    // Bare return emits a zero return
+    let code = compile("func f() -> void { return; } func main() { f(); }").unwrap().to_string();
+    assert!(code.contains("%ret 0\n"));
+
+    // Void functions cannot produce a value
+    assert!(compile("func f() -> void { return 1; } func main() { f(); }").is_err());
+
+    // Void functions cannot be used in expressions
+    assert!(compile("func f() -> void { } func main() { int a; a = f(); }").is_err());
+    assert!(compile("func f() -> void { } func main() { print(f() + 1); }").is_err());
+}
+
+#[test]
+fn test_int_functions_return_on_every_path() {
+    // Bare return is not allowed in an int function
+    assert!(compile("func f() -> int { return; } func main() { }").is_err());
+
+    // Missing return
+    assert!(compile("func f() -> int { int a; } func main() { }").is_err());
+
+    // Return only in one branch
+    assert!(compile("func f(int a) -> int { if a < 0 { return 0; } } func main() { }").is_err());
+
+    // Return inside a loop body does not count
+    assert!(compile("func f(int a) -> int { while a < 0 { return 0; } } func main() { }").is_err());
+
+    // Both branches return
+    assert!(compile("func f(int a) -> int { if a < 0 { return 0; } else { return 1; } } func main() { }").is_ok());
+
+    // Return after the loop
+    assert!(compile("func f(int a) -> int { while a < 0 { a = a + 1; } return a; } func main() { }").is_ok());
+}
+
+#[test]
+fn test_call_statements() {
+    // Any declared function may be called for its side effects
+    assert!(compile("func f(int a) { print(a); } func main() { f(1); }").is_ok());
+
+    // Calls to undeclared functions
+    assert!(compile("func main() { g(1); }").is_err());
+    assert!(compile("func main() { int a; a = g(1); }").is_err());
+}
+
+#[test]
+fn test_print_statements() {
+    // A single integer keeps the plain %out form
+    let code = compile("func main() { int a; print(a); print a + 1; }").unwrap().to_string();
+    assert!(code.contains("%out a\n"));
+    assert!(!code.contains("%out_nl"));
+
+    // Parenthesized expressions are still expressions, and only the temps
+    // they use are generated
+    let code = compile("func main() { int a; int b; print (a + b) * 2; }").unwrap().to_string();
+    assert!(code.ends_with("%int t0\n%add t0, a, b\n%int t1\n%mult t1, t0, 2\n%out t1\n%endfunc\n"), "{code}");
+
+    // Strings go into the constant section ahead of the functions
+    let code = compile("func main() { int x; print(\"sum = \", x); print(\"sum = \"); }").unwrap().to_string();
+    assert!(code.starts_with("%str $0, \"sum = \"\n%func main()"));
+    assert!(code.contains("%out_str $0\n%out_int x\n%out_nl\n"));
+    assert_eq!(code.matches("%str ").count(), 1);
+
+    // Escapes are written back out for the IR lexer
+    let code = compile("func main() { print(\"a\\tb\\\"\"); }").unwrap().to_string();
+    assert!(code.starts_with("%str $0, \"a\\tb\\\"\"\n"));
+
+    // Strings are only valid as print arguments
+    assert!(compile("func main() { int a; a = \"x\"; }").is_err());
+    assert!(compile("func main() { print(\"x\" + 1); }").is_err());
+    assert!(compile("func main() { print(\"x\" 1); }").is_err());
+}
+
+#[test]
+fn test_character_statements() {
+    let code = compile("func main() { int c; readc(c); printc(c + 1); printc 'a'; }").unwrap().to_string();
+    assert!(code.contains("%inputc c\n"));
+    assert!(code.contains("%outc 97\n"));
+
+    // Array elements are read through a temp
+    let code = compile("func main() { int[4] s; readc(s[2]); }").unwrap().to_string();
+    assert!(code.contains("%inputc "));
+    assert!(code.contains("%mov [s + 2], "));
+
+    // Destinations must be declared variables or array elements
+    assert!(compile("func main() { readc(c); }").is_err());
+    assert!(compile("func main() { int c; readc(c + 1); }").is_err());
+    assert!(compile("func main() { int c; readc(c[0]); }").is_err());
+    assert!(compile("func main() { int c; readc c }").is_err());
+}
+
+// the labels of a while loop's condition and of the code after it
+fn loop_labels(code: &str) -> (String, String) {
+    let start = code.lines().find(|l| l.starts_with(':')).unwrap().to_string();
+    let branch = code.lines().find(|l| l.starts_with("%branch_ifn")).unwrap();
+    let end = branch.rsplit(", ").next().unwrap().to_string();
+    (start, end)
+}
+
+#[test]
+fn test_while_jumps_back_to_condition() {
+    let code = compile("func main() { int i; while i < 3 { i = i + 1; } }").unwrap().to_string();
+    let (start, end) = loop_labels(&code);
+    assert!(code.contains(&format!("%jmp {start}\n{end}\n")), "{code}");
+}
+
+#[test]
+fn test_continue_jumps_to_condition() {
+    let code = compile("func main() { int i; while i < 3 { i = i + 1; continue; } }").unwrap().to_string();
+    let (start, end) = loop_labels(&code);
+    assert_eq!(code.matches(&format!("%jmp {start}\n")).count(), 2, "{code}");
+    assert!(!code.contains(&format!("%jmp {end}\n")), "{code}");
+
+    // break still leaves the loop
+    let code = compile("func main() { int i; while i < 3 { break; } }").unwrap().to_string();
+    let (_, end) = loop_labels(&code);
+    assert!(code.contains(&format!("%jmp {end}\n")), "{code}");
+}
+
+#[test]
+fn test_then_block_skips_else_block() {
+    let code = compile("func main() { int a; if a < 1 { a = 1; } else { a = 2; } }").unwrap().to_string();
+    let end = code.lines().rfind(|l| l.starts_with(':')).unwrap();
+    assert!(code.contains(&format!("%mov a, 1\n%jmp {end}\n")), "{code}");
+    assert!(code.ends_with(&format!("%mov a, 2\n{end}\n%endfunc\n")), "{code}");
+}
+
+#[test]
+fn test_comparison_operand_order() {
+    // Code for the right hand side of a comparison comes before the comparison
+    let code = compile("func main() { int a; if a != 0 - 1 { a = 1; } }").unwrap().to_string();
+    let comparison = code.lines().find(|l| l.starts_with("%neq")).unwrap();
+    let rhs = comparison.rsplit(", ").next().unwrap();
+    assert!(code.contains(&format!("%sub {rhs}, 0, 1\n{comparison}\n")), "{code}");
+    let sub = code.find("%sub").unwrap();
+    let neq = code.find("%neq").unwrap();
+    assert!(sub < neq);
+}
+
+#[test]
+fn test_long_declarations() {
+    let code = compile("func f(long n) -> long { return n; } func main() { long a; long[4] b; a = f(3000000000); b[0] = a; }").unwrap().to_string();
+    assert!(code.contains("%func f(%long n)\n"));
+    assert!(code.contains("%long a\n"));
+    assert!(code.contains("%long[] b, 4\n"));
+    assert!(code.contains("%mov a, "));
+
+    // Arithmetic on longs uses long temps
+    let code = compile("func main() { long a; a = 1L; a = a * 2 + a; }").unwrap().to_string();
+    assert!(!code.contains("%int"));
+
+    // Comparisons still produce an int
+    let code = compile("func main() { long a; if a > 5 { print(a); } }").unwrap().to_string();
+    assert!(code.contains("%int t"));
+}
+
+#[test]
+fn test_long_type_checking() {
+    // Unsuffixed int literals work with either type
+    assert!(compile("func main() { long a; a = 5; a = a + 1; }").is_ok());
+
+    // Mixing int and long needs a cast
+    assert!(compile("func main() { int a; long b; a = b; }").is_err());
+    assert!(compile("func main() { int a; long b; b = a; }").is_err());
+    assert!(compile("func main() { int a; long b; b = b + a; }").is_err());
+    assert!(compile("func main() { int a; long b; if a < b { } }").is_err());
+    assert!(compile("func main() { int a; a = 5L; }").is_err());
+    assert!(compile("func main() { int a; a = 3000000000; }").is_err());
+    assert!(compile("func main() { int[4] a; long i; a[i] = 1; }").is_err());
+
+    // Arguments and return values are checked too
+    assert!(compile("func f(long n) -> void { } func main() { int a; f(a); }").is_err());
+    assert!(compile("func f(int n) -> void { } func main() { f(1, 2); }").is_err());
+    assert!(compile("func f(long n) -> int { return n; } func main() { }").is_err());
+
+    // Casts convert in either direction
+    let code = compile("func main() { int a; long b; a = int(b); b = long(a) + b; }").unwrap().to_string();
+    assert!(code.contains("%int t"));
+    assert!(code.contains("%long t"));
+    assert!(compile("func main() { int a; a = int 5; }").is_err());
+}
+
+#[test]
+fn test_parsing_on_several_threads() {
+    // every parse has its own state, so programs can be parsed at the same time
+    let code = "func f(int n) -> int { if n < 2 { return n; } return f(n - 1) + 1; }\nfunc main() { int a; a = f(5); print(\"a = \", a); }";
+    let expected = parse_program(&lex(code).unwrap(), &mut 0).unwrap();
+    let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(move || {
+        let mut results = vec![];
+        for _ in 0..50 {
+            results.push(parse_program(&lex(code).unwrap(), &mut 0).unwrap());
+        }
+        results
+    })).collect();
+    for thread in threads {
+        for result in thread.join().unwrap() {
+            assert_eq!(result, expected);
+        }
+    }
+}
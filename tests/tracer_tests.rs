@@ -0,0 +1,63 @@
+use rustcompiler::interpreter::*;
+use rustcompiler::phases::lexer::*;
+use rustcompiler::phases::semantic_parser::*;
+use rustcompiler::tracer::*;
+
+const SQUARES: &str = "%func square(%int n)\n%int t\n%mult t, n, n\n%ret t\n%endfunc\n%func main()\n%int[] a, 2\n%int r\n%call r, square(3)\n%mov [a + 1], r\n%ret r\n%endfunc\n";
+
+// runs the program with a tracer, giving back its result and the trace.
+fn trace(program: &Program, format: TraceFormat, function: Option<&str>) -> (Result<i64, RuntimeError>, String) {
+    let mut log: Vec<u8> = vec![];
+    let result = {
+        let mut tracer = Tracer::new(format, &mut log);
+        tracer.function = function.map(String::from);
+        program.run_with_hook(&mut Vm::with_io(std::io::empty(), std::io::sink()), &mut tracer)
+    };
+    (result, String::from_utf8(log).unwrap())
+}
+
+#[test]
+fn test_human_trace() {
+    let program = parse_ir(SQUARES).unwrap();
+    let (result, log) = trace(&program, TraceFormat::Human, None);
+    assert_eq!(result.unwrap(), 9);
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines, vec![
+        "main (IR line 7): %int[] a, 2",
+        "main (IR line 8): %int r | r: 0 -> 0",
+        "main (IR line 9): %call r, square(3)",
+        "  square (IR line 2): %int t | t: 0 -> 0",
+        "  square (IR line 3): %mult t, n, n | n = 3 | t: 0 -> 9",
+        "  square (IR line 4): %ret t | t = 9 | returns to r: 0 -> 9",
+        "main (IR line 10): %mov [a + 1], r | r = 9 | a[1]: 0 -> 9",
+        "main (IR line 11): %ret r | r = 9",
+    ]);
+}
+
+#[test]
+fn test_json_trace() {
+    let program = parse_ir(SQUARES).unwrap();
+    let (_, log) = trace(&program, TraceFormat::Json, Some("square"));
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1], "{\"depth\":2,\"function\":\"square\",\"line\":3,\"span\":null,\"instruction\":\"%mult t, n, n\",\"reads\":{\"n\":3},\"dest\":\"t\",\"before\":0,\"after\":9}");
+    assert!(lines[2].ends_with("\"dest\":\"r\",\"before\":0,\"after\":9,\"returned\":true}"));
+}
+
+#[test]
+fn test_trace_errors() {
+    // the instruction that failed is still in the trace
+    let program = parse_ir("%func main()\n%int a\n%int[] b, 2\n%mov a, [b + 5]\n%ret a\n%endfunc\n").unwrap();
+    let (result, log) = trace(&program, TraceFormat::Human, None);
+    assert!(result.is_err());
+    assert_eq!(log.lines().last(), Some("main (IR line 4): %mov a, [b + 5] | a: 0 -> ?"));
+}
+
+#[test]
+fn test_trace_spans() {
+    let (tokens, spans) = lex_with_spans("func main() {\n    int x;\n    x = 2 * 3;\n}\n").unwrap();
+    let program = Program::load(&parse_program_with_spans(&tokens, &spans, &mut 0).unwrap()).unwrap();
+    let (_, log) = trace(&program, TraceFormat::Human, None);
+    assert!(log.contains(" at line 3, column 5: %mult "));
+    assert!(log.ends_with(": %endfunc\n"));
+}
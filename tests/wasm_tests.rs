@@ -0,0 +1,732 @@
+mod common;
+
+use common::*;
+use rustcompiler::backend::wasm;
+use rustcompiler::interpreter::*;
+
+use std::collections::HashMap;
+
+// there is no WebAssembly runtime to test with, so this checks the text the
+// way a runtime would before running it: the shape of the module, that every
+// name used is defined, and the types on the stack through every function.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Sexp {
+    Atom(String),
+    Str(Vec<u8>),
+    List(Vec<Sexp>),
+}
+
+fn parse_sexps(text: &str) -> Result<Vec<Sexp>, String> {
+    let bytes = text.as_bytes();
+    let mut stack: Vec<Vec<Sexp>> = vec![vec![]];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+        b' ' | b'\n' | b'\t' | b'\r' => i += 1,
+        b';' if bytes.get(i + 1) == Some(&b';') => {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        }
+        b'(' if bytes.get(i + 1) == Some(&b';') => {
+            match text[i..].find(";)") {
+            Some(end) => i += end + 2,
+            None => return Err(String::from("unterminated block comment")),
+            }
+        }
+        b'(' => {
+            stack.push(vec![]);
+            i += 1;
+        }
+        b')' => {
+            if stack.len() == 1 {
+                return Err(String::from("unbalanced ')'"));
+            }
+            let list = stack.pop().unwrap();
+            stack.last_mut().unwrap().push(Sexp::List(list));
+            i += 1;
+        }
+        b'"' => {
+            let mut string = vec![];
+            i += 1;
+            loop {
+                match bytes.get(i) {
+                None => return Err(String::from("unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    match bytes.get(i + 1) {
+                    Some(b'n') => string.push(b'\n'),
+                    Some(b't') => string.push(b'\t'),
+                    Some(b'\\') => string.push(b'\\'),
+                    Some(b'"') => string.push(b'"'),
+                    Some(b'\'') => string.push(b'\''),
+                    _ => {
+                        let hex = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                        match hex {
+                        Some(byte) => {
+                            string.push(byte);
+                            i += 1;
+                        }
+                        None => return Err(format!("bad escape at byte {i}")),
+                        }
+                    }
+                    }
+                    i += 2;
+                }
+                Some(byte) if *byte < 0x20 => return Err(String::from("control character in a string")),
+                Some(byte) => {
+                    string.push(*byte);
+                    i += 1;
+                }
+                }
+            }
+            i += 1;
+            stack.last_mut().unwrap().push(Sexp::Str(string));
+        }
+        _ => {
+            let start = i;
+            while i < bytes.len() && !b" \n\t\r()\";".contains(&bytes[i]) {
+                i += 1;
+            }
+            stack.last_mut().unwrap().push(Sexp::Atom(String::from(&text[start..i])));
+        }
+        }
+    }
+    if stack.len() != 1 {
+        return Err(String::from("unbalanced '('"));
+    }
+    Ok(stack.pop().unwrap())
+}
+
+fn atom(sexp: &Sexp) -> Option<&str> {
+    match sexp {
+    Sexp::Atom(atom) => Some(atom),
+    _ => None,
+    }
+}
+
+// a list starting with the keyword, e.g. (param $a i64).
+fn keyword<'s>(sexp: &'s Sexp, word: &str) -> Option<&'s [Sexp]> {
+    match sexp {
+    Sexp::List(items) if items.first().and_then(atom) == Some(word) => Some(&items[1..]),
+    _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValType {
+    I32,
+    I64,
+}
+
+fn val_type(sexp: &Sexp) -> Result<ValType, String> {
+    match atom(sexp) {
+    Some("i32") => Ok(ValType::I32),
+    Some("i64") => Ok(ValType::I64),
+    _ => Err(format!("unknown type {sexp:?}")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+    params: Vec<ValType>,
+    results: Vec<ValType>,
+}
+
+#[derive(Debug)]
+struct Func<'s> {
+    name: Option<String>,
+    signature: Signature,
+    // the names of the parameters and locals, with their types.
+    locals: Vec<(Option<String>, ValType)>,
+    body: &'s [Sexp],
+    export: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Wasm<'s> {
+    // module and name of each import, with the function it becomes.
+    imports: Vec<(String, String)>,
+    functions: Vec<Func<'s>>,
+    memory_pages: Option<u64>,
+    memory_export: Option<String>,
+    globals: HashMap<String, (ValType, bool)>,
+    data: Vec<(u64, Vec<u8>)>,
+}
+
+// the (param ...) (result ...) (local ...) at the start of a func, and what follows.
+fn func<'s>(fields: &'s [Sexp]) -> Result<Func<'s>, String> {
+    let mut f = Func { name: None, signature: Signature { params: vec![], results: vec![] }, locals: vec![], body: &[], export: None };
+    let mut i = 0;
+    if let Some(name) = fields.first().and_then(atom).filter(|name| name.starts_with('$')) {
+        f.name = Some(String::from(name));
+        i += 1;
+    }
+    while let Some(field) = fields.get(i) {
+        if let Some(export) = keyword(field, "export") {
+            match export {
+            [Sexp::Str(name)] => f.export = Some(String::from_utf8(name.clone()).unwrap()),
+            _ => return Err(String::from("bad inline export")),
+            }
+        } else if let Some(param) = keyword(field, "param").or_else(|| keyword(field, "local")) {
+            let is_param = keyword(field, "param").is_some();
+            if is_param && f.locals.len() != f.signature.params.len() {
+                return Err(String::from("param after a local"));
+            }
+            // either one named or any number of unnamed.
+            let named = param.first().and_then(atom).filter(|name| name.starts_with('$'));
+            let types: Vec<&Sexp> = if named.is_some() { param[1..].iter().collect() } else { param.iter().collect() };
+            if named.is_some() && types.len() != 1 {
+                return Err(format!("a named {field:?} takes one type"));
+            }
+            for typ in types {
+                let typ = val_type(typ)?;
+                if is_param {
+                    f.signature.params.push(typ);
+                }
+                f.locals.push((named.map(String::from), typ));
+            }
+        } else if let Some(result) = keyword(field, "result") {
+            if f.locals.len() != f.signature.params.len() {
+                return Err(String::from("result after a local"));
+            }
+            for typ in result {
+                f.signature.results.push(val_type(typ)?);
+            }
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    f.body = &fields[i..];
+    Ok(f)
+}
+
+fn module(sexps: &[Sexp]) -> Result<Wasm<'_>, String> {
+    let fields = match sexps {
+    [module] => keyword(module, "module").ok_or("expected (module ...)")?,
+    _ => return Err(String::from("expected a single module")),
+    };
+    let mut wasm = Wasm::default();
+    let mut definitions = false;
+    for field in fields {
+        let items = match field {
+        Sexp::List(items) => items,
+        _ => return Err(format!("unexpected {field:?} in the module")),
+        };
+        match items.first().and_then(atom) {
+        Some("import") => {
+            if definitions {
+                return Err(String::from("imports have to come before everything they could be confused with"));
+            }
+            match &items[1..] {
+            [Sexp::Str(module), Sexp::Str(name), description] => {
+                let f = func(keyword(description, "func").ok_or("only functions are imported")?)?;
+                if !f.body.is_empty() || !f.locals.iter().all(|(name, _)| name.is_none()) {
+                    return Err(String::from("an imported function has no body or locals"));
+                }
+                wasm.imports.push((String::from_utf8(module.clone()).unwrap(), String::from_utf8(name.clone()).unwrap()));
+                wasm.functions.push(f);
+            }
+            _ => return Err(String::from("bad import")),
+            }
+        }
+        Some("memory") => {
+            definitions = true;
+            if wasm.memory_pages.is_some() {
+                return Err(String::from("more than one memory"));
+            }
+            for item in &items[1..] {
+                if let Some([Sexp::Str(name)]) = keyword(item, "export") {
+                    wasm.memory_export = Some(String::from_utf8(name.clone()).unwrap());
+                } else {
+                    wasm.memory_pages = Some(atom(item).and_then(|pages| pages.parse().ok()).ok_or("bad memory size")?);
+                }
+            }
+        }
+        Some("global") => {
+            definitions = true;
+            match &items[1..] {
+            [Sexp::Atom(name), typ, init] => {
+                let (typ, mutable) = match keyword(typ, "mut") {
+                Some([typ]) => (val_type(typ)?, true),
+                _ => (val_type(typ)?, false),
+                };
+                let constant = if typ == ValType::I32 { "i32.const" } else { "i64.const" };
+                if !matches!(keyword(init, constant), Some([Sexp::Atom(_)])) {
+                    return Err(format!("global {name} needs a constant of its type"));
+                }
+                wasm.globals.insert(name.clone(), (typ, mutable));
+            }
+            _ => return Err(String::from("bad global")),
+            }
+        }
+        Some("data") => {
+            definitions = true;
+            match &items[1..] {
+            [offset, Sexp::Str(bytes)] => {
+                let offset = match keyword(offset, "i32.const") {
+                Some([Sexp::Atom(offset)]) => offset.parse().map_err(|_| "bad data offset")?,
+                _ => return Err(String::from("data needs an i32.const offset")),
+                };
+                wasm.data.push((offset, bytes.clone()));
+            }
+            _ => return Err(String::from("bad data")),
+            }
+        }
+        Some("func") => {
+            definitions = true;
+            wasm.functions.push(func(&items[1..])?);
+        }
+        _ => return Err(format!("unexpected {field:?} in the module")),
+        }
+    }
+    Ok(wasm)
+}
+
+// the types an instruction takes and gives, for the ones with nothing special about them.
+fn simple(instruction: &str) -> Option<(Vec<ValType>, Vec<ValType>)> {
+    use ValType::*;
+    let (params, results) = match instruction {
+    "i64.add" | "i64.sub" | "i64.mul" | "i64.div_s" | "i64.div_u" | "i64.rem_s" | "i64.rem_u" | "i64.and" | "i64.or" | "i64.shl" | "i64.shr_u" | "i64.shr_s" => (vec![I64, I64], vec![I64]),
+    "i64.eq" | "i64.ne" | "i64.lt_s" | "i64.lt_u" | "i64.le_s" | "i64.le_u" | "i64.gt_s" | "i64.gt_u" | "i64.ge_s" | "i64.ge_u" => (vec![I64, I64], vec![I32]),
+    "i32.add" | "i32.sub" | "i32.mul" | "i32.and" | "i32.or" | "i32.shl" | "i32.shr_u" => (vec![I32, I32], vec![I32]),
+    "i32.eq" | "i32.ne" | "i32.lt_s" | "i32.lt_u" | "i32.le_s" | "i32.le_u" | "i32.gt_s" | "i32.gt_u" | "i32.ge_s" | "i32.ge_u" => (vec![I32, I32], vec![I32]),
+    "i64.eqz" => (vec![I64], vec![I32]),
+    "i32.eqz" => (vec![I32], vec![I32]),
+    "i32.wrap_i64" => (vec![I64], vec![I32]),
+    "i64.extend_i32_s" | "i64.extend_i32_u" => (vec![I32], vec![I64]),
+    "i64.load" => (vec![I32], vec![I64]),
+    "i32.load8_u" => (vec![I32], vec![I32]),
+    "i64.store" | "i64.store8" => (vec![I32, I64], vec![]),
+    "i32.store8" => (vec![I32, I32], vec![]),
+    "memory.size" => (vec![], vec![I32]),
+    "memory.grow" => (vec![I32], vec![I32]),
+    "memory.fill" => (vec![I32, I32, I32], vec![]),
+    "nop" => (vec![], vec![]),
+    _ => return None,
+    };
+    Some((params, results))
+}
+
+#[derive(Debug, PartialEq)]
+enum FrameKind {
+    Function,
+    Block,
+    Loop,
+    If,
+    Else,
+}
+
+struct Frame {
+    kind: FrameKind,
+    label: Option<String>,
+    // the types at the end of the frame, which a br to a block or the function gives.
+    results: Vec<ValType>,
+    height: usize,
+    unreachable: bool,
+}
+
+struct Checker<'w> {
+    wasm: &'w Wasm<'w>,
+    locals: &'w [(Option<String>, ValType)],
+    // None for a value of any type, after an unconditional jump.
+    stack: Vec<Option<ValType>>,
+    frames: Vec<Frame>,
+}
+
+impl Checker<'_> {
+    fn push(&mut self, typ: ValType) {
+        self.stack.push(Some(typ));
+    }
+
+    fn pop(&mut self, expected: Option<ValType>) -> Result<Option<ValType>, String> {
+        let frame = self.frames.last().unwrap();
+        if self.stack.len() == frame.height {
+            if frame.unreachable {
+                return Ok(expected);
+            }
+            return Err(format!("expected {expected:?} on the stack, but it is empty"));
+        }
+        let actual = self.stack.pop().unwrap();
+        match (actual, expected) {
+        (Some(actual), Some(expected)) if actual != expected => Err(format!("expected {expected:?} on the stack, found {actual:?}")),
+        (Some(actual), _) => Ok(Some(actual)),
+        (None, _) => Ok(expected),
+        }
+    }
+
+    fn pop_all(&mut self, types: &[ValType]) -> Result<(), String> {
+        for typ in types.iter().rev() {
+            self.pop(Some(*typ))?;
+        }
+        Ok(())
+    }
+
+    fn unreachable(&mut self) {
+        let frame = self.frames.last_mut().unwrap();
+        self.stack.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    // what a br to the label has to leave on the stack: nothing for a loop, since
+    // none of them take parameters, and the results of anything else.
+    fn label(&self, label: &str) -> Result<Vec<ValType>, String> {
+        let frame = match label.parse::<usize>() {
+        Ok(depth) => self.frames.iter().rev().nth(depth),
+        Err(_) => self.frames.iter().rev().find(|frame| frame.label.as_deref() == Some(label)),
+        };
+        match frame {
+        Some(frame) if frame.kind == FrameKind::Loop => Ok(vec![]),
+        Some(frame) => Ok(frame.results.clone()),
+        None => Err(format!("no enclosing block, loop or if called {label}")),
+        }
+    }
+
+    fn local(&self, name: &str) -> Result<ValType, String> {
+        let local = match name.parse::<usize>() {
+        Ok(index) => self.locals.get(index),
+        Err(_) => self.locals.iter().find(|(local, _)| local.as_deref() == Some(name)),
+        };
+        local.map(|(_, typ)| *typ).ok_or(format!("no local called {name}"))
+    }
+
+    fn end(&mut self) -> Result<Frame, String> {
+        let results = self.frames.last().unwrap().results.clone();
+        self.pop_all(&results)?;
+        let frame = self.frames.pop().unwrap();
+        if self.stack.len() != frame.height {
+            return Err(String::from("values left on the stack at the end of a block"));
+        }
+        for typ in &frame.results {
+            self.push(*typ);
+        }
+        Ok(frame)
+    }
+
+    fn body(&mut self, body: &[Sexp]) -> Result<(), String> {
+        let mut i = 0;
+        // the next atom, for instructions that take one.
+        let immediate = |i: &mut usize| -> Result<String, String> {
+            *i += 1;
+            body.get(*i - 1).and_then(atom).map(String::from).ok_or(String::from("missing immediate"))
+        };
+        while i < body.len() {
+            let instruction = match &body[i] {
+            Sexp::Atom(instruction) => instruction.clone(),
+            other => return Err(format!("folded instructions are not generated, found {other:?}")),
+            };
+            i += 1;
+            if let Some((params, results)) = simple(&instruction) {
+                self.pop_all(&params)?;
+                for typ in results {
+                    self.push(typ);
+                }
+                continue;
+            }
+            match instruction.as_str() {
+            "i32.const" | "i64.const" => {
+                let value = immediate(&mut i)?;
+                let fits = if instruction == "i32.const" {
+                    value.parse::<i32>().is_ok() || value.parse::<u32>().is_ok()
+                } else {
+                    value.parse::<i64>().is_ok() || value.parse::<u64>().is_ok()
+                };
+                if !fits {
+                    return Err(format!("{value} is not a valid {instruction}"));
+                }
+                self.push(if instruction == "i32.const" { ValType::I32 } else { ValType::I64 });
+            }
+            "local.get" => {
+                let typ = self.local(&immediate(&mut i)?)?;
+                self.push(typ);
+            }
+            "local.set" | "local.tee" => {
+                let typ = self.local(&immediate(&mut i)?)?;
+                self.pop(Some(typ))?;
+                if instruction == "local.tee" {
+                    self.push(typ);
+                }
+            }
+            "global.get" | "global.set" => {
+                let name = immediate(&mut i)?;
+                let (typ, mutable) = *self.wasm.globals.get(&name).ok_or(format!("no global called {name}"))?;
+                if instruction == "global.get" {
+                    self.push(typ);
+                } else if !mutable {
+                    return Err(format!("global {name} is not mutable"));
+                } else {
+                    self.pop(Some(typ))?;
+                }
+            }
+            "call" => {
+                let name = immediate(&mut i)?;
+                let function = self.wasm.functions.iter().find(|f| f.name.as_deref() == Some(name.as_str())).ok_or(format!("no function called {name}"))?;
+                let signature = function.signature.clone();
+                self.pop_all(&signature.params)?;
+                for typ in signature.results {
+                    self.push(typ);
+                }
+            }
+            "drop" => {
+                self.pop(None)?;
+            }
+            "select" => {
+                self.pop(Some(ValType::I32))?;
+                let second = self.pop(None)?;
+                let first = self.pop(second)?;
+                if let Some(typ) = first.or(second) {
+                    self.push(typ);
+                } else {
+                    self.stack.push(None);
+                }
+            }
+            "block" | "loop" | "if" => {
+                if instruction == "if" {
+                    self.pop(Some(ValType::I32))?;
+                }
+                let label = match body.get(i).and_then(atom) {
+                Some(label) if label.starts_with('$') => {
+                    i += 1;
+                    Some(String::from(label))
+                }
+                _ => None,
+                };
+                let kind = match instruction.as_str() {
+                "block" => FrameKind::Block,
+                "loop" => FrameKind::Loop,
+                _ => FrameKind::If,
+                };
+                self.frames.push(Frame { kind, label, results: vec![], height: self.stack.len(), unreachable: false });
+            }
+            "else" => {
+                if self.frames.last().unwrap().kind != FrameKind::If {
+                    return Err(String::from("else without an if"));
+                }
+                let frame = self.end()?;
+                self.frames.push(Frame { kind: FrameKind::Else, ..frame });
+            }
+            "end" => {
+                if self.frames.len() == 1 {
+                    return Err(String::from("end without a block"));
+                }
+                self.end()?;
+            }
+            "br" => {
+                let types = self.label(&immediate(&mut i)?)?;
+                self.pop_all(&types)?;
+                self.unreachable();
+            }
+            "br_if" => {
+                let types = self.label(&immediate(&mut i)?)?;
+                self.pop(Some(ValType::I32))?;
+                self.pop_all(&types)?;
+                for typ in types {
+                    self.push(typ);
+                }
+            }
+            "br_table" => {
+                let mut labels = vec![];
+                while let Some(label) = body.get(i).and_then(atom).filter(|label| label.starts_with('$') || label.parse::<usize>().is_ok()) {
+                    labels.push(self.label(label)?);
+                    i += 1;
+                }
+                if labels.is_empty() || !labels.iter().all(|types| *types == labels[0]) {
+                    return Err(String::from("br_table needs labels of the same type"));
+                }
+                self.pop(Some(ValType::I32))?;
+                self.pop_all(&labels[0])?;
+                self.unreachable();
+            }
+            "return" => {
+                let results = self.frames[0].results.clone();
+                self.pop_all(&results)?;
+                self.unreachable();
+            }
+            "unreachable" => self.unreachable(),
+            _ => return Err(format!("unknown instruction {instruction}")),
+            }
+        }
+        if self.frames.len() != 1 {
+            return Err(String::from("a block is missing its end"));
+        }
+        self.end()?;
+        Ok(())
+    }
+}
+
+// checks the module the way a WebAssembly runtime would before running it.
+fn validate(text: &str) -> Result<(), String> {
+    let sexps = parse_sexps(text)?;
+    let wasm = module(&sexps)?;
+
+    let mut names: Vec<&str> = vec![];
+    for function in &wasm.functions {
+        if let Some(name) = &function.name {
+            if names.contains(&name.as_str()) {
+                return Err(format!("function {name} defined twice"));
+            }
+            names.push(name);
+        }
+    }
+    for function in &wasm.functions[wasm.imports.len()..] {
+        let mut checker = Checker { wasm: &wasm, locals: &function.locals, stack: vec![], frames: vec![] };
+        checker.frames.push(Frame { kind: FrameKind::Function, label: None, results: function.signature.results.clone(), height: 0, unreachable: false });
+        checker.body(function.body).map_err(|e| format!("in function {:?}: {e}", function.name))?;
+    }
+
+    // what the host relies on.
+    let import = |module: &str, name: &str, params: Vec<ValType>, results: Vec<ValType>| -> Result<(), String> {
+        let index = wasm.imports.iter().position(|import| *import == (String::from(module), String::from(name))).ok_or(format!("{module}.{name} is not imported"))?;
+        if wasm.functions[index].signature != (Signature { params, results }) {
+            return Err(format!("{module}.{name} has the wrong type"));
+        }
+        Ok(())
+    };
+    import("tt", "print", vec![ValType::I32, ValType::I32], vec![])?;
+    import("tt", "read", vec![], vec![ValType::I32])?;
+    import("tt", "error", vec![ValType::I32, ValType::I32], vec![])?;
+    if wasm.imports.len() != 3 {
+        return Err(String::from("the host only provides print, read and error"));
+    }
+    let main = wasm.functions.iter().find(|f| f.export.as_deref() == Some("main")).ok_or("main is not exported")?;
+    if main.signature != (Signature { params: vec![], results: vec![ValType::I32] }) {
+        return Err(String::from("main has to take nothing and give an i32"));
+    }
+    if wasm.memory_export.as_deref() != Some("memory") {
+        return Err(String::from("the memory is not exported"));
+    }
+
+    let pages = wasm.memory_pages.ok_or("there is no memory")?;
+    let mut data = wasm.data.clone();
+    data.sort();
+    let mut end = 0;
+    for (offset, bytes) in &data {
+        if *offset < end {
+            return Err(format!("data at {offset} overlaps the data before it"));
+        }
+        end = offset + bytes.len() as u64;
+    }
+    if end > pages * 65536 {
+        return Err(String::from("the data does not fit in the memory"));
+    }
+    Ok(())
+}
+
+// the bytes of memory the data segments start out with.
+fn memory(text: &str) -> Vec<u8> {
+    let sexps = parse_sexps(text).unwrap();
+    let wasm = module(&sexps).unwrap();
+    let mut memory = vec![0; (wasm.memory_pages.unwrap() * 65536) as usize];
+    for (offset, bytes) in wasm.data {
+        memory[offset as usize..offset as usize + bytes.len()].copy_from_slice(&bytes);
+    }
+    memory
+}
+
+#[test]
+fn test_examples() {
+    let mut checked = 0;
+    for (name, module) in examples() {
+        let text = wasm::generate(&module);
+        if let Err(e) = validate(&text) {
+            panic!("{name} does not validate: {e}\n{text}");
+        }
+        // the frontend only ever makes loops entered at the top
+        assert!(!text.contains("$dispatch"), "{name}");
+        // every string constant is in memory
+        let memory = memory(&text);
+        for (_, string) in &module.strings {
+            assert!(memory.windows(string.len()).any(|window| window == string.as_bytes()), "{string:?} from {name}");
+        }
+        checked += 1;
+    }
+    assert!(checked >= 5);
+}
+
+#[test]
+fn test_control_flow() {
+    let module = compile("func main() -> int {
+    int i;
+    int total;
+    int[10] a;
+    while i < 10 {
+        i = i + 1;
+        if i % 3 == 0 {
+            continue;
+        }
+        if i == 8 {
+            break;
+        }
+        a[i] = i;
+        total = total + a[i] / 2;
+    }
+    print(\"total \", total);
+    return total;
+}").unwrap();
+    let text = wasm::generate(&module);
+    validate(&text).unwrap();
+    // the while loop comes back as a loop, with blocks to break out to
+    assert_eq!(text.matches("loop $loop").count(), 1);
+    assert!(text.contains("block $"));
+    assert!(!text.contains("$dispatch"));
+
+    // jumping into the middle of a loop cannot be written with loops and
+    // blocks, so every block goes in a dispatch loop instead
+    let module = parse_module("%func main()
+%int i
+%int c
+%int t
+%input c
+%branch_if c, :b
+:a
+%add i, i, 1
+%lt t, i, 5
+%branch_ifn t, :done
+:b
+%add i, i, 2
+%jmp :a
+:done
+%ret i
+%endfunc
+").unwrap();
+    let text = wasm::generate(&module);
+    validate(&text).unwrap();
+    assert!(text.contains("loop $dispatch"));
+    assert!(text.contains("br_table"));
+
+    // names from hand-written IR that WAT would not take, a function with
+    // nothing in it, and no main at all
+    let module = parse_module("%func a-b(%int x, %long y)\n%int t\n%int[] a.b, 2\n%mov t, x\n:x.y\n%branch_ifn t, :x.y\n%ret t\n%endfunc\n%func empty()\n%endfunc\n").unwrap();
+    let text = wasm::generate(&module);
+    validate(&text).unwrap();
+    assert!(text.contains("(func $f_a.2db (param $v_x i64) (param $v_y i64) (result i64)"));
+    assert!(memory(&text).windows(9).any(|window| window == b"No main f"));
+}
+
+#[test]
+fn test_validator() {
+    let module = compile("func main() { int[3] a; int i; read(i); a[i] = 4; print(a[i]); return 10 / (i - 1); }").unwrap();
+    let text = wasm::generate(&module);
+    validate(&text).unwrap();
+
+    // the checks catch broken modules, so passing them means something
+    let broken = [
+        ("br_if $digit", "br_if $nowhere"),
+        ("local.get $v_i", "local.get $v_j"),
+        ("call $tt_div", "call $tt_missing"),
+        ("i64.extend_i32_u", "i32.wrap_i64"),
+        ("(memory (export \"memory\")", "(memory (export \"mem\")"),
+        ("(import \"tt\" \"read\"", "(import \"tt\" \"readline\""),
+        ("\n  )\n", "\n  \n"),
+        ("    end\n    unreachable\n", "    end\n"),
+        ("call $tt_out_int\n", "call $tt_out_int\n    i64.const 1\n"),
+        ("i32.wrap_i64\n    i32.const 255", "i32.const 255"),
+    ];
+    for (from, to) in broken {
+        assert!(text.contains(from), "{from}");
+        let broken = text.replacen(from, to, 1);
+        assert!(validate(&broken).is_err(), "{from} -> {to}");
+    }
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::*;
+use rustcompiler::backend::x86;
+use rustcompiler::phases::ir::Module;
+
+use std::path::PathBuf;
+
+// assembles and links the module into an executable.
+fn build(module: &Module, name: &str) -> PathBuf {
+    let executable = executable("x86", name);
+    x86::link(&x86::generate(module), executable.to_str().unwrap()).unwrap();
+    executable
+}
+
+#[test]
+fn test_examples() {
+    if !have("cc") {
+        return;
+    }
+    check_examples(build);
+}
+
+#[test]
+fn test_arithmetic_and_calls() {
+    if !have("cc") {
+        return;
+    }
+    check_arithmetic_and_calls(build);
+}
+
+#[test]
+fn test_runtime_errors() {
+    if !have("cc") {
+        return;
+    }
+    check_runtime_errors(build);
+}